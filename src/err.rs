@@ -18,6 +18,18 @@ pub enum AppError {
     #[error("The parameter '{0}' is required, but has not been supplied")]
     MissingProgramParameter(String),
 
+    #[error("Error in mdr stage selection: {0:?} {1:?}")]
+    StageSelectionError(String, String),
+
+    #[error("Error in mdr checkpoint: {0:?} {1:?}")]
+    CheckpointError(String, String),
+
+    #[error("Error in AACT restore: {0:?} {1:?}")]
+    RestoreError(String, String),
+
+    #[error("The ctgov schema does not match the expected manifest: {0:?}")]
+    CtgovSchemaError(Vec<String>),
+
     #[error("couldn't read file {1:?}")]
     IoReadErrorWithPath(#[source] std::io::Error, std::path::PathBuf,),
 
@@ -66,6 +78,13 @@ pub fn report_error(e: AppError) -> () {
                   "A required parameter is neither in the config file nor the command line arguments".to_string(), 
                   format!("Parameter is: {}", p), "MISSING PARAMETER"),
 
+        AppError::StageSelectionError(p, d) => print_error (p, d, "STAGE SELECTION ERROR"),
+        AppError::CheckpointError(p, d) => print_error (p, d, "CHECKPOINT ERROR"),
+        AppError::RestoreError(p, d) => print_error (p, d, "AACT RESTORE ERROR"),
+        AppError::CtgovSchemaError(v) => print_error (
+                  format!("{} problem(s) found when checking the ctgov schema against the manifest (src/mdr/ctgov_manifest.csv):", v.len()),
+                  v.join("\n"), "CTGOV SCHEMA ERROR"),
+
         AppError::LogSetupError(p, d) => print_error (p, d, "LOG SETUP ERROR"),
 
         AppError::IoReadErrorWithPath(e, p) => print_error (e.to_string(), 
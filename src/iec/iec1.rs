@@ -1,329 +1,193 @@
+use super::iec_splitter::{split_criteria, IecType};
+use super::iec_utils::execute_and_report;
+use crate::mdr::dry_run;
+use crate::mdr::executor::SqlExecutor;
+use crate::AppError;
+use log::info;
 
-
-/*
-
-
-DROP TABLE IF EXISTS ad.iec_base;
-create table ad.iec_base(
-    id                     INT             PRIMARY KEY GENERATED ALWAYS AS IDENTITY (start with 1000001 increment by 1)
-  , sd_sid                 VARCHAR         NOT null
-  , criteria			   VARCHAR         NULL
-  , elig_crit			   VARCHAR         NULL
-  , inc_crit               VARCHAR         NULL
-  , exc_crit               VARCHAR         null
-  , has_incl			   bool            NOT null default false
-  , has_excl			   bool            NOT null default false
-  , examined               bool            NOT null default false
-
-);
-CREATE INDEX iec_base_sid ON ad.iec_base(sd_sid);
-
-
--- try and differentiate IEC
-
-insert into ad.iec_base(sd_sid, criteria)
-select nct_id, criteria
-from ctgov.eligibilities;
-
--- tidy up the beginning of the criteria statements
-
-update ad.iec_base
-set criteria = trim(LEADING '*' from criteria)
-where criteria like '*%'
-
-update ad.iec_base
-set criteria = trim(criteria)
-where criteria like ' %'
-
--- identify the records with no criteria
-
-update ad.iec_base
-set examined = true
-where criteria is null
-or criteria ilike 'No eligibility criteria%';
-
-update ad.iec_base
-set examined = true
-where criteria ilike 'data ana%'
-or criteria ilike 'please contact site%';
-
-update ad.iec_base
-set examined = true
-where criteria ilike 'no%' and
-(criteria ilike 'none %'
-or criteria like 'No patient will be included%'
-or criteria like 'No in- or exclusion criteria%'
-or criteria = 'Non applicable');
-
-
-update ad.iec_base
-set examined = true
-where criteria ilike 'no %' and
-(criteria = 'No formal inclusion or exclusion criteria'
-or criteria like 'No inclusion or exclusion criteria%'
-or criteria = 'No particular inclusion or exclusion criteria'
-or criteria ilike 'No patients enrolled%'
-or criteria = 'No inclusion or exclusion criteria');
-
-
--- flag 'inclusion' and  'exclusion' in the criteria text
-
-update ad.iec_base
-set has_incl = true
-where criteria ilike '%inclusion%'
-and examined = false;
-
-update ad.iec_base
-set has_excl = true
-where criteria ilike '%exclusion%'
-and examined = false;
-
--- records with no explicit inclusion or exclusion criteria
-
-update ad.iec_base
-set examined = true,
-elig_crit = criteria
-where has_incl = false
-and has_excl = false
-and examined = false
-
--- records with no explicit exclusion criteria
-
-update ad.iec_base
-set examined = true,
-inc_crit = criteria
-where has_incl = true
-and has_excl = false
-and examined = false
-
--- records with no explicit inclusion criteria
-
-update ad.iec_base
-set examined = true,
-exc_crit = criteria
-where has_incl = false
-and has_excl = true
-and examined = false
-
--- records with both explicit inclusion and exclusion criteria
-
-update ad.iec_base
-set examined = true,
-inc_crit = (REGEXP_SPLIT_TO_ARRAY(criteria, 'exclusion', 'i'))[1],
-exc_crit = (REGEXP_SPLIT_TO_ARRAY(criteria, 'exclusion', 'i'))[2]
-where has_incl = true
-and has_excl = true
-and examined = false
-
--- takes about 30 seconds
-
--- process inclusion criteria 1
-
-update ad.iec_base
-set inc_crit = replace(inc_crit, 'Key Inclusion Criteria', 'Inclusion Criteria')
-where inc_crit like 'Key Inclusion Criteria%';
-
-update ad.iec_base
-set inc_crit = replace(inc_crit, 'INCLUSION CRITERIA', 'Inclusion Criteria')
-where inc_crit like 'INCLUSION CRITERIA%';
-
-update ad.iec_base
-set inc_crit = trim(leading 'Inclusion Criteria' from inc_crit)
-where inc_crit like 'Inclusion Criteria%';
-
-update ad.iec_base
-set inc_crit = trim(leading ':' from inc_crit)
-where inc_crit like ':%';
-
-update ad.iec_base
-set inc_crit = trim(inc_crit);
-
-update ad.iec_base
-set inc_crit =  trim(leading '\$£' from regexp_replace(inc_crit, '^\n\n', '$£'));
-
-update ad.iec_base
-set inc_crit =  trim(trailing '\$£' from regexp_replace(inc_crit, '\n\n$', '$£'));
-
-update ad.iec_base
-set inc_crit =  trim(inc_crit);
-
-update ad.iec_base
-set inc_crit = null,
-has_incl = false
-where length(inc_crit) < 3;
-
--- process exclusion criteria 1
-
-update ad.iec_base
-set exc_crit = trim(exc_crit)
-where exc_crit like ' %';
-
-update ad.iec_base
-set exc_crit = replace(exc_crit, 'CRITERIA', 'Criteria')
-where exc_crit like 'CRITERIA%';
-
-update ad.iec_base
-set exc_crit = trim(leading 'Criteria' from exc_crit)
-where exc_crit like 'Criteria%';
-
-update ad.iec_base
-set exc_crit = trim(leading ':' from exc_crit)
-where exc_crit like ':%';
-
-update ad.iec_base
-set exc_crit =  trim(exc_crit)
-where exc_crit like ' %';
-
-update ad.iec_base
-set exc_crit =  trim(leading '\$£' from regexp_replace(exc_crit, '^\n\n', '$£'));
-
-update ad.iec_base
-set exc_crit =  trim(trailing '\$£' from regexp_replace(exc_crit, '\n\n$', '$£'));
-
-update ad.iec_base
-set exc_crit =  trim(exc_crit)
-where exc_crit like ' %';
-
-update ad.iec_base
-set exc_crit = null,
-has_excl = false
-where length(exc_crit) < 3;
-
--- iec base table -- needs a lot more work, 
--- but very roughly can be split betwen those 
--- with internal carriage returns and those without
--- but some of the latter also a listing
-
-SELECT pg_size_pretty(pg_total_relation_size('ad.iec_base'));
-VACUUM (FULL, VERBOSE, ANALYZE) ad.iec_base;
-SELECT pg_size_pretty(pg_total_relation_size('ad.iec_base'));
-
-select count(elig_crit)
-from ad.iec_base
-where elig_crit is not null
-
-select count(inc_crit)
-from ad.iec_base
-where inc_crit is not null
-
-select count(exc_crit)
-from ad.iec_base
-where exc_crit is not null
-
-
-update ad.studies s
-set iec_flag = 1
-from ad.iec_base e
-where s.sd_sid = e.sd_sid
-and elig_crit is not null
-and not (elig_crit ~ '\n')
-and elig_crit not like '(-)%'
-and elig_crit not like '(1%'
-and elig_crit not like '（1%'
-and elig_crit not like '(a)%'
-and elig_crit not like '(A)%'
-and elig_crit not like '(i)%'
-and elig_crit not like '(I)%'
-and elig_crit not like '1.%'
-and elig_crit not like '1)%';
-
-
-update ad.studies s
-set iec_flag = 2
-from ad.iec_base e
-where s.sd_sid = e.sd_sid
-and elig_crit is not null
-and (elig_crit ~ '\n'
-or elig_crit like '(-)%'
-or elig_crit like '(1%'
-or elig_crit like '（1%'
-or elig_crit like '(a)%'
-or elig_crit like '(A)%'
-or elig_crit like '(i)%'
-or elig_crit like '(I)%'
-or elig_crit like '1.%'
-or elig_crit like '1)%');
-
-
-update ad.studies s
-set iec_flag = iec_flag + 4
-from ad.iec_base e
-where s.sd_sid = e.sd_sid
-and has_incl = true
-and not (inc_crit ~ '\n')
-and inc_crit not like '(-)%'
-and inc_crit not like '(1%'
-and inc_crit not like '（1%'
-and inc_crit not like '(a)%'
-and inc_crit not like '(A)%'
-and inc_crit not like '(i)%'
-and inc_crit not like '(I)%'
-and inc_crit not like '1.%'
-and inc_crit not like '1)%';
-
-
-update ad.studies s
-set iec_flag = iec_flag + 8
-from ad.iec_base e
-where s.sd_sid = e.sd_sid
-and has_incl = true
-and (inc_crit ~ '\n'
-or inc_crit like '(-)%'
-or inc_crit like '(1%'
-or inc_crit like '（1%'
-or inc_crit like '(a)%'
-or inc_crit like '(A)%'
-or inc_crit like '(i)%'
-or inc_crit like '(I)%'
-or inc_crit like '1.%'
-or inc_crit like '1)%');
-
-
-update ad.studies s
-set iec_flag = iec_flag + 16
-from ad.iec_base e
-where s.sd_sid = e.sd_sid
-and has_excl = true
-and not (exc_crit ~ '\n')
-and exc_crit not like '(-)%'
-and exc_crit not like '(1%'
-and exc_crit not like '（1%'
-and exc_crit not like '(a)%'
-and exc_crit not like '(A)%'
-and exc_crit not like '(i)%'
-and exc_crit not like '(I)%'
-and exc_crit not like '1.%'
-and exc_crit not like '1)%';
-
-
-update ad.studies s
-set iec_flag = iec_flag + 32
-from ad.iec_base e
-where s.sd_sid = e.sd_sid
-and has_excl = true
-and (exc_crit ~ '\n'
-or exc_crit like '(-)%'
-or exc_crit like '(1%'
-or exc_crit like '（1%'
-or exc_crit like '(a)%'
-or exc_crit like '(A)%'
-or exc_crit like '(i)%'
-or exc_crit like '(I)%'
-or exc_crit like '1.%'
-or exc_crit like '1)%');
-
-
-SELECT pg_size_pretty(pg_total_relation_size('ad.studies'));
-VACUUM (FULL, VERBOSE, ANALYZE) ad.studies;
-SELECT pg_size_pretty(pg_total_relation_size('ad.studies'));
-
-
-select iec_flag, count(id)
-from ad.studies 
-group by iec_flag
-
-select * from ad.iec_base
-
-
-
-*/
\ No newline at end of file
+// The number of ad.iec_base records read and split as a batch.
+
+const SPLIT_BATCH: i32 = 10000;
+
+#[derive(sqlx::FromRow)]
+struct CriteriaRow {
+    sd_sid: String,
+    criteria: String,
+}
+
+
+pub async fn build_iec_table (ex: &SqlExecutor) -> Result<(), AppError> {
+
+    let sql = r#"SET client_min_messages TO WARNING;
+    DROP TABLE IF EXISTS ad.iec_base;
+    CREATE TABLE ad.iec_base(
+      id                     INT             PRIMARY KEY GENERATED ALWAYS AS IDENTITY (start with 1000001 increment by 1)
+    , sd_sid                 VARCHAR         NOT NULL
+    , block_num              INT             NOT NULL default 1
+    , cohort                 VARCHAR         NULL
+    , criteria               VARCHAR         NULL
+    , elig_crit              VARCHAR         NULL
+    , inc_crit               VARCHAR         NULL
+    , exc_crit               VARCHAR         NULL
+    , has_incl               BOOL            NOT NULL default false
+    , has_excl               BOOL            NOT NULL default false
+    , examined               BOOL            NOT NULL default false
+    );
+    CREATE INDEX iec_base_sid ON ad.iec_base(sd_sid);
+    SET client_min_messages TO NOTICE;"#;
+
+    ex.execute(sql).await?;
+    info!("iec_base table (re)created");
+    info!("");
+
+    Ok(())
+}
+
+
+pub async fn load_iec_data (ex: &SqlExecutor) -> Result<(), AppError> {
+
+    execute_and_report(load_criteria_sql(), "eligibility criteria records transferred to ad.iec_base", ex).await?;
+
+    // Tidy up the beginning of the criteria statements.
+
+    ex.execute(r#"update ad.iec_base set criteria = trim(leading '*' from criteria) where criteria like '*%';"#).await?;
+    ex.execute(r#"update ad.iec_base set criteria = trim(criteria) where criteria like ' %';"#).await?;
+
+    // Identify the records with no criteria, which need no further examination.
+
+    let mut r = 0;
+    for sql in [no_criteria_1_sql(), no_criteria_2_sql(), no_criteria_3_sql(), no_criteria_4_sql()] {
+        r += ex.execute(sql).await?.rows_affected();
+    }
+    info!("{} records identified as having no criteria", r);
+
+    split_iec_data(ex).await?;
+    info!("");
+
+    Ok(())
+}
+
+
+async fn split_iec_data (ex: &SqlExecutor) -> Result<(), AppError> {
+
+    // The remaining criteria are split at their inclusion and exclusion headings (see
+    // iec_splitter.rs), which are removed. Each block of criteria is a record in
+    // ad.iec_base, with the cohort it applies to, if any, so that a study may have
+    // several records. The original records are read in batches (by id), and replaced
+    // by those for their blocks. In a dry run ad.iec_base cannot be read, so nothing
+    // is split.
+
+    if dry_run::is_active() {
+        info!("dry run: criteria not split into inclusion and exclusion, as ad.iec_base cannot be read");
+        return Ok(());
+    }
+
+    let (min_id, max_id): (Option<i32>, Option<i32>) = ex.fetch_rows("select min(id), max(id) from ad.iec_base where examined = false").await?
+        .into_iter().next().unwrap_or((None, None));
+    let (Some(min_id), Some(max_id)) = (min_id, max_id) else {
+        info!("no criteria to split");
+        return Ok(());
+    };
+
+    let (mut unsplit, mut split, mut with_cohorts) = (0, 0, 0);
+    let mut start = min_id;
+    while start <= max_id {
+        let sql = format!(r#"select sd_sid, criteria from ad.iec_base
+                          where examined = false and id >= {} and id < {} order by id"#, start, start + SPLIT_BATCH);
+        let rows: Vec<CriteriaRow> = ex.fetch_rows(&sql).await?;
+
+        let mut columns: Vec<Vec<String>> = vec![Vec::new(); 6];
+        for r in rows {
+            let blocks = split_criteria(&r.criteria);
+            if blocks.iter().any(|b| b.iec_type != IecType::Eligibility) { split += 1; } else { unsplit += 1; }
+            if blocks.iter().any(|b| b.cohort.is_some()) { with_cohorts += 1; }
+
+            // Criteria that are (almost) blank have no blocks, but the study is retained.
+
+            if blocks.is_empty() {
+                for (c, v) in columns.iter_mut().zip([r.sd_sid.as_str(), "1", "", r.criteria.as_str(), "none", ""]) {
+                    c.push(v.to_string());
+                }
+            }
+            for (i, b) in blocks.into_iter().enumerate() {
+                let criteria = if i == 0 { r.criteria.clone() } else { "".to_string() };
+                columns[0].push(r.sd_sid.clone());
+                columns[1].push((i + 1).to_string());
+                columns[2].push(b.cohort.unwrap_or_default());
+                columns[3].push(criteria);
+                columns[4].push(b.iec_type.name().to_string());
+                columns[5].push(b.text);
+            }
+        }
+
+        if !columns[0].is_empty() {
+            ex.execute_with_arrays(insert_blocks_sql(), &columns).await?;
+        }
+        ex.execute(&format!("delete from ad.iec_base where examined = false and id >= {} and id < {};", start, start + SPLIT_BATCH)).await?;
+        start += SPLIT_BATCH;
+    }
+
+    info!("{} records with no inclusion or exclusion headings, not split", unsplit);
+    info!("{} records split into inclusion and / or exclusion criteria", split);
+    info!("{} records with criteria for one or more labelled cohorts", with_cohorts);
+
+    Ok(())
+}
+
+
+fn load_criteria_sql <'a>() -> &'a str {
+    r#"insert into ad.iec_base(sd_sid, criteria)
+    select nct_id, criteria
+    from ctgov.eligibilities;"#
+}
+
+
+fn no_criteria_1_sql <'a>() -> &'a str {
+    r#"update ad.iec_base
+    set examined = true
+    where criteria is null
+    or criteria ilike 'No eligibility criteria%';"#
+}
+
+
+fn no_criteria_2_sql <'a>() -> &'a str {
+    r#"update ad.iec_base
+    set examined = true
+    where criteria ilike 'data ana%'
+    or criteria ilike 'please contact site%';"#
+}
+
+
+fn no_criteria_3_sql <'a>() -> &'a str {
+    r#"update ad.iec_base
+    set examined = true
+    where criteria ilike 'no%' and
+    (criteria ilike 'none %'
+    or criteria like 'No patient will be included%'
+    or criteria like 'No in- or exclusion criteria%'
+    or criteria = 'Non applicable');"#
+}
+
+
+fn no_criteria_4_sql <'a>() -> &'a str {
+    r#"update ad.iec_base
+    set examined = true
+    where criteria ilike 'no %' and
+    (criteria = 'No formal inclusion or exclusion criteria'
+    or criteria like 'No inclusion or exclusion criteria%'
+    or criteria = 'No particular inclusion or exclusion criteria'
+    or criteria ilike 'No patients enrolled%'
+    or criteria = 'No inclusion or exclusion criteria');"#
+}
+
+
+fn insert_blocks_sql <'a>() -> &'a str {
+    r#"insert into ad.iec_base(sd_sid, block_num, cohort, criteria, elig_crit, inc_crit, exc_crit, has_incl, has_excl, examined)
+    select sd_sid, block_num::int, nullif(cohort, ''), nullif(criteria, ''),
+    case when iec_type = 'eligibility' then crit end,
+    case when iec_type = 'inclusion' then crit end,
+    case when iec_type = 'exclusion' then crit end,
+    iec_type = 'inclusion', iec_type = 'exclusion', true
+    from unnest($1::varchar[], $2::varchar[], $3::varchar[], $4::varchar[], $5::varchar[], $6::varchar[])
+    as t(sd_sid, block_num, cohort, criteria, iec_type, crit);"#
+}
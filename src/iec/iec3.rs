@@ -1,238 +1,226 @@
-
-
-/*
-
--- Can finally move to deleting spurious entries
-
-update ad.iec_base
-set exc_crit = null,
-has_excl = false
-where length(exc_crit) < 10
-and (
-	exc_crit = '???'
-	or exc_crit = '<Clinical'
-	or exc_crit = '<Common'
-	or exc_crit = '1-2'
-	or exc_crit like '12.%'
-	or exc_crit like '13.%'
-	or exc_crit like '2.%'
-	or exc_crit like '3.%'
-	or exc_crit like '4.%'
-	or exc_crit like '5.%'
-	or exc_crit like '6.%'
-	or exc_crit like '7.%'
-	or exc_crit like '8.%'   
-);
-
-
-update ad.iec_base
-set exc_crit = null,
-has_excl = false
-where length(exc_crit) < 10
-and (
-	lower(exc_crit) = 'age'
-	or lower(exc_crit) = 'all'
-	or lower(exc_crit) = 'and'
-	or lower(exc_crit) = 'any'
-	or exc_crit ilike 'ary%'
-	or lower(exc_crit) = 'baseline'
-	or lower(exc_crit) = 'basic'
-	or exc_crit ilike 'case%'
-	or exc_crit ilike 'clinic%'
-	or lower(exc_crit) = 'common'
-);
-
-
-update ad.iec_base
-set exc_crit = null,
-has_excl = false
-where length(exc_crit) < 10
-and (
-	exc_crit ilike 'criter%' 
-	or lower(exc_crit) = 'critical'
-	or lower(exc_crit) = 'current'
-	or lower(exc_crit) = 'few'
-	or lower(exc_crit) = 'for'
-	or lower(exc_crit) = 'general'
-	or lower(exc_crit) = 'initial'
-	or lower(exc_crit) = 'key'
-	or lower(exc_crit) = 'known'
-	or lower(exc_crit) = 'list'
-	or lower(exc_crit) = 'main'
-);
-
-
-update ad.iec_base
-set exc_crit = null,
-has_excl = false
-where length(exc_crit) < 10
-and (
-	lower(exc_crit) = 'major'
-	or lower(exc_crit) = 'medical'
-	or exc_crit ilike 'meet%'
-	or lower(exc_crit) = 'n. a'
-	or lower(exc_crit) = 'n.a'
-	or exc_crit ilike 'n/a%'
-	or exc_crit ilike 'nil%'
-	or lower(exc_crit) = 'no any'
-	or lower(exc_crit) = 'no extra'
-	or lower(exc_crit) = 'no formal'
-);
-
-
-update ad.iec_base
-set exc_crit = null,
-has_excl = false
-where length(exc_crit) < 10
-and (
-	lower(exc_crit) = 'n?a'
-	or lower(exc_crit) = 'multiple'
-	or exc_crit ilike 'na$%'
-	or lower(exc_crit) = 'neither'
-	or lower(exc_crit) = 'no one'
-	or exc_crit ilike 'no$%'
-	or exc_crit ilike 'nil%'
-	or lower(exc_crit) = 'no other'
-	or lower(exc_crit) = 'nobody'
-	or lower(exc_crit) = 'non'
-	or exc_crit ilike 'non$%'
-	or lower(exc_crit) = 'none'
-);
-
-
-update ad.iec_base
-set exc_crit = null,
-has_excl = false
-where length(exc_crit) < 10
-and (
-	exc_crit ilike 'none%'
-	or lower(exc_crit) = 'not'
-	or lower(exc_crit) = 'not any'
-	or lower(exc_crit) = 'not have'
-	or lower(exc_crit) = 'nothing'
-	or lower(exc_crit) = 'null'
-	or lower(exc_crit) = 'obvious'
-	or exc_crit ilike 'other%'
-	or lower(exc_crit) = 'our'
-	or lower(exc_crit) = 'overall'
-);
-
-
-update ad.iec_base
-set exc_crit = null,
-has_excl = false
-where length(exc_crit) < 10
-and (
-	exc_crit ilike 'part%'
-	or exc_crit ilike 'patient%'
-	or exc_crit ilike 'phase%'
-	or lower(exc_crit) = 'primary'
-	or lower(exc_crit) = 'principal'
-	or lower(exc_crit) = 'recipient'
-	or lower(exc_crit) = 'refusal'
-	or lower(exc_crit) = 'registry'
-	or lower(exc_crit) = 'regular'
-	or lower(exc_crit) = 's below'
-	or lower(exc_crit) = 's exist'
-	or lower(exc_crit) = 'same'
-	or lower(exc_crit) = 'see'
-	or lower(exc_crit) = 'see above'
-);
-
-
-update ad.iec_base
-set exc_crit = null,
-has_excl = false
-where length(exc_crit) < 10
-and (
-	exc_crit ilike 'stage%'
-	or exc_crit ilike 'step%'
-	or exc_crit ilike 'study%'
-	or lower(exc_crit) = 'the'
-	or exc_crit ilike 'the %'
-	or exc_crit ilike 'usual%'
-	or lower(exc_crit) = 'subject'
-	or lower(exc_crit) = 'systemic'
-	or lower(exc_crit) = 'tbc'
-	or lower(exc_crit) = 'temporary'
-	or lower(exc_crit) = 'these'
-	or lower(exc_crit) = 'to add'
-	or lower(exc_crit) = 'trial'
-	or lower(exc_crit) = 'us sites:'
-);
-
-
-select inc_crit
-from ad.iec_base
-where length(inc_crit) < 100
-and not (inc_crit ~ '\n')
-order by inc_crit
-
-select inc_crit
-from ad.iec_base
-where not (inc_crit ~ '\n')
-order by inc_crit
-
-
-SELECT pg_size_pretty(pg_total_relation_size('ad.iec_bases'));
-VACUUM (FULL, VERBOSE, ANALYZE) ad.iec_bases;
-SELECT pg_size_pretty(pg_total_relation_size('ad.iec_bases'));
-
-
-update ad.studies s
-set iec_flag = 0;
-
-update ad.studies s
-set iec_flag = 1
-from ad.iec_base e
-where s.sd_sid = e.sd_sid
-and elig_crit is not null
-and not (elig_crit ~ '\n');
-
-update ad.studies s
-set iec_flag = 2
-from ad.iec_base e
-where s.sd_sid = e.sd_sid
-and elig_crit is not null
-and elig_crit ~ '\n';
-
-update ad.studies s
-set iec_flag = iec_flag + 4
-from ad.iec_base e
-where s.sd_sid = e.sd_sid
-and has_incl = true
-and not (inc_crit ~ '\n');
-
-update ad.studies s
-set iec_flag = iec_flag + 8
-from ad.iec_base e
-where s.sd_sid = e.sd_sid
-and has_incl = true
-and inc_crit ~ '\n';
-
-update ad.studies s
-set iec_flag = iec_flag + 16
-from ad.iec_base e
-where s.sd_sid = e.sd_sid
-and has_excl = true
-and not (exc_crit ~ '\n');
-
-update ad.studies s
-set iec_flag = iec_flag + 32
-from ad.iec_base e
-where s.sd_sid = e.sd_sid
-and has_excl = true
-and exc_crit ~ '\n';
-
-
-SELECT pg_size_pretty(pg_total_relation_size('ad.studies'));
-VACUUM (FULL, VERBOSE, ANALYZE) ad.studies;
-SELECT pg_size_pretty(pg_total_relation_size('ad.studies'));
-
-
-select iec_flag, count(id)
-from ad.studies 
-group by iec_flag
-
-
-*/
\ No newline at end of file
+use super::iec_utils::{remove_spurious_crit, execute_and_report};
+use crate::mdr::executor::SqlExecutor;
+use crate::AppError;
+use log::info;
+
+
+pub async fn remove_spurious_inc_crit (ex: &SqlExecutor) -> Result<(), AppError> {
+
+    remove_spurious_crit("inc_crit", spurious_inc_sql(), ex).await?;
+    info!("");
+
+    Ok(())
+}
+
+
+pub async fn remove_spurious_exc_crit (ex: &SqlExecutor) -> Result<(), AppError> {
+
+    for conditions in [spurious_exc_1_sql(), spurious_exc_2_sql(), spurious_exc_3_sql(), spurious_exc_4_sql(),
+                       spurious_exc_5_sql(), spurious_exc_6_sql(), spurious_exc_7_sql(), spurious_exc_8_sql()] {
+        remove_spurious_crit("exc_crit", conditions, ex).await?;
+    }
+    info!("");
+
+    Ok(())
+}
+
+
+pub async fn set_iec_flags (ex: &SqlExecutor) -> Result<(), AppError> {
+
+    // The iec_flag is derived from the parsed criteria in ad.study_iec. It is the sum
+    // of a value for any unsplit criteria (1 for a single criterion, 2 for more than
+    // one), any inclusion criteria (4 or 8) and any exclusion criteria (16 or 32),
+    // plus 64 if any of the criteria are in a numbered or lettered list, and 128 if
+    // any list has nested sub-items. A study with no criteria has a flag of 0.
+
+    ex.execute("update ad.studies set iec_flag = 0;").await?;
+
+    let numbered = "bool_or(leader ~ '^[(（]?[0-9A-Za-z]')";
+    let flags = [
+        (1, "eligibility", "count(*) = 1", "studies with a single unstructured statement of criteria (1)"),
+        (2, "eligibility", "count(*) > 1", "studies with multiple criteria, not split into inclusion and exclusion (2)"),
+        (4, "inclusion", "count(*) = 1", "studies with a single inclusion criterion (+4)"),
+        (8, "inclusion", "count(*) > 1", "studies with multiple inclusion criteria (+8)"),
+        (16, "exclusion", "count(*) = 1", "studies with a single exclusion criterion (+16)"),
+        (32, "exclusion", "count(*) > 1", "studies with multiple exclusion criteria (+32)"),
+        (64, "", numbered, "studies with criteria in numbered or lettered lists (+64)"),
+        (128, "", "max(indent_level) > 1", "studies with criteria in lists with nested sub-items (+128)"),
+    ];
+    for (value, iec_type, having, fb) in flags {
+        execute_and_report(&iec_flag_sql(value, iec_type, having), fb, ex).await?;
+    }
+    info!("");
+
+    Ok(())
+}
+
+
+fn iec_flag_sql(value: i32, iec_type: &str, having: &str) -> String {
+
+    // Adds the value to the flag of each study whose criteria (of the type given,
+    // or of any type) meet the condition.
+
+    let type_where = if iec_type.is_empty() { "".to_string() } else { format!("where iec_type = '{}' ", iec_type) };
+    format!(r#"update ad.studies s set iec_flag = iec_flag + {}
+        from (select sd_sid from ad.study_iec {}group by sd_sid having {}) c
+        where s.sd_sid = c.sd_sid;"#, value, type_where, having)
+}
+
+
+fn spurious_inc_sql <'a>() -> &'a str {
+    r#"inc_crit = '???'
+    or lower(inc_crit) = 'all'
+    or lower(inc_crit) = 'general'
+    or lower(inc_crit) = 'in- and'
+    or lower(inc_crit) = 'major'
+    or lower(inc_crit) = 'nil'
+    or inc_crit ilike 'n/a%'
+    or inc_crit ilike 'non%'
+    or lower(inc_crit) = 'see above'
+    or lower(inc_crit) = 'to_add'"#
+}
+
+
+fn spurious_exc_1_sql <'a>() -> &'a str {
+    r#"exc_crit = '???'
+    or exc_crit = '<Clinical'
+    or exc_crit = '<Common'
+    or exc_crit = '1-2'
+    or exc_crit like '12.%'
+    or exc_crit like '13.%'
+    or exc_crit like '2.%'
+    or exc_crit like '3.%'
+    or exc_crit like '4.%'
+    or exc_crit like '5.%'
+    or exc_crit like '6.%'
+    or exc_crit like '7.%'
+    or exc_crit like '8.%'"#
+}
+
+
+fn spurious_exc_2_sql <'a>() -> &'a str {
+    r#"lower(exc_crit) = 'age'
+    or lower(exc_crit) = 'all'
+    or lower(exc_crit) = 'and'
+    or lower(exc_crit) = 'any'
+    or exc_crit ilike 'ary%'
+    or lower(exc_crit) = 'baseline'
+    or lower(exc_crit) = 'basic'
+    or exc_crit ilike 'case%'
+    or exc_crit ilike 'clinic%'
+    or lower(exc_crit) = 'common'"#
+}
+
+
+fn spurious_exc_3_sql <'a>() -> &'a str {
+    r#"exc_crit ilike 'criter%'
+    or lower(exc_crit) = 'critical'
+    or lower(exc_crit) = 'current'
+    or lower(exc_crit) = 'few'
+    or lower(exc_crit) = 'for'
+    or lower(exc_crit) = 'general'
+    or lower(exc_crit) = 'initial'
+    or lower(exc_crit) = 'key'
+    or lower(exc_crit) = 'known'
+    or lower(exc_crit) = 'list'
+    or lower(exc_crit) = 'main'"#
+}
+
+
+fn spurious_exc_4_sql <'a>() -> &'a str {
+    r#"lower(exc_crit) = 'major'
+    or lower(exc_crit) = 'medical'
+    or exc_crit ilike 'meet%'
+    or lower(exc_crit) = 'n. a'
+    or lower(exc_crit) = 'n.a'
+    or exc_crit ilike 'n/a%'
+    or exc_crit ilike 'nil%'
+    or lower(exc_crit) = 'no any'
+    or lower(exc_crit) = 'no extra'
+    or lower(exc_crit) = 'no formal'"#
+}
+
+
+fn spurious_exc_5_sql <'a>() -> &'a str {
+
+    // A '$' stands for a line break in these short entries (see tidy_exc_crit).
+
+    r#"lower(exc_crit) = 'n?a'
+    or lower(exc_crit) = 'multiple'
+    or exc_crit ilike 'na$%'
+    or lower(exc_crit) = 'neither'
+    or lower(exc_crit) = 'no one'
+    or exc_crit ilike 'no$%'
+    or exc_crit ilike 'nil%'
+    or lower(exc_crit) = 'no other'
+    or lower(exc_crit) = 'nobody'
+    or lower(exc_crit) = 'non'
+    or exc_crit ilike 'non$%'
+    or lower(exc_crit) = 'none'"#
+}
+
+
+fn spurious_exc_6_sql <'a>() -> &'a str {
+    r#"exc_crit ilike 'none%'
+    or lower(exc_crit) = 'not'
+    or lower(exc_crit) = 'not any'
+    or lower(exc_crit) = 'not have'
+    or lower(exc_crit) = 'nothing'
+    or lower(exc_crit) = 'null'
+    or lower(exc_crit) = 'obvious'
+    or exc_crit ilike 'other%'
+    or lower(exc_crit) = 'our'
+    or lower(exc_crit) = 'overall'"#
+}
+
+
+fn spurious_exc_7_sql <'a>() -> &'a str {
+    r#"exc_crit ilike 'part%'
+    or exc_crit ilike 'patient%'
+    or exc_crit ilike 'phase%'
+    or lower(exc_crit) = 'primary'
+    or lower(exc_crit) = 'principal'
+    or lower(exc_crit) = 'recipient'
+    or lower(exc_crit) = 'refusal'
+    or lower(exc_crit) = 'registry'
+    or lower(exc_crit) = 'regular'
+    or lower(exc_crit) = 's below'
+    or lower(exc_crit) = 's exist'
+    or lower(exc_crit) = 'same'
+    or lower(exc_crit) = 'see'
+    or lower(exc_crit) = 'see above'"#
+}
+
+
+fn spurious_exc_8_sql <'a>() -> &'a str {
+    r#"exc_crit ilike 'stage%'
+    or exc_crit ilike 'step%'
+    or exc_crit ilike 'study%'
+    or lower(exc_crit) = 'the'
+    or exc_crit ilike 'the %'
+    or exc_crit ilike 'usual%'
+    or lower(exc_crit) = 'subject'
+    or lower(exc_crit) = 'systemic'
+    or lower(exc_crit) = 'tbc'
+    or lower(exc_crit) = 'temporary'
+    or lower(exc_crit) = 'these'
+    or lower(exc_crit) = 'to add'
+    or lower(exc_crit) = 'trial'
+    or lower(exc_crit) = 'us sites:'"#
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_iec_flag_sql() {
+        let sql = iec_flag_sql(8, "inclusion", "count(*) > 1");
+        assert!(sql.starts_with("update ad.studies s set iec_flag = iec_flag + 8"));
+        assert!(sql.contains("from ad.study_iec where iec_type = 'inclusion' group by sd_sid having count(*) > 1) c"));
+
+        let sql = iec_flag_sql(128, "", "max(indent_level) > 1");
+        assert!(sql.contains("from ad.study_iec group by sd_sid having max(indent_level) > 1) c"));
+    }
+}
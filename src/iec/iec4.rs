@@ -0,0 +1,123 @@
+use super::iec_parser::parse_criteria;
+use crate::mdr::dry_run;
+use crate::mdr::executor::SqlExecutor;
+use crate::AppError;
+use log::info;
+
+// The number of ad.iec_base records read, parsed and written back as a batch.
+
+const PARSE_BATCH: i32 = 10000;
+
+#[derive(sqlx::FromRow)]
+struct IecBaseRow {
+    sd_sid: String,
+    block_num: i32,
+    cohort: Option<String>,
+    elig_crit: Option<String>,
+    inc_crit: Option<String>,
+    exc_crit: Option<String>,
+}
+
+
+pub async fn build_study_iec_table (ex: &SqlExecutor) -> Result<(), AppError> {
+
+    let sql = r#"SET client_min_messages TO WARNING;
+    DROP TABLE IF EXISTS ad.study_iec;
+    CREATE TABLE ad.study_iec(
+      id                     INT             PRIMARY KEY GENERATED ALWAYS AS IDENTITY (start with 1000001 increment by 1)
+    , sd_sid                 VARCHAR         NOT NULL
+    , block_num              INT             NOT NULL
+    , cohort                 VARCHAR         NULL
+    , iec_type               VARCHAR         NOT NULL
+    , seq_num                VARCHAR         NOT NULL
+    , indent_level           INT             NOT NULL
+    , leader                 VARCHAR         NULL
+    , iec_text               VARCHAR         NOT NULL
+    );
+    CREATE INDEX study_iec_sid ON ad.study_iec(sd_sid);
+    SET client_min_messages TO NOTICE;"#;
+
+    ex.execute(sql).await?;
+    info!("study_iec table (re)created");
+    info!("");
+
+    Ok(())
+}
+
+
+pub async fn parse_iec_data (ex: &SqlExecutor) -> Result<(), AppError> {
+
+    // The criteria are parsed in the program rather than in sql, as identifying the
+    // list structure depends on the items that precede each line. The ad.iec_base
+    // records are read in batches (by id), and the criteria written back as one
+    // insert per batch. In a dry run ad.iec_base cannot be read, so nothing is parsed.
+
+    if dry_run::is_active() {
+        info!("dry run: criteria not parsed into ad.study_iec, as ad.iec_base cannot be read");
+        return Ok(());
+    }
+
+    let (min_id, max_id): (Option<i32>, Option<i32>) = ex.fetch_rows("select min(id), max(id) from ad.iec_base").await?
+        .into_iter().next().unwrap_or((None, None));
+    let (Some(min_id), Some(max_id)) = (min_id, max_id) else {
+        info!("no criteria to parse, as ad.iec_base is empty");
+        return Ok(());
+    };
+
+    let mut total: u64 = 0;
+    let mut start = min_id;
+    while start <= max_id {
+        let sql = format!(r#"select sd_sid, block_num, cohort, elig_crit, inc_crit, exc_crit from ad.iec_base
+                          where id >= {} and id < {} order by id"#, start, start + PARSE_BATCH);
+        let rows: Vec<IecBaseRow> = ex.fetch_rows(&sql).await?;
+
+        let mut columns: Vec<Vec<String>> = vec![Vec::new(); 8];
+        for r in rows {
+            for (iec_type, text) in [("eligibility", r.elig_crit), ("inclusion", r.inc_crit), ("exclusion", r.exc_crit)] {
+                let Some(text) = text else { continue };
+                for c in parse_criteria(&text) {
+                    columns[0].push(r.sd_sid.clone());
+                    columns[1].push(r.block_num.to_string());
+                    columns[2].push(r.cohort.clone().unwrap_or_default());
+                    columns[3].push(iec_type.to_string());
+                    columns[4].push(c.seq_num);
+                    columns[5].push(c.indent_level.to_string());
+                    columns[6].push(c.leader);
+                    columns[7].push(c.text);
+                }
+            }
+        }
+
+        if !columns[0].is_empty() {
+            total += ex.execute_with_arrays(insert_criteria_sql(), &columns).await?.rows_affected();
+        }
+        start += PARSE_BATCH;
+    }
+
+    info!("{} individual criteria parsed into ad.study_iec", total);
+    ex.execute(r#"update ad.study_iec set iec_text = regexp_replace(iec_text, '\s{2,}', ' ', 'g') where iec_text ~ '\s{2,}';"#).await?;
+    report_type_counts(ex).await?;
+    info!("");
+
+    Ok(())
+}
+
+
+async fn report_type_counts (ex: &SqlExecutor) -> Result<(), AppError> {
+
+    let sql = r#"select iec_type, count(*), count(distinct sd_sid), count(*) filter (where indent_level > 1)
+                 from ad.study_iec group by iec_type order by iec_type"#;
+    let counts: Vec<(String, i64, i64, i64)> = ex.fetch_rows(sql).await?;
+    for (iec_type, n, studies, nested) in counts {
+        info!("{} {} criteria, for {} studies ({} nested sub-items)", n, iec_type, studies, nested);
+    }
+    Ok(())
+}
+
+
+fn insert_criteria_sql <'a>() -> &'a str {
+    r#"insert into ad.study_iec(sd_sid, block_num, cohort, iec_type, seq_num, indent_level, leader, iec_text)
+    select sd_sid, block_num::int, nullif(cohort, ''), iec_type, seq_num, indent_level::int, nullif(leader, ''), iec_text
+    from unnest($1::varchar[], $2::varchar[], $3::varchar[], $4::varchar[], $5::varchar[], $6::varchar[], $7::varchar[], $8::varchar[])
+    as t(sd_sid, block_num, cohort, iec_type, seq_num, indent_level, leader, iec_text);"#
+}
@@ -0,0 +1,371 @@
+// Splits a block of criteria text (the inclusion, exclusion or unsplit criteria of
+// a study) into individual criteria. Each line is examined for a leader, i.e. the
+// list marker at its start: a number ("1.", "1)", "(1)", "（1）", or "1.1" for
+// numbered sub-items), a letter ("a)", "(a)", "A."), a roman numeral ("i)", "(ii)")
+// or a bullet ("-", "*", "•", "(-)"...). Each distinct style of leader is a level of
+// the list, so that sub-items (e.g. bullets within numbered items) are given a
+// hierarchical sequence number ("2.1"). Lines without a leader continue the item
+// above, unless no list has started, when each line is a criterion in its own right.
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Criterion {
+    pub seq_num: String,
+    pub indent_level: usize,
+    pub leader: String,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    Line,
+    Number,
+    LowerAlpha,
+    UpperAlpha,
+    LowerRoman,
+    UpperRoman,
+    Bullet(char),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Form {
+    Plain,      // bullets, and lines without a leader
+    Dot,        // 1.  a.  1.1
+    Paren,      // 1)  a)
+    Enclosed,   // (1) (a) （1） (-)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Style {
+    kind: Kind,
+    form: Form,
+    depth: usize,   // the number of parts in a multi-level number, e.g. 2 for '1.1'
+}
+
+struct Level {
+    style: Style,
+    count: u32,     // the item's position at this level, used for the seq_num
+    value: u32,     // the value of the item's leader, e.g. 3 for 'c)'
+    heading: bool,  // a line, without a leader, that introduces the items that follow
+}
+
+struct Leader<'a> {
+    style: Style,
+    value: u32,
+    leader: &'a str,
+    rest: &'a str,
+}
+
+const BULLETS: [char; 13] = ['-', '–', '—', '*', '•', '●', '·', '°', '▪', '■', '◦', '○', '►'];
+
+const ROMANS: [&str; 20] = ["i", "ii", "iii", "iv", "v", "vi", "vii", "viii", "ix", "x",
+    "xi", "xii", "xiii", "xiv", "xv", "xvi", "xvii", "xviii", "xix", "xx"];
+
+
+pub fn parse_criteria(text: &str) -> Vec<Criterion> {
+
+    let mut crits: Vec<Criterion> = Vec::new();
+    let mut levels: Vec<Level> = Vec::new();
+
+    for line in text.lines().map(|l| l.trim()).filter(|l| !l.is_empty()) {
+
+        if let Some(ld) = find_leader(line, &levels) {
+            place_item(&mut levels, ld.style, ld.value);
+            crits.push(Criterion {
+                seq_num: seq_num(&levels),
+                indent_level: levels.len(),
+                leader: ld.leader.to_string(),
+                text: ld.rest.to_string(),
+            });
+            continue;
+        }
+
+        // A line without a leader is a new top level item if there is no list as
+        // yet, or if it is a heading between lists, and otherwise continues the item.
+
+        let new_item = match levels.first() {
+            None => true,
+            Some(top) => top.style.kind == Kind::Line && (levels.len() == 1 || line.ends_with(':')),
+        };
+
+        match crits.last_mut() {
+            Some(last) if !new_item => {
+                if !last.text.is_empty() {
+                    last.text.push(' ');
+                }
+                last.text.push_str(line);
+            },
+            _ => {
+                levels.truncate(1);
+                let style = Style { kind: Kind::Line, form: Form::Plain, depth: 1 };
+                match levels.first_mut() {
+                    Some(top) => {
+                        top.style = style;
+                        top.count += 1;
+                        top.heading = line.ends_with(':');
+                    },
+                    None => levels.push(Level { style, count: 1, value: 0, heading: line.ends_with(':') }),
+                }
+                crits.push(Criterion {
+                    seq_num: seq_num(&levels),
+                    indent_level: 1,
+                    leader: "".to_string(),
+                    text: line.to_string(),
+                });
+            },
+        }
+    }
+
+    crits.retain(|c| !c.text.is_empty());
+    crits
+}
+
+
+fn place_item(levels: &mut Vec<Level>, style: Style, value: u32) {
+
+    // An item of a style already in use is a sibling of the items of that style,
+    // and ends any lists below them. A new style starts a list of sub-items, unless
+    // the item above is a line that does not introduce it (i.e. a preamble).
+
+    if let Some(k) = levels.iter().position(|l| l.style == style) {
+        levels.truncate(k + 1);
+        levels[k].count += 1;
+        levels[k].value = value;
+    }
+    else if let Some(last) = levels.last_mut() && last.style.kind == Kind::Line && !last.heading {
+        last.style = style;
+        last.count += 1;
+        last.value = value;
+    }
+    else {
+        levels.push(Level { style, count: 1, value, heading: false });
+    }
+}
+
+
+fn seq_num(levels: &[Level]) -> String {
+    levels.iter().map(|l| l.count.to_string()).collect::<Vec<String>>().join(".")
+}
+
+
+fn find_leader<'a>(line: &'a str, levels: &[Level]) -> Option<Leader<'a>> {
+
+    let first = line.chars().next()?;
+
+    if let Some(rest) = line.strip_prefix("(-)") {
+        return Some(leader_of(line, rest, Style { kind: Kind::Bullet('-'), form: Form::Enclosed, depth: 1 }, 0));
+    }
+    // '>' is only a bullet if followed by a space, as it usually starts an inequality ('>18 years').
+
+    let is_arrow = first == '>' && line.trim_start_matches('>').starts_with(char::is_whitespace);
+    if BULLETS.contains(&first) || is_arrow || (first == 'o' && line.starts_with("o ")) {
+        let rest = line.trim_start_matches(first);
+        return Some(leader_of(line, rest, Style { kind: Kind::Bullet(first), form: Form::Plain, depth: 1 }, 0));
+    }
+
+    let enclosed = first == '(' || first == '（';
+    let body = if enclosed { &line[first.len_utf8()..] } else { line };
+
+    if body.starts_with(|c: char| c.is_ascii_digit()) {
+        find_number(line, body, enclosed, levels)
+    }
+    else if body.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        find_letter(line, body, enclosed, levels)
+    }
+    else {
+        None
+    }
+}
+
+
+fn find_number<'a>(line: &'a str, body: &'a str, enclosed: bool, levels: &[Level]) -> Option<Leader<'a>> {
+
+    // Numbers are followed by '.' or ')', or enclosed in brackets. Numbers of more
+    // than one part (1.1, 1.1.) are only leaders within a numbered list, and must be
+    // followed by a space, so that values such as 1.5 mg, or 2.5mg, are not taken as
+    // leaders.
+
+    let token_len = body.find(|c: char| !(c.is_ascii_digit() || c == '.')).unwrap_or(body.len());
+    let token = &body[..token_len];
+    let parts: Vec<&str> = token.trim_end_matches('.').split('.').collect();
+    if parts.iter().any(|p| p.is_empty() || p.len() > 3) {
+        return None;
+    }
+    let value: u32 = parts.last()?.parse().ok()?;
+    let after = &body[token_len..];
+
+    if enclosed {
+        if parts.len() > 1 || token.ends_with('.') {
+            return None;
+        }
+        let rest = after.strip_prefix(')').or_else(|| after.strip_prefix('）'))?;
+        return Some(leader_of(line, rest, Style { kind: Kind::Number, form: Form::Enclosed, depth: 1 }, value));
+    }
+
+    if parts.len() == 1 {
+        if token.ends_with('.') {
+            return Some(leader_of(line, after, Style { kind: Kind::Number, form: Form::Dot, depth: 1 }, value));
+        }
+        let rest = after.strip_prefix(')')?;
+        return Some(leader_of(line, rest, Style { kind: Kind::Number, form: Form::Paren, depth: 1 }, value));
+    }
+
+    if !levels.iter().any(|l| l.style.kind == Kind::Number) {
+        return None;
+    }
+    let rest = after.strip_prefix(')').unwrap_or(after);
+    if !(rest.is_empty() || rest.starts_with(char::is_whitespace)) {
+        return None;
+    }
+    Some(leader_of(line, rest, Style { kind: Kind::Number, form: Form::Dot, depth: parts.len() }, value))
+}
+
+
+fn find_letter<'a>(line: &'a str, body: &'a str, enclosed: bool, levels: &[Level]) -> Option<Leader<'a>> {
+
+    // A single letter, or a roman numeral, followed by ')', or by '.' and a space
+    // (so that 'e.g.' is not a leader), or enclosed in brackets.
+
+    let token_len = body.find(|c: char| !c.is_ascii_alphabetic()).unwrap_or(body.len());
+    if token_len > 5 {
+        return None;
+    }
+    let token = &body[..token_len];
+    let after = &body[token_len..];
+
+    let (form, rest) = if enclosed {
+        (Form::Enclosed, after.strip_prefix(')').or_else(|| after.strip_prefix('）'))?)
+    }
+    else if let Some(rest) = after.strip_prefix(')') {
+        (Form::Paren, rest)
+    }
+    else {
+        let rest = after.strip_prefix('.')?;
+        if !(rest.is_empty() || rest.starts_with(char::is_whitespace)) {
+            return None;
+        }
+        (Form::Dot, rest)
+    };
+
+    let upper = token.chars().all(|c| c.is_ascii_uppercase());
+    if !upper && !token.chars().all(|c| c.is_ascii_lowercase()) {
+        return None;
+    }
+    let (alpha_kind, roman_kind) = if upper { (Kind::UpperAlpha, Kind::UpperRoman) } else { (Kind::LowerAlpha, Kind::LowerRoman) };
+    let alpha = Style { kind: alpha_kind, form, depth: 1 };
+    let roman = Style { kind: roman_kind, form, depth: 1 };
+    let roman_value = ROMANS.iter().position(|r| r.eq_ignore_ascii_case(token)).map(|p| p as u32 + 1);
+
+    if token_len > 1 {
+        return Some(leader_of(line, rest, roman, roman_value?));
+    }
+
+    // A single i, v or x may be a letter or a roman numeral, depending on the
+    // item before it at the same style, if any. Otherwise 'i' is taken as the
+    // start of a list of roman numerals, and 'v' or 'x' as letters.
+
+    let alpha_value = token.to_ascii_lowercase().as_bytes()[0] as u32 - 'a' as u32 + 1;
+    let follows = |style: Style, value: u32| levels.iter().any(|l| l.style == style && l.value + 1 == value);
+
+    let style_and_value = match roman_value {
+        Some(_) if follows(alpha, alpha_value) => (alpha, alpha_value),
+        Some(v) if follows(roman, v) || v == 1 => (roman, v),
+        _ => (alpha, alpha_value),
+    };
+    Some(leader_of(line, rest, style_and_value.0, style_and_value.1))
+}
+
+
+fn leader_of<'a>(line: &'a str, rest: &'a str, style: Style, value: u32) -> Leader<'a> {
+    let leader = &line[..line.len() - rest.len()];
+    Leader { style, value, leader, rest: rest.trim() }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seqs(crits: &[Criterion]) -> Vec<(&str, &str, &str)> {
+        crits.iter().map(|c| (c.seq_num.as_str(), c.leader.as_str(), c.text.as_str())).collect()
+    }
+
+    #[test]
+    fn check_numbered_list_with_continuations() {
+        let text = "1. Age 18 or over\n2) wrongly styled\n\n2. Signed consent,\n   given in writing\n3.Able to attend";
+        let crits = parse_criteria(text);
+        assert_eq!(seqs(&crits), vec![
+            ("1", "1.", "Age 18 or over"),
+            ("1.1", "2)", "wrongly styled"),
+            ("2", "2.", "Signed consent, given in writing"),
+            ("3", "3.", "Able to attend")]);
+        assert_eq!(crits[1].indent_level, 2);
+    }
+
+    #[test]
+    fn check_inequalities_are_not_bullets() {
+        let text = ">18 years old\n>= 50 kg body weight\n> Able to swallow tablets";
+        assert_eq!(seqs(&parse_criteria(text)), vec![
+            ("1", "", ">18 years old"),
+            ("2", "", ">= 50 kg body weight"),
+            ("3", ">", "Able to swallow tablets")]);
+    }
+
+    #[test]
+    fn check_nested_items() {
+        let text = "1. Disease characteristics:\n- histologically confirmed\n- measurable\n(a) by CT\n(b) by MRI\n2. Prior therapy:\n• none";
+        let crits = parse_criteria(text);
+        assert_eq!(seqs(&crits), vec![
+            ("1", "1.", "Disease characteristics:"),
+            ("1.1", "-", "histologically confirmed"),
+            ("1.2", "-", "measurable"),
+            ("1.2.1", "(a)", "by CT"),
+            ("1.2.2", "(b)", "by MRI"),
+            ("2", "2.", "Prior therapy:"),
+            ("2.1", "•", "none")]);
+    }
+
+    #[test]
+    fn check_leader_styles() {
+        let text = "(1) one\n（2） two\n(-) dash\n1.1 sub\n1.2. sub\n(i) first\n(ii) second\no circle";
+        let leaders: Vec<String> = parse_criteria(text).into_iter().map(|c| format!("{} {}", c.seq_num, c.leader)).collect();
+        assert_eq!(leaders, vec!["1 (1)", "2 （2）", "2.1 (-)", "2.1.1 1.1", "2.1.2 1.2.", "2.1.2.1 (i)", "2.1.2.2 (ii)", "2.1.2.2.1 o"]);
+    }
+
+    #[test]
+    fn check_letters_and_romans() {
+        let text = "g) seven\nh) eight\ni) nine\nj) ten";
+        let leaders: Vec<String> = parse_criteria(text).into_iter().map(|c| c.seq_num).collect();
+        assert_eq!(leaders, vec!["1", "2", "3", "4"]);
+
+        let text = "a) letter\ni) roman\nii) roman\nb) letter";
+        let leaders: Vec<String> = parse_criteria(text).into_iter().map(|c| c.seq_num).collect();
+        assert_eq!(leaders, vec!["1", "1.1", "1.2", "2"]);
+    }
+
+    #[test]
+    fn check_values_that_are_not_leaders() {
+        for line in ["1.5 mg/kg daily", "0.5 ml", "e.g. diabetes", "No. of lesions", "2 weeks", "Dr. Smith"] {
+            let crits = parse_criteria(line);
+            assert_eq!(crits.len(), 1);
+            assert_eq!(crits[0].leader, "", "{}", line);
+            assert_eq!(crits[0].text, line);
+        }
+    }
+
+    #[test]
+    fn check_lines_without_leaders() {
+        let crits = parse_criteria("Adults\nNo prior surgery\n\nAble to consent");
+        assert_eq!(seqs(&crits), vec![("1", "", "Adults"), ("2", "", "No prior surgery"), ("3", "", "Able to consent")]);
+
+        let crits = parse_criteria("Healthy volunteers.\n1. aged 18-65\n2. BMI < 30");
+        assert_eq!(seqs(&crits), vec![("1", "", "Healthy volunteers."), ("2", "1.", "aged 18-65"), ("3", "2.", "BMI < 30")]);
+
+        let crits = parse_criteria("Part A:\n1. aged 18-65\n2. BMI < 30\nPart B:\n1. aged over 65");
+        assert_eq!(seqs(&crits), vec![
+            ("1", "", "Part A:"),
+            ("1.1", "1.", "aged 18-65"),
+            ("1.2", "2.", "BMI < 30"),
+            ("2", "", "Part B:"),
+            ("2.1", "1.", "aged over 65")]);
+    }
+}
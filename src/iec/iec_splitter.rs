@@ -0,0 +1,342 @@
+// Splits the criteria text of a study into blocks of inclusion and exclusion criteria,
+// at the section headings within it, rather than at the first 'exclusion' in the text.
+// A heading is a line that starts (after any qualifier, e.g. 'Key', or cohort label,
+// e.g. 'Part A') with 'inclusion' or 'exclusion', and that is either terminated by a
+// colon (when any text after the colon begins the block) or consists only of the
+// heading, e.g. 'Exclusion Criteria', 'Inclusion criteria for Part B'. Text before the
+// first heading, or in a study without headings, is an unsplit eligibility block.
+//
+// Studies with several cohorts may repeat the headings for each cohort. The cohort is
+// taken from the heading itself ('Part A Inclusion Criteria:'), or from a line naming
+// the cohort (e.g. 'Cohort 2:') immediately above a heading, which then applies to the
+// headings that follow until the next such line.
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IecType {
+    Eligibility,
+    Inclusion,
+    Exclusion,
+}
+
+impl IecType {
+
+    pub fn name(&self) -> &str {
+        match self {
+            IecType::Eligibility => "eligibility",
+            IecType::Inclusion => "inclusion",
+            IecType::Exclusion => "exclusion",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CritBlock {
+    pub iec_type: IecType,
+    pub cohort: Option<String>,
+    pub text: String,
+}
+
+struct Heading {
+    iec_type: IecType,
+    cohort: Option<String>,
+    rest: String,
+}
+
+// Words that may precede 'inclusion' or 'exclusion' in a heading without being part of
+// a cohort label, and the words that start a cohort label.
+
+const QUALIFIERS: [&str; 20] = ["key", "main", "major", "general", "additional", "specific", "principal",
+    "other", "study", "patient", "patients", "participant", "participants", "subject", "subjects",
+    "clinical", "core", "primary", "secondary", "further"];
+
+const COHORT_WORDS: [&str; 20] = ["part", "parts", "cohort", "cohorts", "arm", "arms", "group", "groups",
+    "stage", "phase", "step", "population", "panel", "substudy", "sub-study", "period", "module",
+    "segment", "dose", "expansion"];
+
+const NEGATIONS: [&str; 5] = ["no", "not", "without", "any", "none"];
+
+
+pub fn split_criteria(text: &str) -> Vec<CritBlock> {
+
+    let lines: Vec<&str> = text.lines().collect();
+    let mut blocks = vec![CritBlock { iec_type: IecType::Eligibility, cohort: None, text: "".to_string() }];
+    let mut context: Option<String> = None;
+
+    for (i, line) in lines.iter().enumerate() {
+
+        if let Some(h) = parse_heading(line) {
+            if h.iec_type == IecType::Eligibility {
+                // A general heading, e.g. 'Inclusion and Exclusion Criteria:', is dropped.
+                add_line(blocks.last_mut().unwrap(), &h.rest);
+            }
+            else {
+                let cohort = h.cohort.or_else(|| context.clone());
+                blocks.push(CritBlock { iec_type: h.iec_type, cohort, text: h.rest });
+            }
+            continue;
+        }
+
+        if let Some(label) = parse_cohort_line(line) && heading_follows(&lines[i + 1..]) {
+            context = Some(label);
+            continue;
+        }
+
+        add_line(blocks.last_mut().unwrap(), line);
+    }
+
+    // Empty blocks (e.g. a heading directly followed by another) are dropped, and
+    // adjacent blocks of the same type and cohort merged.
+
+    let mut result: Vec<CritBlock> = Vec::new();
+    for mut b in blocks {
+        b.text = b.text.trim().to_string();
+        if b.text.is_empty() {
+            continue;
+        }
+        match result.last_mut() {
+            Some(last) if last.iec_type == b.iec_type && last.cohort == b.cohort => {
+                last.text.push('\n');
+                last.text.push_str(&b.text);
+            },
+            _ => result.push(b),
+        }
+    }
+    result
+}
+
+
+fn add_line(block: &mut CritBlock, line: &str) {
+    if !block.text.is_empty() {
+        block.text.push('\n');
+    }
+    block.text.push_str(line);
+}
+
+
+fn heading_follows(lines: &[&str]) -> bool {
+    lines.iter().find(|l| !l.trim().is_empty())
+        .and_then(|l| parse_heading(l))
+        .is_some_and(|h| h.iec_type != IecType::Eligibility)
+}
+
+
+fn strip_decoration(line: &str) -> &str {
+
+    // Headings are sometimes bulleted, or emphasised with asterisks or underscores.
+
+    line.trim()
+        .trim_start_matches(['-', '*', '•', '#', '='])
+        .trim_matches(['*', '_'])
+        .trim()
+}
+
+
+fn parse_heading(line: &str) -> Option<Heading> {
+
+    let t = strip_decoration(line);
+    let (head, rest, colon) = match t.find(':') {
+        Some(i) => (&t[..i], t[i + 1..].trim_matches(['*', '_']).trim(), true),
+        None => (t, "", false),
+    };
+    let head = head.trim().trim_end_matches(['.', '*', '_']).trim();
+    if head.is_empty() || head.len() > 100 {
+        return None;
+    }
+
+    // Only ascii characters are lower cased, so that positions are the same in both.
+
+    let lower = head.to_ascii_lowercase();
+    let (iec_type, kw_start, kw_end) = match (find_word(&lower, "inclusion"), find_word(&lower, "exclusion")) {
+        (Some(i), Some(e)) => {
+            let (first, second) = if i < e { (i, e) } else { (e, i) };
+            let between = lower[first + 9..second].trim();
+            if !["and", "/", "&", "-", "or", "and/or", ""].contains(&between) {
+                return None;
+            }
+            (IecType::Eligibility, first, second + 9)
+        },
+        (Some(i), None) => (IecType::Inclusion, i, i + 9),
+        (None, Some(e)) => (IecType::Exclusion, e, e + 9),
+        (None, None) => return None,
+    };
+
+    let mut after = head[kw_end..].trim_start();
+    let has_criteria = after.to_ascii_lowercase().starts_with("criteri");
+    if has_criteria {
+        let n = after.find(|c: char| !c.is_ascii_alphabetic()).unwrap_or(after.len());
+        after = &after[n..];
+    }
+    if !colon && !has_criteria {
+        return None;
+    }
+
+    let prefix_label = label_from_prefix(&head[..kw_start])?;
+    let suffix_label = label_from_suffix(after, colon && has_criteria)?;
+    let cohort = match (prefix_label, suffix_label) {
+        (Some(p), Some(s)) => Some(format!("{} {}", p, s)),
+        (p, s) => p.or(s),
+    };
+
+    Some(Heading { iec_type, cohort, rest: rest.to_string() })
+}
+
+
+fn parse_cohort_line(line: &str) -> Option<String> {
+
+    // A line naming a cohort, e.g. 'Part A:' or 'Cohort 2 (dose expansion):'.
+
+    let t = strip_decoration(line);
+    let label = t.strip_suffix(':')?.trim();
+    if label.contains(':') || !is_cohort_label(label) {
+        return None;
+    }
+    Some(label.to_string())
+}
+
+
+fn find_word(lower: &str, word: &str) -> Option<usize> {
+
+    // The first occurrence of the word that is not part of a longer word.
+
+    lower.match_indices(word).map(|(i, _)| i).find(|&i| {
+        let before = lower[..i].chars().next_back();
+        !before.is_some_and(|c| c.is_ascii_alphabetic())
+    })
+}
+
+
+fn clean_label(s: &str) -> &str {
+    s.trim_matches(|c: char| c.is_whitespace() || "-–—,;()[]/".contains(c))
+}
+
+
+fn label_from_prefix(prefix: &str) -> Option<Option<String>> {
+
+    // The text before the keyword must be qualifiers, a cohort label, or both.
+    // Returns None if it is not, and otherwise the label, if any.
+
+    let words: Vec<&str> = clean_label(prefix).split_whitespace().collect();
+    let is_qualifier = |w: &&&str| QUALIFIERS.contains(&clean_label(w).to_lowercase().as_str());
+    let start = words.iter().take_while(is_qualifier).count();
+    let end = words.len() - words[start..].iter().rev().take_while(is_qualifier).count();
+    let label = clean_label(&words[start..end].join(" ")).to_string();
+
+    if label.is_empty() {
+        Some(None)
+    }
+    else if is_cohort_label(&label) {
+        Some(Some(label))
+    }
+    else {
+        None
+    }
+}
+
+
+fn label_from_suffix(after: &str, colon_and_criteria: bool) -> Option<Option<String>> {
+
+    // The text after the keyword (and 'criteria') must be a cohort label, which may
+    // be introduced by 'for' or 'in', or be bracketed. Other short descriptions of
+    // the participants concerned are accepted in headings such as
+    // 'Inclusion criteria for healthy volunteers:', unless they include a negation.
+
+    let mut s = clean_label(after);
+    for connector in ["for ", "in ", "applicable to ", "specific to ", "of "] {
+        // The text may not be ascii, so the connector's length need not be a char boundary.
+        if s.len() > connector.len() && s.get(..connector.len()).is_some_and(|p| p.eq_ignore_ascii_case(connector))
+            && let Some(rest) = s.get(connector.len()..) {
+            s = clean_label(rest);
+            break;
+        }
+    }
+
+    let lower = s.to_lowercase();
+    if s.is_empty() || lower == "all" || lower.starts_with("all ") || lower.starts_with("both ") {
+        return Some(None);
+    }
+    if is_cohort_label(s) {
+        return Some(Some(s.to_string()));
+    }
+    let negated = lower.split_whitespace().any(|w| NEGATIONS.contains(&w));
+    if colon_and_criteria && s.len() <= 60 && !negated {
+        return Some(Some(s.to_string()));
+    }
+    None
+}
+
+
+fn is_cohort_label(s: &str) -> bool {
+    let words: Vec<String> = s.split_whitespace().map(|w| clean_label(w).to_lowercase()).collect();
+    !words.is_empty() && words.len() <= 6 && s.len() <= 60 && COHORT_WORDS.contains(&words[0].as_str())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary(blocks: &[CritBlock]) -> Vec<(&str, Option<&str>, &str)> {
+        blocks.iter().map(|b| (b.iec_type.name(), b.cohort.as_deref(), b.text.as_str())).collect()
+    }
+
+    #[test]
+    fn check_simple_split() {
+        let text = "Inclusion Criteria:\n\n- Adults\n- with asthma\n\nKey Exclusion Criteria:\n\n- Smokers";
+        assert_eq!(summary(&split_criteria(text)), vec![
+            ("inclusion", None, "- Adults\n- with asthma"),
+            ("exclusion", None, "- Smokers")]);
+    }
+
+    #[test]
+    fn check_non_ascii_headings() {
+        let text = "Inclusion criteria（入选标准）:\n- Adults\nExclusion criteria:\n- Smokers";
+        assert_eq!(summary(&split_criteria(text)), vec![
+            ("inclusion", Some("（入选标准）"), "- Adults"),
+            ("exclusion", None, "- Smokers")]);
+
+        let text = "Inclusion criteria élèves:\n- Adults";
+        assert_eq!(summary(&split_criteria(text)), vec![("inclusion", Some("élèves"), "- Adults")]);
+    }
+
+    #[test]
+    fn check_exclusion_in_text_is_not_a_heading() {
+        let text = "INCLUSION CRITERIA\nPatients with no exclusion of prior therapy\nExclusion of pregnancy: required\nExclusion criteria: Smokers";
+        assert_eq!(summary(&split_criteria(text)), vec![
+            ("inclusion", None, "Patients with no exclusion of prior therapy\nExclusion of pregnancy: required"),
+            ("exclusion", None, "Smokers")]);
+
+        let text = "Adults with no exclusion criteria other than age";
+        assert_eq!(summary(&split_criteria(text)), vec![("eligibility", None, text)]);
+    }
+
+    #[test]
+    fn check_cohort_blocks() {
+        let text = "Part A Inclusion Criteria:\n1. healthy\nPart A Exclusion Criteria:\n1. smokers\n\
+                    Inclusion Criteria for Part B:\n1. with asthma\nExclusion criteria (Part B)\n1. none";
+        assert_eq!(summary(&split_criteria(text)), vec![
+            ("inclusion", Some("Part A"), "1. healthy"),
+            ("exclusion", Some("Part A"), "1. smokers"),
+            ("inclusion", Some("Part B"), "1. with asthma"),
+            ("exclusion", Some("Part B"), "1. none")]);
+
+        let text = "Cohort 1:\nInclusion Criteria:\n- adults\nExclusion Criteria:\n- smokers\n\
+                    Cohort 2 (expansion):\nInclusion Criteria:\n- children\nPart A:\n- note";
+        assert_eq!(summary(&split_criteria(text)), vec![
+            ("inclusion", Some("Cohort 1"), "- adults"),
+            ("exclusion", Some("Cohort 1"), "- smokers"),
+            ("inclusion", Some("Cohort 2 (expansion)"), "- children\nPart A:\n- note")]);
+    }
+
+    #[test]
+    fn check_heading_variants() {
+        let text = "Healthy volunteers\nInclusion and Exclusion Criteria:\n**Inclusion Criteria:** aged 18-65\n\
+                    Inclusion criteria for all participants:\n- consent\n- EXCLUSION:\n- smokers";
+        assert_eq!(summary(&split_criteria(text)), vec![
+            ("eligibility", None, "Healthy volunteers"),
+            ("inclusion", None, "aged 18-65\n- consent"),
+            ("exclusion", None, "- smokers")]);
+
+        let text = "Inclusion criteria for healthy volunteers:\n- aged 18-65";
+        assert_eq!(summary(&split_criteria(text)), vec![("inclusion", Some("healthy volunteers"), "- aged 18-65")]);
+    }
+}
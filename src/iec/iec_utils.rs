@@ -0,0 +1,110 @@
+use crate::mdr::executor::SqlExecutor;
+use crate::AppError;
+use log::info;
+
+// Sql is executed by the common mdr executor, so that each step is timed, and
+// written to the dry run file if one is active. The field is either inc_crit
+// or exc_crit, and its flag has_incl or has_excl respectively.
+
+pub async fn update_crit(field: &str, set_expr: &str, sql_where: &str, fb: &str, ex: &SqlExecutor) -> Result<u64, AppError> {
+
+    let sql = format!("update ad.iec_base set {} = {} where {};", field, set_expr, sql_where);
+    let r = ex.execute(&sql).await?.rows_affected();
+    if !fb.is_empty() {
+        let recs = if r == 1 {"record"} else {"records"};
+        info!("{} {} {} {}", r, field, recs, fb);
+    }
+    Ok(r)
+}
+
+
+pub async fn trim_crit(field: &str, sql_where: &str, ex: &SqlExecutor) -> Result<(), AppError> {
+
+    update_crit(field, &format!("trim({})", field), sql_where, "", ex).await?;
+    Ok(())
+}
+
+
+pub async fn remove_leading_char_in_crit(field: &str, s: &str, sql_where: &str, ex: &SqlExecutor) -> Result<(), AppError> {
+
+    // The string is removed, as often as it is repeated, from the start of the
+    // field. It is matched as a whole, e.g. '1.' does not match the start of '11.'
+
+    let pattern = leading_pattern(s);
+    let set_expr = format!("regexp_replace({}, '{}', '')", field, pattern);
+    let mut cond = format!("{} ~ '{}'", field, pattern);
+    if !sql_where.is_empty() {
+        cond = format!("{} and {}", sql_where, cond);
+    }
+    let fb = format!("had leading '{}' removed", s);
+    update_crit(field, &set_expr, &cond, &fb, ex).await?;
+    Ok(())
+}
+
+
+fn leading_pattern(s: &str) -> String {
+
+    // A regular expression for one or more repeats of the string at the start of
+    // the field, with the characters special to regular expressions escaped, and
+    // any single quotes doubled, as the pattern is used within a sql string.
+
+    let mut literal = String::new();
+    for c in s.chars() {
+        if r"\^$.|?*+()[]{}".contains(c) {
+            literal.push('\\');
+        }
+        literal.push(c);
+    }
+    format!("^({})+", literal.replace('\'', "''"))
+}
+
+
+pub async fn remove_blank_crit(field: &str, ex: &SqlExecutor) -> Result<(), AppError> {
+
+    // Entries (after trimming) of less than 3 characters are not meaningful.
+
+    let sql_where = format!("length({}) < 3", field);
+    let set_expr = format!("null, {} = false", flag_for(field));
+    update_crit(field, &set_expr, &sql_where, "removed as (almost) blank", ex).await?;
+    Ok(())
+}
+
+
+pub async fn remove_spurious_crit(field: &str, conditions: &str, ex: &SqlExecutor) -> Result<(), AppError> {
+
+    // Short entries that are simply fragments of a header, or that in effect
+    // say there are no criteria, are removed.
+
+    let set_expr = format!("null, {} = false", flag_for(field));
+    let sql_where = format!("length({}) < 10 and ({})", field, conditions);
+    update_crit(field, &set_expr, &sql_where, "removed as spurious fragments", ex).await?;
+    Ok(())
+}
+
+
+pub async fn execute_and_report(sql: &str, fb: &str, ex: &SqlExecutor) -> Result<(), AppError> {
+
+    let r = ex.execute(sql).await?.rows_affected();
+    info!("{} {}", r, fb);
+    Ok(())
+}
+
+
+fn flag_for(field: &str) -> &str {
+    if field == "inc_crit" {"has_incl"} else {"has_excl"}
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_leading_patterns() {
+        assert_eq!(leading_pattern("-"), "^(-)+");
+        assert_eq!(leading_pattern("1."), r"^(1\.)+");
+        assert_eq!(leading_pattern("a)"), r"^(a\))+");
+        assert_eq!(leading_pattern("\\"), r"^(\\)+");
+        assert_eq!(leading_pattern("'"), "^('')+");
+    }
+}
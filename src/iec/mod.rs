@@ -1,9 +1,127 @@
+/***************************************************************************
+ * The import of the inclusion / exclusion criteria (iec). The criteria text
+ * for each study, in ctgov.eligibilities, is copied to ad.iec_base, where
+ *  - studies with no (meaningful) criteria are identified,
+ *  - the remaining text is split, at its inclusion and exclusion headings,
+ *    into blocks of inclusion and exclusion criteria, each labelled with
+ *    the cohort it applies to, if any (see iec_splitter.rs), or, if there
+ *    are no headings, kept as the general criteria,
+ *  - spurious short fragments are removed from each block,
+ *  - each block is parsed into its individual criteria, numbered to show any
+ *    nesting of sub-items, in ad.study_iec (see iec_parser.rs),
+ * and the iec_flag of each study in ad.studies is then set to reflect the
+ * level of criteria available (see iec3::set_iec_flags).
+ *
+ * The sql is run as a single stage, through the mdr executor, so it is
+ * timed and logged in the same way as the mdr stages, and is included in
+ * a dry run. It needs ad.studies, i.e. the mdr import must have been run.
+ ***************************************************************************/
+
+mod iec1;
+mod iec2;
+mod iec3;
+mod iec4;
+mod iec_parser;
+mod iec_splitter;
+mod iec_utils;
+
 use crate::err::AppError;
+use crate::mdr::dry_run;
+use crate::mdr::executor::SqlExecutor;
 use sqlx::postgres::PgPool;
+use log::info;
+
+pub async fn do_iec_import(pool: &PgPool) -> Result<(), AppError> {
+
+    if !dry_run::is_active() {
+        check_iec_tables(pool).await?;
+    }
+
+    let ex = SqlExecutor::new(pool);
+    if ex.start_stage("iec").await? {
+        info!("running iec stage");
+        let outcome = import_iec_data(&ex).await;
+        ex.end_stage(outcome).await?;
+    }
+
+    if !dry_run::is_active() {
+        report_iec_counts(pool).await?;
+    }
+
+    Ok(())
+}
+
+
+async fn import_iec_data(ex: &SqlExecutor) -> Result<(), AppError> {
+
+    iec1::build_iec_table(ex).await?;
+    iec1::load_iec_data(ex).await?;
+
+    iec2::tidy_inc_crit(ex).await?;
+    iec2::tidy_exc_crit(ex).await?;
+
+    iec3::remove_spurious_inc_crit(ex).await?;
+    iec3::remove_spurious_exc_crit(ex).await?;
+    ex.vacuum_table("iec_base").await?;
 
-pub async fn do_iec_import(_pool: &PgPool) -> Result<(), AppError> {  
+    iec4::build_study_iec_table(ex).await?;
+    iec4::parse_iec_data(ex).await?;
+
+    iec3::set_iec_flags(ex).await?;
+    ex.vacuum_table("studies").await?;
 
-    // TO DO! 
     Ok(())
+}
+
+
+async fn check_iec_tables(pool: &PgPool) -> Result<(), AppError> {
+
+    let mut missing = Vec::new();
+    for table in ["ctgov.eligibilities", "ad.studies"] {
+        let sql = format!("select to_regclass('{}') is not null", table);
+        let exists: bool = sqlx::query_scalar(&sql).fetch_one(pool)
+            .await.map_err(|e| AppError::SqlxError(e, sql.clone()))?;
+        if !exists {
+            missing.push(table);
+        }
+    }
+
+    if !missing.is_empty() {
+        return Result::Err(AppError::StageSelectionError(
+            format!("The iec import cannot be run - missing table(s): {}", missing.join(", ")),
+            "Run the mdr import (at least the studies stage) before importing iec data".to_string()));
+    }
+    Ok(())
+}
+
 
-}
\ No newline at end of file
+async fn report_iec_counts(pool: &PgPool) -> Result<(), AppError> {
+
+    // A study may have several records in ad.iec_base, one for each block of criteria.
+
+    let sql = r#"select count(*) filter (where not (elig or inc or exc)),
+                 count(*) filter (where elig), count(*) filter (where inc), count(*) filter (where exc),
+                 count(*) filter (where cohorts > 1)
+                 from (select sd_sid, bool_or(elig_crit is not null) as elig, bool_or(inc_crit is not null) as inc,
+                       bool_or(exc_crit is not null) as exc, count(distinct cohort) as cohorts
+                       from ad.iec_base group by sd_sid) s"#;
+    let (none, elig, inc, exc, cohorts): (i64, i64, i64, i64, i64) = sqlx::query_as(sql).fetch_one(pool)
+        .await.map_err(|e| AppError::SqlxError(e, sql.to_string()))?;
+
+    info!("iec import completed:");
+    info!("{} studies with no criteria", none);
+    info!("{} studies with criteria not split into inclusion and exclusion", elig);
+    info!("{} studies with inclusion criteria", inc);
+    info!("{} studies with exclusion criteria", exc);
+    info!("{} studies with criteria for more than one cohort", cohorts);
+
+    let sql = "select iec_flag, count(*) from ad.studies group by iec_flag order by iec_flag";
+    let flags: Vec<(i32, i64)> = sqlx::query_as(sql).fetch_all(pool)
+        .await.map_err(|e| AppError::SqlxError(e, sql.to_string()))?;
+    for (flag, n) in flags {
+        info!("{} studies with iec_flag {}", n, flag);
+    }
+    info!("");
+
+    Ok(())
+}
@@ -24,12 +24,61 @@ pub async fn run(args: Vec<OsString>) -> Result<(), AppError> {
                               
     let params = setup::get_params(cli_pars, &config_string)?;
     let flags = params.flags;
+
+    if flags.show_plan {
+        for line in mdr::stages::describe_plan(&params.mdr_plan) {
+            println!("{}", line);
+        }
+        return Ok(());
+    }
+
     setup::establish_log(&params)?;
-    let pool = setup::get_db_pool().await?;
+
+    // In a dry run the mdr and iec sql is written to a file in the log folder rather  
+    // than executed, so the pool only connects lazily, and nothing else is run.
+
+    if flags.dry_run {
+        if flags.process_mdr_data || flags.process_iec_data {
+            let pool = setup::get_lazy_db_pool()?;
+            mdr::dry_run::start(&params.log_folder)?;
+            if flags.process_mdr_data {
+                if params.mdr_plan.stage_names().contains(&"restore") {
+                    mdr::dry_run::note(&format!("restore of AACT snapshot {} (pg_restore) not included", params.restore_pars.dump_path))?;
+                }
+                mdr::do_mdr_import(&params.data_date, &params.mdr_plan, flags.resume, params.transaction_mode, params.jobs, &pool).await?;
+            }
+            if flags.process_iec_data {
+                iec::do_iec_import(&pool).await?;
+            }
+            mdr::dry_run::end()?;
+        }
+        return Ok(());
+    }
+
+    // The AACT snapshot must be restored before the pool is created,
+    // as the database itself may need to be created.
+
+    if flags.process_mdr_data && params.mdr_plan.stage_names().contains(&"restore") {
+        mdr::restore::restore_aact(&params.restore_pars).await?;
+    }
+
+    let pool = setup::get_db_pool(params.jobs).await?;
+
+    // Listing and pruning of checkpoints are done on their own.
+
+    if flags.list_checkpoints || !params.prune_checkpoints.is_empty() {
+        if !params.prune_checkpoints.is_empty() {
+            mdr::prune_checkpoints(&params.prune_checkpoints, &pool).await?;
+        }
+        for line in mdr::list_checkpoints(&pool).await? {
+            println!("{}", line);
+        }
+        return Ok(());
+    }
             
             
     if flags.process_mdr_data {
-        mdr::do_mdr_import(&params.data_date, &pool).await?;
+        mdr::do_mdr_import(&params.data_date, &params.mdr_plan, flags.resume, params.transaction_mode, params.jobs, &pool).await?;
     }
      
     if flags.process_iec_data {
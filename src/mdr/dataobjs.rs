@@ -1,12 +1,66 @@
 
-use super::utils;
 
-use sqlx::{Pool, Postgres};
+use super::executor::SqlExecutor;
+use super::links;
 use crate::AppError;
 use log::info;
 
+// Each study has a registry entry object, and a results summary object if results have
+// been posted, both managed by the NLM. The sd_oid of an object is '<sd_sid> :: <object
+// type> :: <object title>', e.g. 'NCT00000102 :: 13 :: CTG registry entry'.
 
-pub async fn build_data_objects_table (pool: &Pool<Postgres>) -> Result<(), AppError> {  
+struct RegistryObject {
+    type_id: i32,
+    title: &'static str,
+    url_suffix: &'static str,
+    year_column: &'static str,
+    month_column: &'static str,
+    date_type_column: &'static str,
+}
+
+static REGISTRY_OBJECTS: [RegistryObject; 2] = [
+    RegistryObject { type_id: 13, title: "CTG registry entry", url_suffix: "",
+                     year_column: "reg_year", month_column: "reg_month", date_type_column: "reg_date_type" },
+    RegistryObject { type_id: 28, title: "CTG results summary", url_suffix: "?tab=results",
+                     year_column: "res_year", month_column: "res_month", date_type_column: "res_date_type" },
+];
+
+// Object and instance codes (from the mdr lookup tables).
+
+const OBJ_CLASS_TEXT: i32 = 23;
+const ACCESS_PUBLIC_ON_SCREEN: i32 = 11;
+const DOI_STATUS_NONE: i32 = 9;
+const NLM_ORG_ID: i32 = 100133;
+const NLM_ORG_NAME: &str = "National Library of Medicine";
+const NLM_ROR_ID: &str = "0060t0j89";
+const CTG_SYSTEM_ID: i32 = 100120;
+const CTG_SYSTEM: &str = "ClinicalTrials.gov";
+const CTG_STUDY_URL: &str = "https://clinicaltrials.gov/study/";
+const RESOURCE_WEB_TEXT: i32 = 35;
+const TITLE_TYPE_SHORT_NAME_OBJECT: i32 = 22;
+const DATE_AVAILABLE: i32 = 12;
+
+// Document and IPD object codes (from the mdr lookup tables).
+
+const OBJ_CLASS_DATASET: i32 = 14;
+const OBJ_CLASS_SOFTWARE: i32 = 19;
+const OBJ_STUDY_PROTOCOL: i32 = 11;
+const OBJ_ICF: i32 = 18;
+const OBJ_SAP: i32 = 22;
+const OBJ_CSR: i32 = 26;
+const OBJ_ANALYTIC_CODE: i32 = 29;
+const OBJ_OTHER_DOCUMENT: i32 = 37;
+const OBJ_PROTOCOL_AND_SAP: i32 = 74;
+const OBJ_IPD: i32 = 80;
+const ACCESS_PUBLIC_DOWNLOAD: i32 = 12;
+const ACCESS_CASE_BY_CASE: i32 = 15;
+const RESOURCE_PDF: i32 = 11;
+const DEIDENT_NOT_KNOWN: i32 = 0;
+const DEIDENT_APPLIED: i32 = 2;
+const CONSENT_NOT_KNOWN: i32 = 0;
+
+
+pub async fn build_data_objects_table (ex: &SqlExecutor) -> Result<(), AppError> {  
 
     let sql = r#"DROP TABLE IF EXISTS ad.data_objects;
     CREATE TABLE ad.data_objects(
@@ -40,14 +94,151 @@ pub async fn build_data_objects_table (pool: &Pool<Postgres>) -> Result<(), AppE
     CREATE INDEX data_objects_sid ON ad.data_objects(sd_sid);
     "#;
 
-    utils::execute_sql(sql, pool).await?;
+    ex.execute(sql).await?;
     info!("data objects table (re)created");
     
     Ok(())
 
 }
 
-pub async fn build_datasets_table (pool: &Pool<Postgres>) -> Result<(), AppError> {  
+
+pub async fn load_data_objects_data (ex: &SqlExecutor) -> Result<(), AppError> {
+
+    // The display title is that of the study followed by the object title. The
+    // publication year is that in which the entry, or the results, were first posted.
+
+    for obj in REGISTRY_OBJECTS.iter() {
+        let sql = format!(r#"insert into ad.data_objects (sd_oid, sd_sid, title, display_title,
+            doi_status_id, publication_year, object_class_id, object_type_id,
+            managing_org_id, managing_org, managing_org_ror_id, lang_code, access_type_id,
+            eosc_category, add_study_contribs, add_study_topics, datetime_of_data_fetch)
+            select {oid}, sd_sid, '{title}', coalesce(display_title, sd_sid)||' :: {title}',
+            {doi}, {year}, {class}, {type_id},
+            {org_id}, '{org}', '{ror}', 'en', {access},
+            0, true, true, dt_of_data
+            from ad.studies
+            where {year} is not null
+            order by sd_sid;"#,
+            oid = oid_sql("sd_sid", obj), title = obj.title, doi = DOI_STATUS_NONE, year = obj.year_column,
+            class = OBJ_CLASS_TEXT, type_id = obj.type_id, org_id = NLM_ORG_ID, org = NLM_ORG_NAME,
+            ror = NLM_ROR_ID, access = ACCESS_PUBLIC_ON_SCREEN);
+        execute_objs_fb(&sql, obj, "data objects created", ex).await?;
+    }
+    info!("");
+
+    load_document_objects(ex).await?;
+
+    ex.vacuum_table("data_objects").await?;
+    Ok(())
+}
+
+
+async fn load_document_objects (ex: &SqlExecutor) -> Result<(), AppError> {
+
+    // The documents provided to CTG (ctgov.provided_documents) are each an object, as
+    // is the IPD of each study that plans to share it, together with the types of
+    // information (ctgov.ipd_information_types) to be shared with the IPD - unless the
+    // same document has been provided to CTG. The objects are collected, with their urls
+    // and the text describing the IPD sharing, in ad.temp_doc_objects, which is also read
+    // by the obj_instances and datasets stages.
+
+    let sql = r#"SET client_min_messages TO WARNING;
+    DROP TABLE IF EXISTS ad.temp_doc_objects;
+    CREATE TABLE ad.temp_doc_objects(
+      id                     INT             GENERATED ALWAYS AS IDENTITY PRIMARY KEY
+    , sd_sid                 VARCHAR         NOT NULL
+    , sd_oid                 VARCHAR         NULL
+    , object_type_id         INT             NULL
+    , title                  VARCHAR         NULL
+    , doc_date               DATE            NULL
+    , url                    VARCHAR         NULL
+    , access_type_id         INT             NULL
+    , access_details         VARCHAR         NULL
+    , sharing_text           VARCHAR         NULL
+    , source                 VARCHAR         NOT NULL
+    );"#;
+    ex.execute(sql).await?;
+
+    let sql = format!(r#"insert into ad.temp_doc_objects(sd_sid, title, doc_date, url, access_type_id, source)
+        select nct_id, {}, document_date, url, {}, 'provided'
+        from ctgov.provided_documents
+        order by nct_id, id;"#, links::ipd_type_sql("document_type"), ACCESS_PUBLIC_DOWNLOAD);
+    execute_docs_fb(&sql, "found from ctgov provided documents", ex).await?;
+
+    let sharing = r#"trim(coalesce(s.plan_to_share_ipd_description, '')||coalesce(' Time frame: '||s.ipd_time_frame, '')
+                     ||coalesce(' Access criteria: '||s.ipd_access_criteria, ''))"#;
+    let sql = format!(r#"insert into ad.temp_doc_objects(sd_sid, title, url, access_type_id, access_details, sharing_text, source)
+        select nct_id, 'Individual participant data set', s.ipd_url, {}, s.ipd_access_criteria, {}, 'ipd'
+        from ctgov.studies s
+        where s.plan_to_share_ipd = 'YES'
+        order by s.nct_id;"#, ACCESS_CASE_BY_CASE, sharing);
+    execute_docs_fb(&sql, "found from studies planning to share ipd", ex).await?;
+
+    let sql = format!(r#"insert into ad.temp_doc_objects(sd_sid, title, url, access_type_id, access_details, sharing_text, source)
+        select t.nct_id, {}, s.ipd_url, {}, s.ipd_access_criteria, {}, 'ipd information'
+        from ctgov.ipd_information_types t
+        inner join ctgov.studies s
+        on t.nct_id = s.nct_id
+        where s.plan_to_share_ipd = 'YES'
+        order by t.nct_id, t.id;"#, links::ipd_type_sql("t.name"), ACCESS_CASE_BY_CASE, sharing);
+    execute_docs_fb(&sql, "found from ctgov ipd information types", ex).await?;
+
+    // A provided 'Study protocol and statistical analysis plan' covers both a planned
+    // protocol and a planned SAP, hence the comparison of the titles.
+
+    execute_docs_fb(r#"delete from ad.temp_doc_objects a
+        using ad.temp_doc_objects b
+        where a.sd_sid = b.sd_sid
+        and a.source = 'ipd information'
+        and b.source = 'provided'
+        and position(lower(a.title) in lower(b.title)) > 0;"#, "removed as already provided to CTG", ex).await?;
+    info!("");
+
+    let sql = format!(r#"update ad.temp_doc_objects
+        set object_type_id = {},
+        title = title||coalesce(' ('||doc_date::varchar||')', '');"#, doc_object_type_sql("title"));
+    execute_docs_fb(&sql, "had object types and titles assigned", ex).await?;
+    execute_docs_fb("update ad.temp_doc_objects set sd_oid = sd_sid||' :: '||object_type_id::varchar||' :: '||title;",
+                    "had sd_oids assigned", ex).await?;
+    execute_docs_fb(r#"delete from ad.temp_doc_objects a
+        using ad.temp_doc_objects b
+        where a.sd_oid = b.sd_oid
+        and a.id > b.id;"#, "removed as duplicates", ex).await?;
+    links::normalise_urls("temp_doc_objects", "url", "document object", ex).await?;
+    info!("");
+
+    // Documents provided to CTG are managed by the NLM, but the IPD and the documents
+    // shared with it by the study's sponsor.
+
+    let sql = format!(r#"insert into ad.data_objects (sd_oid, sd_sid, title, display_title,
+        doi_status_id, publication_year, object_class_id, object_type_id,
+        managing_org_id, managing_org, managing_org_ror_id, lang_code, access_type_id, access_details,
+        eosc_category, add_study_contribs, add_study_topics, datetime_of_data_fetch)
+        select d.sd_oid, d.sd_sid, d.title, coalesce(s.display_title, d.sd_sid)||' :: '||d.title,
+        {doi}, extract(year from d.doc_date)::int,
+        case when d.object_type_id = {ipd} then {dataset}
+             when d.object_type_id = {code} then {software}
+             else {text} end,
+        d.object_type_id,
+        case when d.source = 'provided' then {org_id} end,
+        case when d.source = 'provided' then '{org}' end,
+        case when d.source = 'provided' then '{ror}' end,
+        'en', d.access_type_id, d.access_details,
+        0, true, true, s.dt_of_data
+        from ad.temp_doc_objects d
+        inner join ad.studies s
+        on d.sd_sid = s.sd_sid
+        order by d.sd_oid;"#,
+        doi = DOI_STATUS_NONE, ipd = OBJ_IPD, dataset = OBJ_CLASS_DATASET, code = OBJ_ANALYTIC_CODE,
+        software = OBJ_CLASS_SOFTWARE, text = OBJ_CLASS_TEXT, org_id = NLM_ORG_ID, org = NLM_ORG_NAME,
+        ror = NLM_ROR_ID);
+    execute_docs_fb(&sql, "added as data objects", ex).await?;
+    info!("");
+
+    Ok(())
+}
+
+pub async fn build_datasets_table (ex: &SqlExecutor) -> Result<(), AppError> {  
 
     let sql = r#"SET client_min_messages TO WARNING; 
     DROP TABLE IF EXISTS ad.object_datasets;
@@ -76,7 +267,7 @@ pub async fn build_datasets_table (pool: &Pool<Postgres>) -> Result<(), AppError
     CREATE INDEX object_datasets_oid ON ad.object_datasets(sd_oid)
     "#;
 
-    utils::execute_sql(sql, pool).await?;
+    ex.execute(sql).await?;
     info!("object datasets table (re)created");
     
     Ok(())
@@ -84,7 +275,45 @@ pub async fn build_datasets_table (pool: &Pool<Postgres>) -> Result<(), AppError
 }
 
 
-pub async fn build_obj_dates_table (pool: &Pool<Postgres>) -> Result<(), AppError> {  
+pub async fn load_datasets_data (ex: &SqlExecutor) -> Result<(), AppError> {
+
+    // What can be inferred about the de-identification of, and the consent for, each IPD
+    // dataset, from the text describing the IPD sharing. Datasets are only added if
+    // something could be inferred.
+
+    let sql = format!(r#"insert into ad.object_datasets (sd_oid, deident_type_id, deident_direct, deident_hipaa,
+        deident_details, consent_type_id, consent_noncommercial, consent_geog_restrict, consent_details)
+        select sd_oid,
+        case when deident then {deident} else {deident_nk} end,
+        deident, hipaa,
+        case when deident or hipaa then 'Inferred from the IPD sharing statement' end,
+        {consent_nk},
+        noncommercial, geog,
+        case when noncommercial or geog then 'Inferred from the IPD sharing statement' end
+        from
+            (select sd_oid,
+            txt ~ 'de-?identif|anonymi[sz]|pseudonymi[sz]|identifiers? (will be |are )?removed' as deident,
+            txt ~ '\mhipaa\M' as hipaa,
+            txt ~ 'non-?commercial|not? (be )?(used )?(for )?commercial' as noncommercial,
+            txt ~ '(only|not) (be )?(shared |transferred )?(within|outside) (of )?(the )?(country|eu\M|european|us\M|united)' as geog
+            from
+                (select sd_oid, lower(coalesce(sharing_text, '')) as txt
+                 from ad.temp_doc_objects
+                 where object_type_id = {ipd}) t
+            ) f
+        where deident or hipaa or noncommercial or geog
+        order by sd_oid;"#,
+        deident = DEIDENT_APPLIED, deident_nk = DEIDENT_NOT_KNOWN, consent_nk = CONSENT_NOT_KNOWN, ipd = OBJ_IPD);
+    let r = ex.execute(&sql).await?.rows_affected();
+    info!("{} object datasets added, from IPD sharing statements", r);
+    info!("");
+
+    ex.vacuum_table("object_datasets").await?;
+    Ok(())
+}
+
+
+pub async fn build_obj_dates_table (ex: &SqlExecutor) -> Result<(), AppError> {  
 
     let sql = r#"SET client_min_messages TO WARNING; 
     DROP TABLE IF EXISTS ad.object_dates;
@@ -105,14 +334,44 @@ pub async fn build_obj_dates_table (pool: &Pool<Postgres>) -> Result<(), AppErro
     );
     CREATE INDEX object_dates_oid ON ad.object_dates(sd_oid);"#;
 
-    utils::execute_sql(sql, pool).await?;
+    ex.execute(sql).await?;
     info!("object dates table (re)created");
     
     Ok(())
 
 }
 
-pub async fn build_obj_instances_table (pool: &Pool<Postgres>) -> Result<(), AppError> {  
+
+pub async fn load_obj_dates_data (ex: &SqlExecutor) -> Result<(), AppError> {
+
+    // The date each object was first posted, i.e. made available, as a year and
+    // month (ctgov gives some of these dates as estimates).
+
+    for obj in REGISTRY_OBJECTS.iter() {
+        let sql = format!(r#"insert into ad.object_dates (sd_oid, date_type_id, date_is_range,
+            date_as_string, start_year, start_month, details)
+            select o.sd_oid, {date_type}, false,
+            case when s.{month} is null then s.{year}::varchar
+                 else to_char(make_date(s.{year}, s.{month}, 1), 'YYYY Mon') end,
+            s.{year}, s.{month},
+            case when s.{date_type_col} = 'e' then 'estimated' end
+            from ad.data_objects o
+            inner join ad.studies s
+            on o.sd_sid = s.sd_sid
+            where o.object_type_id = {type_id}
+            and s.{year} is not null
+            order by o.sd_oid;"#,
+            date_type = DATE_AVAILABLE, year = obj.year_column, month = obj.month_column,
+            date_type_col = obj.date_type_column, type_id = obj.type_id);
+        execute_objs_fb(&sql, obj, "object dates added", ex).await?;
+    }
+    info!("");
+
+    ex.vacuum_table("object_dates").await?;
+    Ok(())
+}
+
+pub async fn build_obj_instances_table (ex: &SqlExecutor) -> Result<(), AppError> {  
 
     let sql = r#"SET client_min_messages TO WARNING; 
     DROP TABLE IF EXISTS ad.object_instances;
@@ -133,7 +392,7 @@ pub async fn build_obj_instances_table (pool: &Pool<Postgres>) -> Result<(), App
     );
     CREATE INDEX object_instances_oid ON ad.object_instances(sd_oid);"#;
 
-    utils::execute_sql(sql, pool).await?;
+    ex.execute(sql).await?;
     info!("object instances table (re)created");
     
     Ok(())
@@ -141,7 +400,46 @@ pub async fn build_obj_instances_table (pool: &Pool<Postgres>) -> Result<(), App
 }
 
 
-pub async fn build_obj_titles_table (pool: &Pool<Postgres>) -> Result<(), AppError> {  
+pub async fn load_obj_instances_data (ex: &SqlExecutor) -> Result<(), AppError> {
+
+    // Each object is available as a page (or tab) of the study's record on the CTG web site.
+
+    for obj in REGISTRY_OBJECTS.iter() {
+        let sql = format!(r#"insert into ad.object_instances (sd_oid, system_id, system, url,
+            url_accessible, resource_type_id)
+            select sd_oid, {system_id}, '{system}', '{url}'||sd_sid||'{suffix}',
+            true, {resource}
+            from ad.data_objects
+            where object_type_id = {type_id}
+            order by sd_oid;"#,
+            system_id = CTG_SYSTEM_ID, system = CTG_SYSTEM, url = CTG_STUDY_URL, suffix = obj.url_suffix,
+            resource = RESOURCE_WEB_TEXT, type_id = obj.type_id);
+        execute_objs_fb(&sql, obj, "object instances added", ex).await?;
+    }
+
+    // Provided documents are pdf files held by CTG, while the url given for the IPD
+    // (and the documents shared with it) is normally the web page of a data sharing platform.
+
+    let sql = format!(r#"insert into ad.object_instances (sd_oid, system_id, system, url,
+        url_accessible, resource_type_id)
+        select sd_oid,
+        case when source = 'provided' then {system_id} end,
+        case when source = 'provided' then '{system}' end,
+        url, true,
+        case when source = 'provided' then {pdf} else {web} end
+        from ad.temp_doc_objects
+        where url is not null
+        order by sd_oid;"#,
+        system_id = CTG_SYSTEM_ID, system = CTG_SYSTEM, pdf = RESOURCE_PDF, web = RESOURCE_WEB_TEXT);
+    execute_docs_fb(&sql, "added as object instances", ex).await?;
+    info!("");
+
+    ex.vacuum_table("object_instances").await?;
+    Ok(())
+}
+
+
+pub async fn build_obj_titles_table (ex: &SqlExecutor) -> Result<(), AppError> {  
 
     let sql = r#"SET client_min_messages TO WARNING; 
     DROP TABLE IF EXISTS ad.object_titles;
@@ -158,9 +456,66 @@ pub async fn build_obj_titles_table (pool: &Pool<Postgres>) -> Result<(), AppErr
     );
     CREATE INDEX object_titles_oid ON ad.object_titles(sd_oid);"#;
 
-    utils::execute_sql(sql, pool).await?;
+    ex.execute(sql).await?;
     info!("object titles table (re)created");
     
     Ok(())
 
 }
+
+
+pub async fn load_obj_titles_data (ex: &SqlExecutor) -> Result<(), AppError> {
+
+    // The single (default) title of each object is its display title.
+
+    let sql = format!(r#"insert into ad.object_titles (sd_oid, title_type_id, title_text,
+        lang_code, is_default)
+        select sd_oid, {}, display_title, 'en', true
+        from ad.data_objects
+        order by sd_oid;"#, TITLE_TYPE_SHORT_NAME_OBJECT);
+    let r = ex.execute(&sql).await?.rows_affected();
+    info!("{} object titles added", r);
+    info!("");
+
+    ex.vacuum_table("object_titles").await?;
+    Ok(())
+}
+
+
+fn oid_sql (sid_column: &str, obj: &RegistryObject) -> String {
+    format!("{}||' :: {} :: {}'", sid_column, obj.type_id, obj.title)
+}
+
+
+fn doc_object_type_sql (column: &str) -> String {
+
+    // The column holds one of the ipd types of links::ipd_type_sql.
+
+    format!(r#"case {}
+            when 'Study protocol and statistical analysis plan' then {}
+            when 'Study protocol' then {}
+            when 'Statistical analysis plan' then {}
+            when 'Informed consent form' then {}
+            when 'Clinical study report' then {}
+            when 'Analytic code' then {}
+            when 'Individual participant data set' then {}
+            else {}
+        end"#, column, OBJ_PROTOCOL_AND_SAP, OBJ_STUDY_PROTOCOL, OBJ_SAP, OBJ_ICF, OBJ_CSR,
+        OBJ_ANALYTIC_CODE, OBJ_IPD, OBJ_OTHER_DOCUMENT)
+}
+
+
+async fn execute_docs_fb (sql: &str, fb: &str, ex: &SqlExecutor) -> Result<(), AppError> {
+
+    let r = ex.execute(sql).await?.rows_affected();
+    info!("{} document objects {}", r, fb);
+    Ok(())
+}
+
+
+async fn execute_objs_fb (sql: &str, obj: &RegistryObject, fb: &str, ex: &SqlExecutor) -> Result<(), AppError> {
+
+    let r = ex.execute(sql).await?.rows_affected();
+    info!("{} {} {}", r, obj.title, fb);
+    Ok(())
+}
@@ -0,0 +1,132 @@
+/***************************************************************************
+ * Dry run of the mdr import. When active, the sql that would be sent to the
+ * database by mdr::utils (including each chunk of a phased transfer or
+ * update, with its predicate) is written to a .sql file in the log folder
+ * instead of being executed. Each statement is preceded by a comment with
+ * its stage and step number. Nothing else that varies between runs is
+ * written, so the files from two versions of the program can be diffed to
+ * see how the statements sent to the database have changed.
+ ***************************************************************************/
+
+use crate::AppError;
+use chrono::Local;
+use log::info;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+// Used as the maximum NCT id when generating the chunk predicates, as the
+// real value would need to be read from ctgov.studies. It is also used as
+// the number of records transferred by a phased transfer, which is an upper
+// bound (at most one record per NCT id) and gives the chunks of later updates.
+
+pub const DRY_RUN_MAX_ID: u64 = 7000000;
+
+struct DryRun {
+    path: PathBuf,
+    writer: BufWriter<File>,
+    stage: String,
+    step: i32,
+    statements: u64,
+}
+
+static DRY_RUN: Mutex<Option<DryRun>> = Mutex::new(None);
+
+
+pub fn start(log_folder: &Path) -> Result<PathBuf, AppError> {
+
+    let datetime_string = Local::now().format("%m-%d %H%M%S").to_string();
+    let path: PathBuf = log_folder.join(format!("AACT DB dry run at {}.sql", datetime_string));
+    let file = File::create(&path).map_err(|e| AppError::IoWriteErrorWithPath(e, path.clone()))?;
+
+    info!("dry run: sql being written to {}, and not executed", path.display());
+    *DRY_RUN.lock().unwrap() = Some(DryRun {
+        path: path.clone(),
+        writer: BufWriter::new(file),
+        stage: "".to_string(),
+        step: 0,
+        statements: 0,
+    });
+
+    Ok(path)
+}
+
+
+pub fn end() -> Result<(), AppError> {
+
+    if let Some(mut dry_run) = DRY_RUN.lock().unwrap().take() {
+        dry_run.writer.flush().map_err(|e| AppError::IoWriteErrorWithPath(e, dry_run.path.clone()))?;
+        info!("dry run: {} sql statements written to {}", dry_run.statements, dry_run.path.display());
+    }
+    Ok(())
+}
+
+
+pub fn is_active() -> bool {
+    DRY_RUN.lock().unwrap().is_some()
+}
+
+
+pub fn start_stage(stage: &str) -> Result<(), AppError> {
+
+    let mut guard = DRY_RUN.lock().unwrap();
+    let Some(dry_run) = guard.as_mut() else {
+        return Ok(());
+    };
+    dry_run.stage = stage.to_string();
+    dry_run.step = 0;
+    let text = format!("\n-- ***** stage {} *****\n\n", stage);
+    dry_run.writer.write_all(text.as_bytes()).map_err(|e| AppError::IoWriteErrorWithPath(e, dry_run.path.clone()))
+}
+
+
+pub fn record(sql: &str) -> Result<(), AppError> {
+
+    let mut guard = DRY_RUN.lock().unwrap();
+    let Some(dry_run) = guard.as_mut() else {
+        return Ok(());
+    };
+    dry_run.step += 1;
+    dry_run.statements += 1;
+    let text = format!("-- {} step {}\n{}\n\n", dry_run.stage, dry_run.step, terminated(sql));
+    dry_run.writer.write_all(text.as_bytes()).map_err(|e| AppError::IoWriteErrorWithPath(e, dry_run.path.clone()))
+}
+
+
+pub fn note(comment: &str) -> Result<(), AppError> {
+
+    // For processing that depends on the contents of the database, and
+    // so cannot be expanded into sql without it.
+
+    let mut guard = DRY_RUN.lock().unwrap();
+    let Some(dry_run) = guard.as_mut() else {
+        return Ok(());
+    };
+    let text = format!("-- {}\n\n", comment);
+    dry_run.writer.write_all(text.as_bytes()).map_err(|e| AppError::IoWriteErrorWithPath(e, dry_run.path.clone()))
+}
+
+
+fn terminated(sql: &str) -> String {
+    let sql = sql.trim();
+    if sql.ends_with(';') {
+        sql.to_string()
+    }
+    else {
+        format!("{};", sql)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_statements_are_terminated() {
+        assert_eq!(terminated("  select 1;\n  "), "select 1;");
+        assert_eq!(terminated("update ad.studies s set x = 1 where s.id >= 1000001 and s.id <= 1150000"),
+                   "update ad.studies s set x = 1 where s.id >= 1000001 and s.id <= 1150000;");
+    }
+}
@@ -0,0 +1,554 @@
+/***************************************************************************
+ * The single executor through which all the mdr sql is sent to the database.
+ * It holds the pool and the label of the stage being run. Each statement
+ * (a 'step') sent through it is
+ *  - written to the dry run file instead, if a dry run is active,
+ *  - otherwise recorded in the run log (where it may be skipped if the run
+ *    is being resumed), timed, and reported if slower than the slow step
+ *    threshold,
+ *  - run within the executor's transaction, if one has been begun.
+ * The steps, rows affected and time taken are totalled for each stage.
+ * Phased transfers and updates, and vacuums, are also run through it, so
+ * that any further policies need only be added here.
+ *
+ * Depending on the transaction mode, each stage is run in a transaction:
+ *  - none: each step is committed as it completes (the default),
+ *  - stage: the stage is committed at its end, and on failure rolled back
+ *    as a whole, so that its tables are left as they were before it ran,
+ *  - step: as for stage, but each step is run within a savepoint. On
+ *    failure only the failed step is rolled back, and the steps completed
+ *    before it are committed, so the stage can be resumed from that step.
+ * Transactions begun within a stage (e.g. when restoring a checkpoint) are
+ * run as savepoints. Steps that are rolled back are marked as such in the
+ * run log, so that they are not skipped on a resumed run. Vacuums cannot
+ * be run within a transaction, and are deferred until it is committed.
+ *
+ * Each stage is run by its own executor, so that independent stages can be
+ * run at the same time (see mod.rs), each with its own transaction if any.
+ * All the executors share a job limit, the number of statements that can be
+ * executed at once (see parallel.rs). The chunks of a phased update cover
+ * disjoint ranges of ids, and are also run at the same time, up to the job
+ * limit, except within a transaction (which has a single connection) or in
+ * a dry run (where the sql is written in order).
+ ***************************************************************************/
+
+use super::{dry_run, run_log};
+use super::parallel::{JobLimit, TaskSet};
+use super::run_log::StageLog;
+use crate::AppError;
+use log::{info, warn};
+use sqlx::{FromRow, PgExecutor, Pool, Postgres, Transaction};
+use sqlx::postgres::{PgQueryResult, PgRow};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+// Steps taking longer than this are reported in the log.
+
+const SLOW_STEP_SECS: u64 = 60;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TransactionMode {
+    None,
+    Stage,
+    Step,
+}
+
+impl TransactionMode {
+
+    pub fn from_name(name: &str) -> Option<TransactionMode> {
+        match name.trim().to_lowercase().as_str() {
+            "" | "none" => Some(TransactionMode::None),
+            "stage" => Some(TransactionMode::Stage),
+            "step" => Some(TransactionMode::Step),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            TransactionMode::None => "none",
+            TransactionMode::Stage => "stage",
+            TransactionMode::Step => "step",
+        }
+    }
+}
+
+
+pub struct StepResult {
+    rows_affected: u64,
+}
+
+impl StepResult {
+
+    pub fn new(rows_affected: u64) -> StepResult {
+        StepResult { rows_affected }
+    }
+
+    pub fn rows_affected(&self) -> u64 {
+        self.rows_affected
+    }
+}
+
+
+struct StageTotals {
+    label: String,
+    steps: u64,
+    rows: u64,
+    elapsed: Duration,
+}
+
+struct TransactionState {
+    transaction: Option<Transaction<'static, Postgres>>,
+    begun_after: Vec<i32>,          // run log step number when each (nested) transaction began
+    deferred_vacuums: Vec<String>,
+}
+
+pub struct SqlExecutor {
+    pool: Pool<Postgres>,
+    mode: TransactionMode,
+    jobs: JobLimit,
+    slow_step: Duration,
+    totals: Mutex<StageTotals>,
+    log: Mutex<StageLog>,
+    state: tokio::sync::Mutex<TransactionState>,
+}
+
+impl SqlExecutor {
+
+    pub fn new(pool: &Pool<Postgres>) -> SqlExecutor {
+        SqlExecutor {
+            pool: pool.clone(),
+            mode: TransactionMode::None,
+            jobs: JobLimit::new(1),
+            slow_step: Duration::from_secs(SLOW_STEP_SECS),
+            totals: Mutex::new(StageTotals { label: "".to_string(), steps: 0, rows: 0, elapsed: Duration::ZERO }),
+            log: Mutex::new(StageLog::new("")),
+            state: tokio::sync::Mutex::new(TransactionState { transaction: None, begun_after: Vec::new(), deferred_vacuums: Vec::new() }),
+        }
+    }
+
+
+    pub fn with_transaction_mode(mut self, mode: TransactionMode) -> SqlExecutor {
+        self.mode = mode;
+        self
+    }
+
+
+    pub fn with_job_limit(mut self, jobs: &JobLimit) -> SqlExecutor {
+        self.jobs = jobs.clone();
+        self
+    }
+
+
+    pub fn pool(&self) -> &Pool<Postgres> {
+
+        // For queries that return data rather than change it, outside any transaction.
+
+        &self.pool
+    }
+
+
+    pub async fn start_stage(&self, label: &str) -> Result<bool, AppError> {
+
+        // Returns false if the stage can be skipped (see run_log::start_stage).
+
+        *self.totals.lock().unwrap() = StageTotals { label: label.to_string(), steps: 0, rows: 0, elapsed: Duration::ZERO };
+        dry_run::start_stage(label)?;
+        let Some(log) = run_log::start_stage(label, &self.pool).await? else {
+            return Ok(false);
+        };
+        *self.log.lock().unwrap() = log;
+        if self.mode != TransactionMode::None {
+            self.begin_transaction().await?;
+        }
+        Ok(true)
+    }
+
+
+    pub async fn end_stage(&self, outcome: Result<(), AppError>) -> Result<(), AppError> {
+
+        // The stage's transaction (if any) is committed, or on failure rolled back,  
+        // unless running a savepoint per step, when the completed steps are kept.
+
+        let outcome = match outcome {
+            Ok(()) if self.mode != TransactionMode::None => self.commit().await,
+            Err(e) if self.mode == TransactionMode::Stage => {
+                self.rollback().await?;
+                info!("mdr stage '{}' rolled back", self.totals.lock().unwrap().label);
+                Err(e)
+            },
+            Err(e) if self.mode == TransactionMode::Step => {
+                self.commit().await?;
+                info!("failed step rolled back, and the completed steps of the stage committed");
+                Err(e)
+            },
+            other => other,
+        };
+
+        let (label, steps, rows, elapsed) = {
+            let t = self.totals.lock().unwrap();
+            (t.label.clone(), t.steps, t.rows, t.elapsed)
+        };
+        if outcome.is_ok() && !dry_run::is_active() {
+            info!("mdr stage '{}' completed: {} sql steps, {} rows affected, in {:.1}s", label, steps, rows, elapsed.as_secs_f64());
+        }
+        run_log::end_stage(&self.log, &outcome, &self.pool).await?;
+        outcome
+    }
+
+
+    pub async fn execute(&self, sql: &str) -> Result<StepResult, AppError> {
+        self.execute_step(sql, &[], true).await
+    }
+
+
+    pub async fn execute_with_arrays(&self, sql: &str, columns: &[Vec<String>]) -> Result<StepResult, AppError> {
+
+        // For inserting values generated by the program, each column being bound 
+        // as a text array (to be unnested in the sql, as $1, $2...). Only the sql
+        // itself is recorded in the run log or written to the dry run file.
+
+        self.execute_step(sql, columns, true).await
+    }
+
+
+    async fn execute_step(&self, sql: &str, binds: &[Vec<String>], in_transaction: bool) -> Result<StepResult, AppError> {
+
+        if dry_run::is_active() {
+            dry_run::record(sql)?;
+            return Ok(StepResult::new(0));
+        }
+
+        let step_row = match run_log::start_step(&self.log, sql, &self.pool).await? {
+            run_log::StepStart::Skipped(rows) => return Ok(StepResult::new(rows)),
+            run_log::StepStart::Run(row) => row,
+        };
+
+        let permit = self.jobs.acquire().await;
+        let started = Instant::now();
+        let res = self.execute_now(sql, binds, in_transaction).await;
+        let elapsed = started.elapsed();
+        drop(permit);
+        run_log::end_step(&self.log, step_row, &res, &self.pool).await?;
+        let res = res?;
+
+        let label = {
+            let mut t = self.totals.lock().unwrap();
+            t.steps += 1;
+            t.rows += res.rows_affected;
+            t.elapsed += elapsed;
+            format!("{} step {}", t.label, t.steps)
+        };
+        if elapsed >= self.slow_step {
+            warn!("slow sql step ({}, {:.1}s, {} rows): {}", label, elapsed.as_secs_f64(), res.rows_affected, first_line(sql));
+        }
+
+        Ok(res)
+    }
+
+
+    async fn execute_now(&self, sql: &str, binds: &[Vec<String>], in_transaction: bool) -> Result<StepResult, AppError> {
+
+        // The transaction state is only held while the statement runs on the 
+        // transaction, so that statements run on the pool can run at the same time.
+
+        let mut state = self.state.lock().await;
+        let res = match state.transaction.as_mut() {
+            Some(t) if in_transaction => {
+                if self.mode == TransactionMode::Step {
+                    run_on(t, "SAVEPOINT mdr_step;").await?;
+                    let res = run_step(sql, binds, &mut **t).await;
+                    let end_sql = if res.is_ok() { "RELEASE SAVEPOINT mdr_step;" } else { "ROLLBACK TO SAVEPOINT mdr_step;" };
+                    run_on(t, end_sql).await?;
+                    res
+                }
+                else {
+                    run_step(sql, binds, &mut **t).await
+                }
+            },
+            _ => {
+                drop(state);
+                run_step(sql, binds, &self.pool).await
+            },
+        };
+        let res = res.map_err(|e| AppError::SqlxError(e, sql.to_string()))?;
+        Ok(StepResult::new(res.rows_affected()))
+    }
+
+
+    pub async fn fetch_column(&self, sql: &str) -> Result<Vec<String>, AppError> {
+
+        // For queries that need to see the changes made within the current transaction.
+
+        let mut state = self.state.lock().await;
+        let res = match state.transaction.as_mut() {
+            Some(t) => sqlx::query_scalar(sql).fetch_all(&mut **t).await,
+            None => sqlx::query_scalar(sql).fetch_all(&self.pool).await,
+        };
+        res.map_err(|e| AppError::SqlxError(e, sql.to_string()))
+    }
+
+
+    pub async fn fetch_rows<T>(&self, sql: &str) -> Result<Vec<T>, AppError>
+        where T: for<'r> FromRow<'r, PgRow> + Send + Unpin {
+
+        let mut state = self.state.lock().await;
+        let res = match state.transaction.as_mut() {
+            Some(t) => sqlx::query_as(sql).fetch_all(&mut **t).await,
+            None => sqlx::query_as(sql).fetch_all(&self.pool).await,
+        };
+        res.map_err(|e| AppError::SqlxError(e, sql.to_string()))
+    }
+
+
+    pub async fn begin_transaction(&self) -> Result<(), AppError> {
+
+        // Until committed or rolled back, all steps (other than vacuums) are run
+        // on the transaction's connection. A transaction begun within another 
+        // is run as a savepoint.
+
+        let mut state = self.state.lock().await;
+        let depth = state.begun_after.len();
+        if dry_run::is_active() {
+            dry_run::note(if depth == 0 { "begin transaction" } else { "savepoint" })?;
+        }
+        else if let Some(t) = state.transaction.as_mut() {
+            run_on(t, &format!("SAVEPOINT mdr_{};", depth)).await?;
+        }
+        else {
+            let t = self.pool.begin().await
+                       .map_err(|e| AppError::SqlxError(e, "begin".to_string()))?;
+            state.transaction = Some(t);
+        }
+        let step = self.log.lock().unwrap().step();
+        state.begun_after.push(step);
+        Ok(())
+    }
+
+
+    pub async fn commit(&self) -> Result<(), AppError> {
+
+        let mut state = self.state.lock().await;
+        if state.begun_after.pop().is_none() {
+            return Ok(());
+        }
+        let depth = state.begun_after.len();
+        if dry_run::is_active() {
+            dry_run::note(if depth == 0 { "commit transaction" } else { "release savepoint" })?;
+        }
+        else if depth > 0 {
+            if let Some(t) = state.transaction.as_mut() {
+                run_on(t, &format!("RELEASE SAVEPOINT mdr_{};", depth)).await?;
+            }
+            return Ok(());
+        }
+        else if let Some(t) = state.transaction.take() {
+            t.commit().await.map_err(|e| AppError::SqlxError(e, "commit".to_string()))?;
+        }
+
+        // Vacuums deferred until the outermost transaction is committed are now run.
+
+        let vacuums: Vec<String> = if depth == 0 { state.deferred_vacuums.drain(..).collect() } else { Vec::new() };
+        drop(state);
+        for table in vacuums {
+            self.vacuum_table(&table).await?;
+        }
+        Ok(())
+    }
+
+
+    pub async fn rollback(&self) -> Result<(), AppError> {
+
+        let mut state = self.state.lock().await;
+        let Some(begun_after) = state.begun_after.pop() else {
+            return Ok(());
+        };
+        let depth = state.begun_after.len();
+        if dry_run::is_active() {
+            return dry_run::note(if depth == 0 { "rollback transaction" } else { "rollback to savepoint" });
+        }
+        if depth > 0 {
+            if let Some(t) = state.transaction.as_mut() {
+                run_on(t, &format!("ROLLBACK TO SAVEPOINT mdr_{}; RELEASE SAVEPOINT mdr_{};", depth, depth)).await?;
+            }
+        }
+        else {
+            state.deferred_vacuums.clear();
+            if let Some(t) = state.transaction.take() {
+                t.rollback().await.map_err(|e| AppError::SqlxError(e, "rollback".to_string()))?;
+            }
+        }
+        drop(state);
+        run_log::mark_rolled_back(&self.log, begun_after, &self.pool).await
+    }
+
+
+    pub async fn phased_transfer(&self, sql: &str, max_id: u64, chunk_size: u64, sql_linker: &str, rec_type: &str, rec_dest: &str) -> Result<u64, AppError> {
+
+        let mut rec_num: u64 = 0;
+        let mut start_num: u64 = 0;
+
+        while start_num <= max_id {
+
+            let end_num = start_num + chunk_size;
+            let chunk_sql = format!("c.nct_id >= 'NCT{:0>8}' and c.nct_id < 'NCT{:0>8}';", start_num, end_num);
+            let chsql = sql.to_string() + sql_linker + &chunk_sql;
+
+            let res = self.execute(&chsql).await?;
+            let recs = res.rows_affected();
+            rec_num += recs;
+            info!("{} {} transferred to {}, {}", recs, rec_type, rec_dest, chunk_sql);
+
+            start_num = end_num;
+        }
+
+        if dry_run::is_active() {
+            rec_num = max_id;   // see dry_run::DRY_RUN_MAX_ID
+        }
+
+        info!("{} {} transferred in total", rec_num, rec_type);
+        info!("");
+
+        Ok(rec_num)
+    }
+
+
+    pub async fn phased_update(&self, sql: &str, rec_num: u64, chunk_size: u64, fback: &str) -> Result<(), AppError> {
+
+        let mut chunks = Vec::new();
+        for i in (0..rec_num).step_by(chunk_size.try_into().unwrap()) {
+
+            let start_num = i + 1000001;
+            let mut end_num = start_num + chunk_size - 1;
+            if end_num > rec_num + 1000001 {
+                end_num = rec_num + 1000000;
+            }
+            chunks.push(format!("s.id >= {} and s.id <= {};", start_num, end_num));
+        }
+
+        // Chunks are started in order, up to the job limit at any one time. If one fails
+        // no more are started, but those already running are allowed to finish.
+
+        let jobs = if dry_run::is_active() || self.in_transaction().await { 1 } else { self.jobs.jobs() };
+        let mut pending = chunks.into_iter();
+        let mut running = TaskSet::new();
+        let mut total_recs = 0;
+        let mut failure = None;
+
+        loop {
+            while failure.is_none() && running.len() < jobs && let Some(chunk_sql) = pending.next() {
+                let chsql = sql.to_string() + " and " + &chunk_sql;
+                running.push(chunk_sql, async move { self.execute(&chsql).await });
+            }
+            let Some((chunk_sql, res)) = running.next().await else {
+                break;
+            };
+            match res {
+                Ok(res) => {
+                    info!("{} {}, {}", res.rows_affected(), fback, chunk_sql);
+                    total_recs += res.rows_affected();
+                },
+                Err(e) => {
+                    failure.get_or_insert(e);
+                },
+            }
+        }
+        if let Some(e) = failure {
+            return Err(e);
+        }
+
+        info!("{} records affected in total", total_recs);
+        info!("");
+
+        Ok(())
+    }
+
+
+    async fn in_transaction(&self) -> bool {
+        !self.state.lock().await.begun_after.is_empty()
+    }
+
+
+    pub async fn vacuum_table(&self, table: &str) -> Result<(), AppError> {
+
+        // For studies table, normally leave until final updates...after iec_flag calculated.
+        // Vacuums cannot be run in a transaction, so are always run directly on the pool,
+        // and any requested within a transaction are deferred until it is committed.
+
+        {
+            let mut state = self.state.lock().await;
+            if !state.begun_after.is_empty() {
+                state.deferred_vacuums.push(table.to_string());
+                return Ok(());
+            }
+        }
+
+        let vac_sql =format!("VACUUM (FULL, ANALYZE) ad.{};", table);
+        if dry_run::is_active() {
+            return self.execute_step(&vac_sql, &[], false).await.map(|_| ());
+        }
+
+        let size_sql = format!("SELECT pg_size_pretty(pg_total_relation_size('ad.{}'));", table);
+        let before: String = sqlx::query_scalar(&size_sql).fetch_one(&self.pool)
+            .await.map_err(|e| AppError::SqlxError(e, size_sql.clone()))?;
+
+        self.execute_step(&vac_sql, &[], false).await?;
+
+        let after: String = sqlx::query_scalar(&size_sql).fetch_one(&self.pool)
+            .await.map_err(|e| AppError::SqlxError(e, size_sql))?;
+
+        info!("vacuum carried out on {} table, (size changing from {} to {})", table, before, after);
+
+        Ok(())
+    }
+}
+
+
+async fn run_step<'e, E: PgExecutor<'e>>(sql: &'e str, binds: &'e [Vec<String>], e: E) -> Result<PgQueryResult, sqlx::Error> {
+    if binds.is_empty() {
+        sqlx::raw_sql(sql).execute(e).await
+    }
+    else {
+        let mut q = sqlx::query(sql);
+        for column in binds {
+            q = q.bind(column);
+        }
+        q.execute(e).await
+    }
+}
+
+
+async fn run_on(t: &mut Transaction<'static, Postgres>, sql: &str) -> Result<(), AppError> {
+    sqlx::raw_sql(sql).execute(&mut **t).await
+        .map_err(|e| AppError::SqlxError(e, sql.to_string()))?;
+    Ok(())
+}
+
+
+fn first_line(sql: &str) -> &str {
+    sql.lines().map(|l| l.trim()).find(|l| !l.is_empty() && !l.starts_with("SET client_min_messages")).unwrap_or("")
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_first_line_of_step() {
+        let sql = r#"SET client_min_messages TO WARNING;
+            update ad.locs set fac_proc = replace(fac_proc, 'a', 'b')
+            where fac_proc like '%a%';"#;
+        assert_eq!(first_line(sql), "update ad.locs set fac_proc = replace(fac_proc, 'a', 'b')");
+        assert_eq!(first_line(""), "");
+    }
+
+    #[test]
+    fn check_transaction_mode_names() {
+        assert_eq!(TransactionMode::from_name(""), Some(TransactionMode::None));
+        assert_eq!(TransactionMode::from_name(" Stage "), Some(TransactionMode::Stage));
+        assert_eq!(TransactionMode::from_name("step"), Some(TransactionMode::Step));
+        assert_eq!(TransactionMode::from_name("steps"), None);
+        assert_eq!(TransactionMode::Step.name(), "step");
+    }
+}
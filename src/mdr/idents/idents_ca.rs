@@ -1,12 +1,12 @@
-use super::idents_utils::{execute_sql, execute_sql_fb};
+use super::idents_utils::execute_sql_fb;
 
 
-use sqlx::{Pool, Postgres};
+use crate::mdr::executor::SqlExecutor;
 use crate::AppError;
 use log::info;
 
 
-pub async fn find_swog_identities(pool: &Pool<Postgres>) -> Result<(), AppError> {  
+pub async fn find_swog_identities(ex: &SqlExecutor) -> Result<(), AppError> {  
         
     // Order in which the routines below are executed is important, as often the
     // SQL relies on identifying those records that have notr yet been identified.
@@ -20,7 +20,7 @@ pub async fn find_swog_identities(pool: &Pool<Postgres>) -> Result<(), AppError>
         source_org_id = 0,
         source_org = 'China Southern Association for Clinical Oncology'
         where id_value ~ '^CSWOG'"#;
-    execute_sql_fb(sql, pool, "Chinese SWOG", "found and labelled").await?;  
+    execute_sql_fb(sql, ex, "Chinese SWOG", "found and labelled").await?;  
 
     // KSWOG
 
@@ -30,7 +30,7 @@ pub async fn find_swog_identities(pool: &Pool<Postgres>) -> Result<(), AppError>
         source_org_id = 0,
         source_org = 'Korean South West Oncology Group'
         where id_value ~ '^KSWOG'"#;
-    execute_sql_fb(sql, pool, "Korean SWOG", "found and labelled").await?;  
+    execute_sql_fb(sql, ex, "Korean SWOG", "found and labelled").await?;  
 
     // SWOG 
 
@@ -45,7 +45,7 @@ pub async fn find_swog_identities(pool: &Pool<Postgres>) -> Result<(), AppError>
         or id_value ~ '-S[0-9]{4}-S[0-9]{4}'
         or id_value ~ '-S[0-9]{4}-[0-9]{4}'
         or id_value ~ '-S[0-9]{4}/S[0-9]{4}')"#;
-    execute_sql_fb(sql, pool, "US SWOG", "found and labelled").await?;  
+    execute_sql_fb(sql, ex, "US SWOG", "found and labelled").await?;  
 
 
     let sql = r#"update ad.temp_idents
@@ -55,7 +55,7 @@ pub async fn find_swog_identities(pool: &Pool<Postgres>) -> Result<(), AppError>
         source_org = 'Ad hoc Collaboration of research organisations'
         where id_value ~ 'SWOG' 
         and id_type_id is null"#;
-    execute_sql_fb(sql, pool, "Ad hoc collaboration", "found and labelled").await?;  
+    execute_sql_fb(sql, ex, "Ad hoc collaboration", "found and labelled").await?;  
 
     info!("");    
     Ok(())
@@ -63,7 +63,7 @@ pub async fn find_swog_identities(pool: &Pool<Postgres>) -> Result<(), AppError>
 }
 
 
-pub async fn find_cog_identities(pool: &Pool<Postgres>) -> Result<(), AppError> {  
+pub async fn find_cog_identities(ex: &SqlExecutor) -> Result<(), AppError> {  
         
     // CECOG
 
@@ -74,7 +74,7 @@ pub async fn find_cog_identities(pool: &Pool<Postgres>) -> Result<(), AppError>
         source_org_id = 0,
         source_org = 'Central European Cooperative Oncology Group'
         where id_value ~ '^CECOG'"#;
-    execute_sql_fb(sql, pool, "Central European COG", "found and labelled").await?;  
+    execute_sql_fb(sql, ex, "Central European COG", "found and labelled").await?;  
 
     // ECOG & ACRIN
 
@@ -84,7 +84,7 @@ pub async fn find_cog_identities(pool: &Pool<Postgres>) -> Result<(), AppError>
         source_org_id = 101684,
         source_org = 'Eastern Cooperative Oncology Group / American College of Radiology Imaging Network'
         where id_value ~ 'ECOG-ACRIN'"#;
-    execute_sql_fb(sql, pool, "ECOG-ACRIN", "found and labelled").await?;  
+    execute_sql_fb(sql, ex, "ECOG-ACRIN", "found and labelled").await?;  
 
     let sql = r#"update ad.temp_idents
         set id_type_id = 200,
@@ -94,7 +94,7 @@ pub async fn find_cog_identities(pool: &Pool<Postgres>) -> Result<(), AppError>
         where id_value ~ 'ACRIN'
         and (length(id_value) <= 14 or id_value ~ 'RESCUE$')
         and id_type_id is null"#;
-    execute_sql_fb(sql, pool, "ACRIN", "found and labelled").await?;  
+    execute_sql_fb(sql, ex, "ACRIN", "found and labelled").await?;  
 
     let sql = r#"update ad.temp_idents
         set id_type_id = 220,
@@ -103,7 +103,7 @@ pub async fn find_cog_identities(pool: &Pool<Postgres>) -> Result<(), AppError>
         source_org = 'Ad hoc Collaboration of research organisations'
         where id_value ~ 'ACRIN' 
         and id_type_id is null"#;
-    let res1 = execute_sql(sql, pool).await?;
+    let res1 = ex.execute(sql).await?;
 
     let sql = r#"update ad.temp_idents
         set id_type_id = 199,
@@ -115,7 +115,7 @@ pub async fn find_cog_identities(pool: &Pool<Postgres>) -> Result<(), AppError>
         and id_value !~ 'RTOG'
         and id_value !~ 'NCIC'
         and id_type_id is null"#;
-    execute_sql_fb(sql, pool, "ECOG", "found and labelled").await?;  
+    execute_sql_fb(sql, ex, "ECOG", "found and labelled").await?;  
 
     let sql = r#"update ad.temp_idents
         set id_type_id = 220,
@@ -124,7 +124,7 @@ pub async fn find_cog_identities(pool: &Pool<Postgres>) -> Result<(), AppError>
         source_org = 'Ad hoc Collaboration of research organisations'
         where id_value ~ 'ECOG'
         and id_type_id is null"#;
-    let res2 = execute_sql(sql, pool).await?;
+    let res2 = ex.execute(sql).await?;
 
     info!("{} ad hoc collaboration IDs found and labelled", res1.rows_affected() + res2.rows_affected());
     info!("");    
@@ -132,7 +132,7 @@ pub async fn find_cog_identities(pool: &Pool<Postgres>) -> Result<(), AppError>
 }
 
 
-pub async fn find_can_and_tog_identities(pool: &Pool<Postgres>) -> Result<(), AppError> {  
+pub async fn find_can_and_tog_identities(ex: &SqlExecutor) -> Result<(), AppError> {  
     
     // CALCGB
 
@@ -144,7 +144,7 @@ pub async fn find_can_and_tog_identities(pool: &Pool<Postgres>) -> Result<(), Ap
         where id_value ~ '^CALGB'
         and (length(id_value) <= 14 
         or (id_value ~ 'ICSC$' and length(id_value) <= 18))"#;
-    execute_sql_fb(sql, pool, "CALGB", "found and labelled").await?;  
+    execute_sql_fb(sql, ex, "CALGB", "found and labelled").await?;  
 
     let sql = r#"update ad.temp_idents
         set id_type_id = 220,
@@ -153,7 +153,7 @@ pub async fn find_can_and_tog_identities(pool: &Pool<Postgres>) -> Result<(), Ap
         source_org = 'Ad hoc Collaboration of research organisations'
         where id_value ~ 'CALGB'
         and id_type_id is null"#;
-    let res1 = execute_sql(sql, pool).await?;
+    let res1 = ex.execute(sql).await?;
 
     // CAN-NCIC
 
@@ -165,7 +165,7 @@ pub async fn find_can_and_tog_identities(pool: &Pool<Postgres>) -> Result<(), Ap
         where id_value ~ '^CAN-NCIC'
         and id_value !~ 'GOG'
         and id_type_id is null"#;
-    execute_sql_fb(sql, pool, "CAN-NCIC", "found and labelled").await?;  
+    execute_sql_fb(sql, ex, "CAN-NCIC", "found and labelled").await?;  
 
     let sql = r#"update ad.temp_idents
         set id_type_id = 220,
@@ -174,7 +174,7 @@ pub async fn find_can_and_tog_identities(pool: &Pool<Postgres>) -> Result<(), Ap
         source_org = 'Ad hoc Collaboration of research organisations'
         where id_value ~ 'CAN-NCIC' 
         and id_type_id is null"#;
-    let res2 = execute_sql(sql, pool).await?;
+    let res2 = ex.execute(sql).await?;
 
 
     // CRTOG
@@ -185,7 +185,7 @@ pub async fn find_can_and_tog_identities(pool: &Pool<Postgres>) -> Result<(), Ap
         source_org_id = 0,
         source_org = 'Chinese Radiation Therapy Oncology Group'
         where id_value ~ 'CRTOG'"#;
-    execute_sql_fb(sql, pool, "Chinese RTOG", "found and labelled").await?;  
+    execute_sql_fb(sql, ex, "Chinese RTOG", "found and labelled").await?;  
 
     // RTOG
 
@@ -197,7 +197,7 @@ pub async fn find_can_and_tog_identities(pool: &Pool<Postgres>) -> Result<(), Ap
         where id_value ~ '^RTOG'
         and id_value !~ 'GOG'
         and id_type_id is null"#;
-    execute_sql_fb(sql, pool, "RTOG", "found and labelled").await?;  
+    execute_sql_fb(sql, ex, "RTOG", "found and labelled").await?;  
 
     let sql = r#"update ad.temp_idents
         set id_type_id = 220,
@@ -206,7 +206,7 @@ pub async fn find_can_and_tog_identities(pool: &Pool<Postgres>) -> Result<(), Ap
         source_org = 'Ad hoc Collaboration of research organisations'
         where id_value ~ 'RTOG' 
         and id_type_id is null"#;
-    let res3 = execute_sql(sql, pool).await?;
+    let res3 = ex.execute(sql).await?;
 
     let r = res1.rows_affected() + res2.rows_affected() + res3.rows_affected();
     info!("{} ad hoc collaboration IDs found and labelled", r);    
@@ -216,7 +216,7 @@ pub async fn find_can_and_tog_identities(pool: &Pool<Postgres>) -> Result<(), Ap
 }
 
 
-pub async fn find_nat_identities(pool: &Pool<Postgres>) -> Result<(), AppError> {  
+pub async fn find_nat_identities(ex: &SqlExecutor) -> Result<(), AppError> {  
         
      // NCCTG
 
@@ -228,7 +228,7 @@ pub async fn find_nat_identities(pool: &Pool<Postgres>) -> Result<(), AppError>
         where id_value ~ '^NCCTG'
         and id_value !~ '-MA'
         and id_value !~ 'NSABP'"#;
-    execute_sql_fb(sql, pool, "NCCTG", "found and labelled").await?;  
+    execute_sql_fb(sql, ex, "NCCTG", "found and labelled").await?;  
 
     let sql = r#"update ad.temp_idents
         set id_type_id = 220,
@@ -237,7 +237,7 @@ pub async fn find_nat_identities(pool: &Pool<Postgres>) -> Result<(), AppError>
         source_org = 'Ad hoc Collaboration of research organisations'
         where id_value ~ 'NCCTG'
         and id_type_id is null"#;
-    let res1 = execute_sql(sql, pool).await?;
+    let res1 = ex.execute(sql).await?;
 
 
     // NSABP
@@ -250,7 +250,7 @@ pub async fn find_nat_identities(pool: &Pool<Postgres>) -> Result<(), AppError>
         where id_value ~ '^NSABP'
         and id_value !~ 'GBG'
         and id_value !~ 'MA'"#;
-    execute_sql_fb(sql, pool, "NSABP", "found and labelled").await?;  
+    execute_sql_fb(sql, ex, "NSABP", "found and labelled").await?;  
 
     let sql = r#"update ad.temp_idents
         set id_type_id = 220,
@@ -259,7 +259,7 @@ pub async fn find_nat_identities(pool: &Pool<Postgres>) -> Result<(), AppError>
         source_org = 'Ad hoc Collaboration of research organisations'
         where id_value ~ 'NSABP'
         and id_type_id is null"#;
-    let res2 = execute_sql(sql, pool).await?;
+    let res2 = ex.execute(sql).await?;
 
 
     // NABTC
@@ -270,7 +270,7 @@ pub async fn find_nat_identities(pool: &Pool<Postgres>) -> Result<(), AppError>
         source_org_id = 0,
         source_org = 'North American Brain Tumor Consortium'
         where id_value ~ '^NABTC'"#;
-    execute_sql_fb(sql, pool, "NABTC", "found and labelled").await?;  
+    execute_sql_fb(sql, ex, "NABTC", "found and labelled").await?;  
 
 
     // ACOSOG
@@ -282,7 +282,7 @@ pub async fn find_nat_identities(pool: &Pool<Postgres>) -> Result<(), AppError>
         source_org = 'American College of Surgeons Oncology Group'
         where id_value ~ '^ACOSOG'
         and id_type_id is null"#;
-    execute_sql_fb(sql, pool, "ACOSOG", "found and labelled").await?;  
+    execute_sql_fb(sql, ex, "ACOSOG", "found and labelled").await?;  
 
 
     // JH - NABTT
@@ -295,7 +295,7 @@ pub async fn find_nat_identities(pool: &Pool<Postgres>) -> Result<(), AppError>
         where id_value ~ 'JHOC-NABTT'
         or id_value ~ 'JHU-NABTT'
         and id_type_id is null"#;
-    execute_sql_fb(sql, pool, "JHOC-NABTT", "found and labelled").await?;  
+    execute_sql_fb(sql, ex, "JHOC-NABTT", "found and labelled").await?;  
 
     // NABTT
 
@@ -307,7 +307,7 @@ pub async fn find_nat_identities(pool: &Pool<Postgres>) -> Result<(), AppError>
         where id_value ~ 'NABTT'
         and id_value !~ 'IXR'
         and id_type_id is null"#;
-    execute_sql_fb(sql, pool, "NABTT", "found and labelled").await?;  
+    execute_sql_fb(sql, ex, "NABTT", "found and labelled").await?;  
 
     let sql = r#"update ad.temp_idents
         set id_type_id = 220,
@@ -316,7 +316,7 @@ pub async fn find_nat_identities(pool: &Pool<Postgres>) -> Result<(), AppError>
         source_org = 'Ad hoc Collaboration of research organisations'
         where id_value ~ 'NABTT'
         and id_type_id is null"#;
-    let res3 = execute_sql(sql, pool).await?;
+    let res3 = ex.execute(sql).await?;
 
     // JH CC
 
@@ -328,7 +328,7 @@ pub async fn find_nat_identities(pool: &Pool<Postgres>) -> Result<(), AppError>
         where (id_value ~ '^JHOC'
         or id_value ~ '^SKCCC')
         and id_type_id is null"#;
-    execute_sql_fb(sql, pool, "John Hopkins Cancer Center", "found and labelled").await?;  
+    execute_sql_fb(sql, ex, "John Hopkins Cancer Center", "found and labelled").await?;  
 
     let sql = r#"update ad.temp_idents
         set id_type_id = 220,
@@ -338,7 +338,7 @@ pub async fn find_nat_identities(pool: &Pool<Postgres>) -> Result<(), AppError>
         where (id_value ~ 'JHOC'  
         or id_value ~ 'SKCCC')
         and id_type_id is null"#;
-    let res4 = execute_sql(sql, pool).await?;
+    let res4 = ex.execute(sql).await?;
 
     let r = res1.rows_affected() + res2.rows_affected() 
             + res3.rows_affected() + res4.rows_affected();
@@ -349,7 +349,7 @@ pub async fn find_nat_identities(pool: &Pool<Postgres>) -> Result<(), AppError>
 }
 
 
-pub async fn find_boog_and_trog_identities(pool: &Pool<Postgres>) -> Result<(), AppError> {  
+pub async fn find_boog_and_trog_identities(ex: &SqlExecutor) -> Result<(), AppError> {  
         
     // BOOG
 
@@ -360,7 +360,7 @@ pub async fn find_boog_and_trog_identities(pool: &Pool<Postgres>) -> Result<(),
         source_org = 'Borstkanker Onderzoek Groep (Dutch Breast Cancer Research Group)'
         where id_value ~ '^BOOG'
         and id_type_id is null"#;
-    execute_sql_fb(sql, pool, "BOOG", "found and labelled").await?;  
+    execute_sql_fb(sql, ex, "BOOG", "found and labelled").await?;  
 
     let sql = r#"update ad.temp_idents
         set id_type_id = 220,
@@ -369,7 +369,7 @@ pub async fn find_boog_and_trog_identities(pool: &Pool<Postgres>) -> Result<(),
         source_org = 'Ad hoc Collaboration of research organisations'
         where id_value ~ 'BOOG'
         and id_type_id is null"#;
-    let res1 = execute_sql(sql, pool).await?;
+    let res1 = ex.execute(sql).await?;
 
 
     // CHNMC
@@ -380,7 +380,7 @@ pub async fn find_boog_and_trog_identities(pool: &Pool<Postgres>) -> Result<(),
         source_org_id = 100355,
         source_org = 'City of Hope National Medical Center'
         where id_value ~ '^CHNMC'"#;
-    execute_sql_fb(sql, pool, "City of Hope NMC", "found and labelled").await?;  
+    execute_sql_fb(sql, ex, "City of Hope NMC", "found and labelled").await?;  
 
     let sql = r#"update ad.temp_idents
         set id_type_id = 220,
@@ -389,7 +389,7 @@ pub async fn find_boog_and_trog_identities(pool: &Pool<Postgres>) -> Result<(),
         source_org = 'Ad hoc Collaboration of research organisations'
         where id_value ~ 'CHNMC'
         and id_type_id is null"#;
-    let res2 = execute_sql(sql, pool).await?;
+    let res2 = ex.execute(sql).await?;
 
     // TROG
 
@@ -400,7 +400,7 @@ pub async fn find_boog_and_trog_identities(pool: &Pool<Postgres>) -> Result<(),
         source_org = 'Trans Tasman Radiation Oncology Group '
         where id_value ~ '^TROG'
         and id_type_id is null"#;
-    execute_sql_fb(sql, pool, "TROG", "found and labelled").await?;  
+    execute_sql_fb(sql, ex, "TROG", "found and labelled").await?;  
 
     let sql = r#"update ad.temp_idents
         set id_type_id = 220,
@@ -409,7 +409,7 @@ pub async fn find_boog_and_trog_identities(pool: &Pool<Postgres>) -> Result<(),
         source_org = 'Ad hoc Collaboration of research organisations'
         where id_value ~ '-TROG'
         and id_type_id is null"#;
-    let res3 = execute_sql(sql, pool).await?;
+    let res3 = ex.execute(sql).await?;
 
     let r = res1.rows_affected() + res2.rows_affected() + res3.rows_affected();
     info!("{} ad hoc collaboration IDs found and labelled", r);    
@@ -419,7 +419,7 @@ pub async fn find_boog_and_trog_identities(pool: &Pool<Postgres>) -> Result<(),
 
 
 
-pub async fn find_gog_and_nrj_identities(pool: &Pool<Postgres>) -> Result<(), AppError> {  
+pub async fn find_gog_and_nrj_identities(ex: &SqlExecutor) -> Result<(), AppError> {  
         
     // ANZGOG
 
@@ -431,7 +431,7 @@ pub async fn find_gog_and_nrj_identities(pool: &Pool<Postgres>) -> Result<(), Ap
         where id_value ~ '^ANZGOG'
         and id_value !~ '-GOG'
         and id_type_id is null"#;
-    execute_sql_fb(sql, pool, "ANZGOG", "found and labelled").await?;  
+    execute_sql_fb(sql, ex, "ANZGOG", "found and labelled").await?;  
 
     let sql = r#"update ad.temp_idents
         set id_type_id = 220,
@@ -440,7 +440,7 @@ pub async fn find_gog_and_nrj_identities(pool: &Pool<Postgres>) -> Result<(), Ap
         source_org = 'Ad hoc Collaboration of research organisations'
         where id_value ~ 'ANZGOG'
         and id_type_id is null"#;
-    let res1 = execute_sql(sql, pool).await?;
+    let res1 = ex.execute(sql).await?;
 
 
     // Japanese Gynecologic Oncology Group
@@ -452,7 +452,7 @@ pub async fn find_gog_and_nrj_identities(pool: &Pool<Postgres>) -> Result<(), Ap
         source_org = 'Japanese Gynecologic Oncology Group'
         where id_value ~ '^JGOG'
         and id_type_id is null"#;
-    execute_sql_fb(sql, pool, "JGOG", "found and labelled").await?;  
+    execute_sql_fb(sql, ex, "JGOG", "found and labelled").await?;  
 
     // Korean Gynecologic Oncology Group
 
@@ -465,7 +465,7 @@ pub async fn find_gog_and_nrj_identities(pool: &Pool<Postgres>) -> Result<(), Ap
         and id_value !~ 'JGOG'
         and id_value !~ 'THAI'
         and id_type_id is null"#;
-    execute_sql_fb(sql, pool, "KGOG", "found and labelled").await?;  
+    execute_sql_fb(sql, ex, "KGOG", "found and labelled").await?;  
 
     // Shanghai Gynecologic Oncology Group
 
@@ -476,7 +476,7 @@ pub async fn find_gog_and_nrj_identities(pool: &Pool<Postgres>) -> Result<(), Ap
         source_org = 'Shanghai Gynecologic Oncology Group'
         where id_value ~ '^SGOG'
         and id_type_id is null"#;
-    execute_sql_fb(sql, pool, "SGOG", "found and labelled").await?;  
+    execute_sql_fb(sql, ex, "SGOG", "found and labelled").await?;  
 
     // Taiwanese Gynecologic Oncology Group
 
@@ -487,7 +487,7 @@ pub async fn find_gog_and_nrj_identities(pool: &Pool<Postgres>) -> Result<(), Ap
         source_org = 'Taiwanese Gynecologic Oncology Group'
         where id_value ~ '^TGOG'
         and id_type_id is null"#;
-    execute_sql_fb(sql, pool, "TGOG", "found and labelled").await?;  
+    execute_sql_fb(sql, ex, "TGOG", "found and labelled").await?;  
 
     // New York Gynecologic Oncology Group
 
@@ -498,7 +498,7 @@ pub async fn find_gog_and_nrj_identities(pool: &Pool<Postgres>) -> Result<(), Ap
         source_org = 'New York Gynecologic Oncology Group'
         where id_value ~ '^NYGOG'
         and id_type_id is null"#;
-    execute_sql_fb(sql, pool, "NYGOG", "found and labelled").await?;  
+    execute_sql_fb(sql, ex, "NYGOG", "found and labelled").await?;  
 
     let sql = r#"update ad.temp_idents
         set id_type_id = 220,
@@ -510,7 +510,7 @@ pub async fn find_gog_and_nrj_identities(pool: &Pool<Postgres>) -> Result<(), Ap
         or id_value ~ 'TGOG' or id_value ~ 'NYGOG')
         and id_value !~ 'ANSGOG'
         and id_type_id is null"#;
-    let res2 = execute_sql(sql, pool).await?;
+    let res2 = ex.execute(sql).await?;
 
     // Chinese Gastrointestinal Oncology Group 
 
@@ -521,7 +521,7 @@ pub async fn find_gog_and_nrj_identities(pool: &Pool<Postgres>) -> Result<(), Ap
         source_org = 'Chinese Gastrointestinal Oncology Group'
         where id_value ~ '^CGOG'
         or id_value ~ '-CGOG'"#;
-    execute_sql_fb(sql, pool, "CGOG", "found and labelled").await?;  
+    execute_sql_fb(sql, ex, "CGOG", "found and labelled").await?;  
 
 
     // BGOG Belgium and Luxembourg Gynaecological Oncology Group
@@ -534,7 +534,7 @@ pub async fn find_gog_and_nrj_identities(pool: &Pool<Postgres>) -> Result<(), Ap
         where id_value ~ '^BGOG'
         and id_value !~ '/'
         and id_type_id is null"#;
-    execute_sql_fb(sql, pool, "BGOG", "found and labelled").await?;  
+    execute_sql_fb(sql, ex, "BGOG", "found and labelled").await?;  
 
     let sql = r#"update ad.temp_idents
         set id_type_id = 220,
@@ -543,7 +543,7 @@ pub async fn find_gog_and_nrj_identities(pool: &Pool<Postgres>) -> Result<(), Ap
         source_org = 'Ad hoc Collaboration of research organisations'
         where id_value ~ 'BGOG'
         and id_type_id is null"#;
-    let res3 = execute_sql(sql, pool).await?;
+    let res3 = ex.execute(sql).await?;
 
 
     // CEEGOG Central and Eastern European Gynecologic Oncology Group
@@ -555,7 +555,7 @@ pub async fn find_gog_and_nrj_identities(pool: &Pool<Postgres>) -> Result<(), Ap
         source_org = 'Central and Eastern European Gynecologic Oncology Group'
         where id_value ~ '^CEEGOG'
         and id_type_id is null"#;
-    execute_sql_fb(sql, pool, "CEEGOG", "found and labelled").await?;  
+    execute_sql_fb(sql, ex, "CEEGOG", "found and labelled").await?;  
 
     let sql = r#"update ad.temp_idents
         set id_type_id = 220,
@@ -564,7 +564,7 @@ pub async fn find_gog_and_nrj_identities(pool: &Pool<Postgres>) -> Result<(), Ap
         source_org = 'Ad hoc Collaboration of research organisations'
         where id_value ~ 'CEEGOG'
         and id_type_id is null"#;
-    let res4 = execute_sql(sql, pool).await?;
+    let res4 = ex.execute(sql).await?;
 
 
     // CQGOG  Chongqing Gynecologic Oncology Group (university cancer hospital)
@@ -576,7 +576,7 @@ pub async fn find_gog_and_nrj_identities(pool: &Pool<Postgres>) -> Result<(), Ap
             source_org = 'Chongqing Gynecologic Oncology Group'
             where id_value ~ '^CQGOG'
             and id_type_id is null"#;
-    execute_sql_fb(sql, pool, "CQGOG", "found and labelled").await?;  
+    execute_sql_fb(sql, ex, "CQGOG", "found and labelled").await?;  
 
     // ENGOT
 
@@ -589,7 +589,7 @@ pub async fn find_gog_and_nrj_identities(pool: &Pool<Postgres>) -> Result<(), Ap
             and id_value !~ '/'
             and id_value !~ 'DGCG'
             and id_type_id is null"#;
-    execute_sql_fb(sql, pool, "ENGOT", "found and labelled").await?;  
+    execute_sql_fb(sql, ex, "ENGOT", "found and labelled").await?;  
 
     let sql = r#"update ad.temp_idents
             set id_type_id = 220,
@@ -598,7 +598,7 @@ pub async fn find_gog_and_nrj_identities(pool: &Pool<Postgres>) -> Result<(), Ap
             source_org = 'Ad hoc Collaboration of research organisations'
             where id_value ~ 'ENGOT'
             and id_type_id is null"#;
-    let res5 = execute_sql(sql, pool).await?;
+    let res5 = ex.execute(sql).await?;
   
     // GOG
 
@@ -610,7 +610,7 @@ pub async fn find_gog_and_nrj_identities(pool: &Pool<Postgres>) -> Result<(), Ap
             where id_value ~ '^GOG'
             and id_value !~ 'INCB'
             and id_type_id is null"#;
-    execute_sql_fb(sql, pool, "GOG", "found and labelled").await?;  
+    execute_sql_fb(sql, ex, "GOG", "found and labelled").await?;  
 
     let sql = r#"update ad.temp_idents
             set id_type_id = 220,
@@ -622,7 +622,7 @@ pub async fn find_gog_and_nrj_identities(pool: &Pool<Postgres>) -> Result<(), Ap
             and id_value !~ 'ANSGOG'
             and id_value !~ 'LGOG'
             and id_type_id is null"#;
-    let res6 = execute_sql(sql, pool).await?;
+    let res6 = ex.execute(sql).await?;
 
     // NRG Oncology
 
@@ -633,7 +633,7 @@ pub async fn find_gog_and_nrj_identities(pool: &Pool<Postgres>) -> Result<(), Ap
             source_org = 'NRG Oncology (US)'
             where id_value ~ '^NRG-'
             and id_type_id is null-- NRG Oncology"#;
-    execute_sql_fb(sql, pool, "NRG Oncology", "found and labelled").await?;  
+    execute_sql_fb(sql, ex, "NRG Oncology", "found and labelled").await?;  
 
     let r = res1.rows_affected() + res2.rows_affected() + res3.rows_affected() 
                + res4.rows_affected() + res5.rows_affected() + res6.rows_affected();
@@ -1,11 +1,10 @@
-//use super::utils::{execute_sql};
-use super::idents_utils::{execute_sql, execute_sql_fb};
+use super::idents_utils::execute_sql_fb;
 
-use sqlx::{Pool, Postgres};
+use crate::mdr::executor::SqlExecutor;
 use crate::AppError;
 use log::info;
 
-pub async fn find_eli_lilly_identities(pool: &Pool<Postgres>) -> Result<(), AppError> {  
+pub async fn find_eli_lilly_identities(ex: &SqlExecutor) -> Result<(), AppError> {  
         
     let sql = r#"update ad.temp_idents
         set id_type_id = 701,
@@ -13,7 +12,7 @@ pub async fn find_eli_lilly_identities(pool: &Pool<Postgres>) -> Result<(), AppE
         source_org_id = 100176,
         source_org = 'Eli Lilly'
         where id_value ~ '^[A-Z][0-9][A-Z]-[A-Z]{2}-[A-Z0-9]{4}$'"#;
-    execute_sql_fb(sql, pool, "Eli Lilly protocol", "found and labelled").await?;  
+    execute_sql_fb(sql, ex, "Eli Lilly protocol", "found and labelled").await?;  
 
     // Lilly took over Loxo Oncolcogy in 2019
 
@@ -24,7 +23,7 @@ pub async fn find_eli_lilly_identities(pool: &Pool<Postgres>) -> Result<(), AppE
         source_org = 'Eli Lilly'
         where id_value ~ '^LOXO-[A-Z]{3}-'
         or id_value = 'LOXO-260 Expanded Access'"#;
-    execute_sql_fb(sql, pool, "Eli Lilly Loxo", "found and labelled").await?;  
+    execute_sql_fb(sql, ex, "Eli Lilly Loxo", "found and labelled").await?;  
 
     let sql = r#"update ad.temp_idents
         set id_type_id = 701,
@@ -35,14 +34,14 @@ pub async fn find_eli_lilly_identities(pool: &Pool<Postgres>) -> Result<(), AppE
         and id_desc !~ 'DICE'
         and id_desc !~ 'AbbVie'
         and id_type_id is null"#;
-    execute_sql_fb(sql, pool, "Other Eli Lilly", "found and labelled").await?;  
+    execute_sql_fb(sql, ex, "Other Eli Lilly", "found and labelled").await?;  
     
     info!("");    
     Ok(())
 }
 
 
-pub async fn find_incyte_identities(pool: &Pool<Postgres>) -> Result<(), AppError> {  
+pub async fn find_incyte_identities(ex: &SqlExecutor) -> Result<(), AppError> {  
         
     let sql = r#"update ad.temp_idents a
         set id_type_id = 702,
@@ -62,14 +61,14 @@ pub async fn find_incyte_identities(pool: &Pool<Postgres>) -> Result<(), AppErro
         and a.id_type_id is null
         and s.name ilike 'Incyte%'
         "#;
-    execute_sql_fb(sql, pool, "InCyte study", "found and labelled").await?;  
+    execute_sql_fb(sql, ex, "InCyte study", "found and labelled").await?;  
 
     info!("");    
     Ok(())
 }
 
 
-pub async fn find_novartis_identities(pool: &Pool<Postgres>) -> Result<(), AppError> {  
+pub async fn find_novartis_identities(ex: &SqlExecutor) -> Result<(), AppError> {  
         
     // Initial group is IDs where Nopvartis is the sponsor
 
@@ -94,7 +93,7 @@ pub async fn find_novartis_identities(pool: &Pool<Postgres>) -> Result<(), AppEr
         )
         and a.id_type_id is null
         and s.name ilike 'Novart%';"#;
-    let res1 = execute_sql(sql, pool).await?;
+    let res1 = ex.execute(sql).await?;
 
     // Tidy these few  up
 
@@ -102,7 +101,7 @@ pub async fn find_novartis_identities(pool: &Pool<Postgres>) -> Result<(), AppEr
     set id_value = replace(id_value, 'NOVARTIS-', '')
         where id_type_id = 703 
         and id_value ~ '^NOVARTIS-C';"#;
-    execute_sql(sql, pool).await?;
+    ex.execute(sql).await?;
 
     // Addiitonal Novartis IDs when they are not listed as the sponsor
     // There are many exceptions, however, to the basic mmatching regexp
@@ -130,7 +129,7 @@ pub async fn find_novartis_identities(pool: &Pool<Postgres>) -> Result<(), AppEr
         and id_value !~ '^CTOR[A-Z0-9]{8,10}'
         and id_value !~ '^CWNT[A-Z0-9]{8,10}'
         and id_type_id is null"#;
-    let res2 = execute_sql(sql, pool).await?;
+    let res2 = ex.execute(sql).await?;
 
     info!("{} Novartis study identifiers found and labelled", res1.rows_affected() + res2.rows_affected()); 
     info!("");    
@@ -140,7 +139,7 @@ pub async fn find_novartis_identities(pool: &Pool<Postgres>) -> Result<(), AppEr
 }
 
 
-pub async fn find_alcon_identities(pool: &Pool<Postgres>) -> Result<(), AppError> {  
+pub async fn find_alcon_identities(ex: &SqlExecutor) -> Result<(), AppError> {  
         
     let sql = r#"update ad.temp_idents a
         set id_type_id = 704,
@@ -178,14 +177,14 @@ pub async fn find_alcon_identities(pool: &Pool<Postgres>) -> Result<(), AppError
         and a.id_type_id is null
         and s.name ilike 'Alcon%'
         "#;
-    execute_sql_fb(sql, pool, "Alcon Research study", "found and labelled").await?;  
+    execute_sql_fb(sql, ex, "Alcon Research study", "found and labelled").await?;  
     
     info!("");    
     Ok(())
 }
 
 
-pub async fn find_pfizer_identities(pool: &Pool<Postgres>) -> Result<(), AppError> {  
+pub async fn find_pfizer_identities(ex: &SqlExecutor) -> Result<(), AppError> {  
  
     let sql = r#"update ad.temp_idents a
         set id_type_id = 705,
@@ -197,14 +196,14 @@ pub async fn find_pfizer_identities(pool: &Pool<Postgres>) -> Result<(), AppErro
         and a.id_value ~ '^(A|B|C)[0-9]{7}$'
         and a.id_type_id is null
         and s.name ilike '%Pfizer%'"#;
-    execute_sql_fb(sql, pool, "Pfizer study", "found and labelled").await?;  
+    execute_sql_fb(sql, ex, "Pfizer study", "found and labelled").await?;  
  
     
     info!("");   
     Ok(())
 }
 
-pub async fn find_gsk_identities(_pool: &Pool<Postgres>) -> Result<(), AppError> {  
+pub async fn find_gsk_identities(_ex: &SqlExecutor) -> Result<(), AppError> {  
 
     /* 
     706		"GSK ID"
@@ -220,14 +219,14 @@ pub async fn find_gsk_identities(_pool: &Pool<Postgres>) -> Result<(), AppError>
         and a.id_type_id is null
         and s.name ilike '%'
         "#;
-    execute_sql_fb(sql, pool, "Pfizer study", "found and labelled").await?;  
+    execute_sql_fb(sql, ex, "Pfizer study", "found and labelled").await?;  
     
     info!("");   
     */  
     Ok(())
 }
 
-pub async fn find_roche_identities(_pool: &Pool<Postgres>) -> Result<(), AppError> {  
+pub async fn find_roche_identities(_ex: &SqlExecutor) -> Result<(), AppError> {  
 
     /* 
     707		"Roche ID"
@@ -243,14 +242,14 @@ pub async fn find_roche_identities(_pool: &Pool<Postgres>) -> Result<(), AppErro
         and a.id_type_id is null
         and s.name ilike '%'
         "#;
-    execute_sql_fb(sql, pool, "Pfizer study", "found and labelled").await?;  
+    execute_sql_fb(sql, ex, "Pfizer study", "found and labelled").await?;  
     
     info!("");   
     */  
     Ok(())
 }
 
-pub async fn find_az_identities(_pool: &Pool<Postgres>) -> Result<(), AppError> {  
+pub async fn find_az_identities(_ex: &SqlExecutor) -> Result<(), AppError> {  
 
     /* 
     708		"Astra Zeneca ID"
@@ -266,14 +265,14 @@ pub async fn find_az_identities(_pool: &Pool<Postgres>) -> Result<(), AppError>
         and a.id_type_id is null
         and s.name ilike '%'
         "#;
-    execute_sql_fb(sql, pool, "Pfizer study", "found and labelled").await?;  
+    execute_sql_fb(sql, ex, "Pfizer study", "found and labelled").await?;  
     
     info!("");   
     */  
     Ok(())
 }
 
-pub async fn find_takeda_identities(_pool: &Pool<Postgres>) -> Result<(), AppError> {  
+pub async fn find_takeda_identities(_ex: &SqlExecutor) -> Result<(), AppError> {  
 
     /* 
    709		"Takeda ID"
@@ -289,14 +288,14 @@ pub async fn find_takeda_identities(_pool: &Pool<Postgres>) -> Result<(), AppErr
         and a.id_type_id is null
         and s.name ilike '%'
         "#;
-    execute_sql_fb(sql, pool, "Pfizer study", "found and labelled").await?;  
+    execute_sql_fb(sql, ex, "Pfizer study", "found and labelled").await?;  
     
     info!("");   
     */  
     Ok(())
 }
 
-pub async fn find_jandj_identities(_pool: &Pool<Postgres>) -> Result<(), AppError> {  
+pub async fn find_jandj_identities(_ex: &SqlExecutor) -> Result<(), AppError> {  
 
     /* 
    710		"Johnson and Johnson ID"
@@ -312,7 +311,7 @@ pub async fn find_jandj_identities(_pool: &Pool<Postgres>) -> Result<(), AppErro
         and a.id_type_id is null
         and s.name ilike '%'
         "#;
-    execute_sql_fb(sql, pool, "Pfizer study", "found and labelled").await?;  
+    execute_sql_fb(sql, ex, "Pfizer study", "found and labelled").await?;  
     
     info!("");   
     */  
@@ -320,7 +319,7 @@ pub async fn find_jandj_identities(_pool: &Pool<Postgres>) -> Result<(), AppErro
 }
 
 
-pub async fn find_jannsen_identities(_pool: &Pool<Postgres>) -> Result<(), AppError> {  
+pub async fn find_jannsen_identities(_ex: &SqlExecutor) -> Result<(), AppError> {  
 
     /* 
    711		"Jannsen ID"
@@ -336,14 +335,14 @@ pub async fn find_jannsen_identities(_pool: &Pool<Postgres>) -> Result<(), AppEr
         and a.id_type_id is null
         and s.name ilike '%'
         "#;
-    execute_sql_fb(sql, pool, "Pfizer study", "found and labelled").await?;  
+    execute_sql_fb(sql, ex, "Pfizer study", "found and labelled").await?;  
     
     info!("");   
     */  
     Ok(())
 }
 
-pub async fn find_sanofi_identities(_pool: &Pool<Postgres>) -> Result<(), AppError> {  
+pub async fn find_sanofi_identities(_ex: &SqlExecutor) -> Result<(), AppError> {  
 
     /* 
    712		"Sanofi-Aventis ID"
@@ -359,14 +358,14 @@ pub async fn find_sanofi_identities(_pool: &Pool<Postgres>) -> Result<(), AppErr
         and a.id_type_id is null
         and s.name ilike '%'
         "#;
-    execute_sql_fb(sql, pool, "Pfizer study", "found and labelled").await?;  
+    execute_sql_fb(sql, ex, "Pfizer study", "found and labelled").await?;  
     
     info!("");   
     */  
     Ok(())
 }
 
-pub async fn find_bms_identities(_pool: &Pool<Postgres>) -> Result<(), AppError> {  
+pub async fn find_bms_identities(_ex: &SqlExecutor) -> Result<(), AppError> {  
 
     /* 
    713		"BMS ID"
@@ -382,14 +381,14 @@ pub async fn find_bms_identities(_pool: &Pool<Postgres>) -> Result<(), AppError>
         and a.id_type_id is null
         and s.name ilike '%'
         "#;
-    execute_sql_fb(sql, pool, "Pfizer study", "found and labelled").await?;  
+    execute_sql_fb(sql, ex, "Pfizer study", "found and labelled").await?;  
     
     info!("");   
     */  
     Ok(())
 }
 
-pub async fn find_abbvie_identities(_pool: &Pool<Postgres>) -> Result<(), AppError> {  
+pub async fn find_abbvie_identities(_ex: &SqlExecutor) -> Result<(), AppError> {  
 
     /* 
    714		"AbbVie ID"
@@ -405,7 +404,7 @@ pub async fn find_abbvie_identities(_pool: &Pool<Postgres>) -> Result<(), AppErr
         and a.id_type_id is null
         and s.name ilike '%'
         "#;
-    execute_sql_fb(sql, pool, "Pfizer study", "found and labelled").await?;  
+    execute_sql_fb(sql, ex, "Pfizer study", "found and labelled").await?;  
     
     info!("");   
     */  
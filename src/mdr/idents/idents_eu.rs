@@ -1,11 +1,11 @@
-use super::idents_utils::{execute_sql, execute_sql_fb, replace_string_in_ident};
+use super::idents_utils::{execute_sql_fb, replace_string_in_ident};
 
-use sqlx::{Pool, Postgres};
+use crate::mdr::executor::SqlExecutor;
 use crate::AppError;
 use log::info;
 
 
-pub async fn find_ansm_identities(pool: &Pool<Postgres>) -> Result<(), AppError> {  
+pub async fn find_ansm_identities(ex: &SqlExecutor) -> Result<(), AppError> {  
 
     // Useful to get the ANSM identifiers characterised first, as many of these are
     // wrobgly classed as EUDRACT numbers
@@ -18,7 +18,7 @@ pub async fn find_ansm_identities(pool: &Pool<Postgres>) -> Result<(), AppError>
         source_org = 'Agence Nationale de Sécurité du Médicament'
         where id_value ~ '20[0-9]{2}-A[0-9]{5}-[0-9]{2}'
         and (id_desc is null or id_desc not ilike 'AbbVie')"#;
-    let res1 = execute_sql(sql, pool).await?;
+    let res1 = ex.execute(sql).await?;
 
     let sql = r#"update ad.temp_idents
         set id_value = substring(id_desc from '20[0-9]{2}-A[0-9]{5}-[0-9]{2}'),
@@ -28,7 +28,7 @@ pub async fn find_ansm_identities(pool: &Pool<Postgres>) -> Result<(), AppError>
         source_org = 'Agence Nationale de Sécurité du Médicament'
         where id_desc ~ '20[0-9]{2}-A[0-9]{5}-[0-9]{2}'
         and id_type_id is null"#;
-    let res2 = execute_sql(sql, pool).await?;
+    let res2 = ex.execute(sql).await?;
 
     // This group has 'AO' rather than 'A0' in the identifier
 
@@ -40,7 +40,7 @@ pub async fn find_ansm_identities(pool: &Pool<Postgres>) -> Result<(), AppError>
         source_org = 'Agence Nationale de Sécurité du Médicament'
         where id_value ~ '20[0-9]{2}-AO[0-9]{4}-[0-9]{2}'
         and id_type_id is null"#;
-    let res3 = execute_sql(sql, pool).await?;
+    let res3 = ex.execute(sql).await?;
     info!("{} French ANSM identifiers found and labelled", res1.rows_affected() + res2.rows_affected() + res3.rows_affected());	
 
     // A series of manipulations of identifiers to get
@@ -48,59 +48,59 @@ pub async fn find_ansm_identities(pool: &Pool<Postgres>) -> Result<(), AppError>
 
     let sql = r#"update ad.temp_idents set id_value = '2'||id_value
         where (id_desc ~ 'ANSM' or id_desc ~ 'RCB') and id_value ~ '^0[0-9]{2}-A[0-9]{5}-[0-9]{2}';"#;
-    execute_sql(sql, pool).await?;
+    ex.execute(sql).await?;
 
     let sql = r#"update ad.temp_idents set id_value = replace(id_value, '-A-', '-A')
         where (id_desc ~ 'ANSM' or id_desc ~ 'RCB') 
         and id_value ~ '[0-9]{2}-A-[0-9]{5}';"#;
-    execute_sql(sql, pool).await?;
+    ex.execute(sql).await?;
 
     let sql = r#"update ad.temp_idents set id_value = replace(id_value, ' ', '-')
         where (id_desc ~ 'ANSM' or id_desc ~ 'RCB')
         and (id_value ~ '20[0-9]{2} A[0-9]{5} [0-9]{2}'
         or id_value ~ '20[0-9]{2} A[0-9]{5}-[0-9]{2}');"#;
-    execute_sql(sql, pool).await?;
+    ex.execute(sql).await?;
 
     let sql = r#"update ad.temp_idents set id_value = replace(id_value, '/', '-')
         where (id_desc ~ 'ANSM' or id_desc ~ 'RCB') and id_value ~ '20[0-9]{2}/A[0-9]{5}/[0-9]{2}';"#;
-    execute_sql(sql, pool).await?;
+    ex.execute(sql).await?;
 
     let sql = r#"update ad.temp_idents set id_value = replace(id_value, '_', '-')
         where (id_desc ~ 'ANSM' or id_desc ~ 'RCB') and (id_value ~ '20[0-9]{2}_A[0-9]{5}-[0-9]{2}'
         or id_value ~ '20[0-9]{2}_A[0-9]{5}_[0-9]{2}');"#;
-    execute_sql(sql, pool).await?;
+    ex.execute(sql).await?;
 
     let sql = r#"update ad.temp_idents set id_value = replace(id_value, 'a', 'A')
         where (id_desc ~ 'ANSM' or id_desc ~ 'RCB') and id_value ~ '20[0-9]{2}-a[0-9]{5}-[0-9]{2}';"#;
-    execute_sql(sql, pool).await?;
+    ex.execute(sql).await?;
 
     let sql = r#"delete from ad.temp_idents where (id_desc ~ 'ANSM' or id_desc ~ 'RCB')
         and id_value  = 'ID-RCB'"#;
-    execute_sql(sql, pool).await?;
+    ex.execute(sql).await?;
 
     let sql = r#"update ad.temp_idents set id_value = replace(id_value, '.', '-')
         where (id_desc ~ 'ANSM' or id_desc ~ 'RCB') 
         and id_value ~ '20[0-9]{2}\.A[0-9]{5}-[0-9]{2}';"#;
-    execute_sql(sql, pool).await?;
+    ex.execute(sql).await?;
 
     let sql = r#"update ad.temp_idents set id_value = replace(id_value, ' ', '')
         where (id_desc ~ 'ANSM' or id_desc ~ 'RCB')
         and (id_value ~ '20[0-9]{2} -A[0-9]{5}-[0-9]{2}' or id_value ~ '20[0-9]{2}- A[0-9]{5}-[0-9]{2}'
         or id_value ~ '20[0-9]{2} - A[0-9]{5}-[0-9]{2}' or id_value ~ '20[0-9]{2}-A[0-9]{5}- [0-9]{2}'
         or id_value ~ '20[0-9]{2}- A[0-9]{5} - [0-9]{2}');"#;
-    execute_sql(sql, pool).await?;
+    ex.execute(sql).await?;
 
     let sql = r#"update ad.temp_idents
         set id_value = substring(substring(id_value from '20[0-9]{2}-A[0-9]{7}$'), 1, 11)||'-'||substring(substring(id_value from '20[0-9]{2}-A[0-9]{7}$'), 12, 2) 
         where id_value ~ '20[0-9]{2}-A[0-9]{7}$'
         and (id_desc <> 'AbbVie' or id_desc is null);"#;
-    execute_sql(sql, pool).await?;
+    ex.execute(sql).await?;
 
     let sql = r#"update ad.temp_idents
         set id_value = substring(id_value, 1, 4)||'-'||substring(id_value, 5, 6)||'-'||substring(id_value, 10, 2) 
         where id_value ~ '^20[0-9]{2}A[0-9]{7}$'
         and (id_desc <> 'AbbVie' or id_desc is null);"#;
-    execute_sql(sql, pool).await?;
+    ex.execute(sql).await?;
 
     // Finally add the newly constructed identifiers  
 
@@ -113,7 +113,7 @@ pub async fn find_ansm_identities(pool: &Pool<Postgres>) -> Result<(), AppError>
         where id_value ~ '20[0-9]{2}-A[0-9]{5}-[0-9]{2}'
         and (id_desc is null or id_desc ~ 'ANSM' or id_desc ~ 'RCB' or id_desc ~ 'Eudra CT')
         and id_type_id is null;"#;
-    execute_sql_fb(sql, pool, "Additional ANSM", "found after repairing data").await?;
+    execute_sql_fb(sql, ex, "Additional ANSM", "found after repairing data").await?;
 
     // And also characterise the ill-formed ones
 
@@ -130,7 +130,7 @@ pub async fn find_ansm_identities(pool: &Pool<Postgres>) -> Result<(), AppError>
         or id_value ~ '20[0-9]{3}-A[0-9]{2,5}$')
         and (id_desc ~ 'ANSM' or id_desc ~ 'RCB')
         and id_type_id is null;"#;
-    execute_sql_fb(sql, pool, "Malformed ANSM", "labelled").await?;
+    execute_sql_fb(sql, ex, "Malformed ANSM", "labelled").await?;
 
     info!("");
 
@@ -138,7 +138,7 @@ pub async fn find_ansm_identities(pool: &Pool<Postgres>) -> Result<(), AppError>
 }
 
 
-pub async fn find_eu_wide_identities(pool: &Pool<Postgres>) -> Result<(), AppError> {  
+pub async fn find_eu_wide_identities(ex: &SqlExecutor) -> Result<(), AppError> {  
 
     let sql = r#"update ad.temp_idents
         set id_value = substring(id_value from '20[0-9]{2}-0[0-9]{5}-[0-9]{2}'),
@@ -152,7 +152,7 @@ pub async fn find_eu_wide_identities(pool: &Pool<Postgres>) -> Result<(), AppErr
         or id_value ilike '%eu%'
         or id_value ilike '%udract%'
         or id_value ilike '%edract%')"#;
-    execute_sql_fb(sql, pool, "EU CTR", "found and labelled").await?;
+    execute_sql_fb(sql, ex, "EU CTR", "found and labelled").await?;
     
 
     // A group of other records include EU-CTR IDs, in some cases along with other
@@ -177,7 +177,7 @@ pub async fn find_eu_wide_identities(pool: &Pool<Postgres>) -> Result<(), AppErr
         and id_value !~ '^SC'
         and id_value !~ '^SF'
         and replace(id_value, substring(id_value from '20[0-9]{2}-0[0-9]{5}-[0-9]{2}'), '') ~ '[1-9]'"#;
-    execute_sql(sql, pool).await?;
+    ex.execute(sql).await?;
     
     let sql = r#"update ad.temp_adds set id_value = trim(id_value);
         update ad.temp_adds set id_value = trim(BOTH '-' from id_value);
@@ -193,7 +193,7 @@ pub async fn find_eu_wide_identities(pool: &Pool<Postgres>) -> Result<(), AppErr
         from ad.temp_adds;
 
         drop table ad.temp_adds;"#;
-    execute_sql(sql, pool).await?;
+    ex.execute(sql).await?;
 
     let sql = r#"update ad.temp_idents
         set id_value = substring(id_value from '20[0-9]{2}-0[0-9]{5}-[0-9]{2}')
@@ -206,7 +206,7 @@ pub async fn find_eu_wide_identities(pool: &Pool<Postgres>) -> Result<(), AppErr
         and id_type_id is null
         and id_value !~ '^SC'
         and id_value !~ '^SF'"#;
-    execute_sql(sql, pool).await?;
+    ex.execute(sql).await?;
 
     let sql = r#"update ad.temp_idents
         set id_value = substring(id_value from '20[0-9]{2}-0[0-9]{5}-[0-9]{2}'),
@@ -216,7 +216,7 @@ pub async fn find_eu_wide_identities(pool: &Pool<Postgres>) -> Result<(), AppErr
         where id_value ~'20[0-9]{2}-0[0-9]{5}-[0-9]{2}' 
         and (length(id_value) = 14)
         and id_type_id is null"#;
-    execute_sql_fb(sql, pool, "Additional EU CTR", "found (in compound values)").await?;
+    execute_sql_fb(sql, ex, "Additional EU CTR", "found (in compound values)").await?;
     
     // A further small group are mis-formatted EUCTR numbers. Some of these can be turned
     // into properly formatted IDs, and then sncoded. The rest are missing digits or have surplus
@@ -228,7 +228,7 @@ pub async fn find_eu_wide_identities(pool: &Pool<Postgres>) -> Result<(), AppErr
         set id_value = id_desc,
         id_desc = id_value
         where id_value = 'EudraCT 2009';"#;
-    execute_sql(sql, pool).await?;
+    ex.execute(sql).await?;
 
     // delete various 'empty' ids
 
@@ -242,7 +242,7 @@ pub async fn find_eu_wide_identities(pool: &Pool<Postgres>) -> Result<(), AppErr
 
         delete from ad.temp_idents
         where id_value ~ '[0-9]-RCB/EUDRACT';"#;
-    execute_sql(sql, pool).await?;
+    ex.execute(sql).await?;
 
     // Create the correct format where possible
 
@@ -257,7 +257,7 @@ pub async fn find_eu_wide_identities(pool: &Pool<Postgres>) -> Result<(), AppErr
         where id_value ~ '20[0-9]{10}'
         and id_value ilike '%eudr%'
         and id_type_id is null;"#;
-    execute_sql(sql, pool).await?;
+    ex.execute(sql).await?;
 
     let sql = r#"update ad.temp_idents
         set id_value = 'Eudra '||substring(substring(id_value from '20[0-9]{2}-[0-9]{8}'), 1, 11)||'-'||substring(substring(id_value from '20[0-9]{2}-[0-9]{8}'), 12, 2)
@@ -270,7 +270,7 @@ pub async fn find_eu_wide_identities(pool: &Pool<Postgres>) -> Result<(), AppErr
         where id_value ~ '20[0-9]{8}-[0-9]{2}'
         and id_value ilike '%eudr%'
         and id_type_id is null;"#;
-    execute_sql(sql, pool).await?;
+    ex.execute(sql).await?;
 
     let sql = r#"update ad.temp_idents
         set id_value = 'Eudra '||substring(substring(id_value from '20[0-9]{4}-[0-9]{4}-[0-9]{2}'), 1, 4)||'-'
@@ -286,7 +286,7 @@ pub async fn find_eu_wide_identities(pool: &Pool<Postgres>) -> Result<(), AppErr
         where id_value ~ '20[0-9]{2}-[0-9]{3} [0-9]{3}-[0-9]{2}'
         and id_value ilike '%eudr%'
         and id_type_id is null;"#;
-    execute_sql(sql, pool).await?;
+    ex.execute(sql).await?;
 
     let sql = r#"update ad.temp_idents
         set id_value = replace(id_value, ' ', '-')
@@ -303,7 +303,7 @@ pub async fn find_eu_wide_identities(pool: &Pool<Postgres>) -> Result<(), AppErr
         or id_value ~ '20[0-9]{2}-[0-9]{6}/[0-9]{2}')
         and id_value ilike '%eudr%'
         and id_type_id is null;"#;
-    execute_sql(sql, pool).await?;
+    ex.execute(sql).await?;
     
     let sql = r#"update ad.temp_idents
         set id_value = substring(id_value from '20[0-9]{2}-0[0-9]{5}-[0-9]{2}'),
@@ -315,7 +315,7 @@ pub async fn find_eu_wide_identities(pool: &Pool<Postgres>) -> Result<(), AppErr
         and id_value ilike '%eudr%'
         and id_value not ilike '%eudramed%'
         and id_value not ilike '%eudract'"#;
-    execute_sql_fb(sql, pool, "Additional EU CTR", "found after repairing data").await?;
+    execute_sql_fb(sql, ex, "Additional EU CTR", "found after repairing data").await?;
 
     // identify obvious mis-formed and uncorrectable ones
 
@@ -337,7 +337,7 @@ pub async fn find_eu_wide_identities(pool: &Pool<Postgres>) -> Result<(), AppErr
         or id_value ~ 'umber [0-9]{3}$'
         )
         and id_type_id is null;"#;
-    execute_sql_fb(sql, pool, "Malformed EUDRACT", "labelled").await?;
+    execute_sql_fb(sql, ex, "Malformed EUDRACT", "labelled").await?;
     info!("");
 
     // CTIS number
@@ -349,7 +349,7 @@ pub async fn find_eu_wide_identities(pool: &Pool<Postgres>) -> Result<(), AppErr
         source_org_id = 100159,
         source_org = 'European Medicines Agency'
         where id_value ~ '20[2|3][0-9]-5[0-9]{5}-[0-9]{2}'"#;
-    execute_sql_fb(sql, pool, "EU CTIS", "found and labelled").await?;
+    execute_sql_fb(sql, ex, "EU CTIS", "found and labelled").await?;
     info!("");
 
     // Eudamed ID
@@ -359,48 +359,48 @@ pub async fn find_eu_wide_identities(pool: &Pool<Postgres>) -> Result<(), AppErr
     let sql = r#"update ad.temp_idents
         set id_value = replace(id_value, 'CVI', 'CIV')
         where id_value ~ 'CVI-[0-9]{2}-[0-9]{2}-[0-9]{6}';"#;
-    execute_sql(sql, pool).await?;
+    ex.execute(sql).await?;
 
     let sql = r#"update ad.temp_idents
         set id_value = replace(id_value, 'CVI ', 'CIV-')
         where id_value ~ 'CIV [0-9]{2}-[0-9]{2}-[0-9]{6}';"#;
-    execute_sql(sql, pool).await?;
+    ex.execute(sql).await?;
 
     let sql = r#"update ad.temp_idents
         set id_value = replace(id_value, ' ', '-')
         where id_value ~ 'CIV-[0-9] [0-9]-[0-9]{2}-[0-9]{6}';"#;
-    execute_sql(sql, pool).await?;
+    ex.execute(sql).await?;
 
     let sql = r#"update ad.temp_idents
         set id_value = replace(id_value, ' ', '')
         where id_value ~ 'CIV -[0-9]{2}-[0-9]{2}-[0-9]{6}';"#;
-    execute_sql(sql, pool).await?;
+    ex.execute(sql).await?;
 
     let sql = r#"update ad.temp_idents
         set id_value = replace(id_value, ' ', '')
         where id_value ~ 'CIV- [0-9]{2}-[0-9]{2}-[0-9]{6}';"#;
-    execute_sql(sql, pool).await?;
+    ex.execute(sql).await?;
 
     let sql = r#"update ad.temp_idents
         set id_value = replace(id_value, 'CIV-ID ', 'CIV-')
         where id_value ~ 'CIV-ID [0-9]{2}-[0-9]{2}-[0-9]{6}';"#;
-    execute_sql(sql, pool).await?;
+    ex.execute(sql).await?;
 
     let sql = r#"update ad.temp_idents
         set id_value = replace(replace(id_value, ' ', ''), 'Cl', 'CI')
         where id_value ~ 'Cl V-1 3-03';"#;
-    execute_sql(sql, pool).await?;
+    ex.execute(sql).await?;
 
     let sql = r#"update ad.temp_idents
         set id_value = replace(id_value, '1-8', '18')
         where id_value ~ 'CIV-1-8-06';"#;
-    execute_sql(sql, pool).await?;
+    ex.execute(sql).await?;
 
     let sql = r#"update ad.temp_idents
         set id_value = 'CIV-'||id_value
         where (id_desc ~ 'CIV' or id_desc ~* 'EudaMed')
         and id_value ~ '^[0-9]{2}-[0-9]{2}';"#;
-    execute_sql(sql, pool).await?;
+    ex.execute(sql).await?;
 
     let sql = r#"update ad.temp_idents
     set id_value = substring(id_value from 'CIV-[0-9]{2}-[0-9]{2}-[0-9]{6}'),
@@ -409,7 +409,7 @@ pub async fn find_eu_wide_identities(pool: &Pool<Postgres>) -> Result<(), AppErr
             source_org_id = 100574,
             source_org = 'European Commission'
     where id_value ~ 'CIV-[0-9]{2}-[0-9]{2}-[0-9]{6}'"#;
-    execute_sql_fb(sql, pool, "Eudamed", "found and labelled").await?;
+    execute_sql_fb(sql, ex, "Eudamed", "found and labelled").await?;
 
     let sql = r#"update ad.temp_idents
     set id_value = substring(id_value from 'CIV-[A-Z]{2}-[0-9]{2}-[0-9]{2}-[0-9]{6}'),
@@ -418,7 +418,7 @@ pub async fn find_eu_wide_identities(pool: &Pool<Postgres>) -> Result<(), AppErr
             source_org_id = 100574,
             source_org = 'European Commission'
     where id_value ~'CIV-[A-Z]{2}-[0-9]{2}-[0-9]{2}-[0-9]{6}'"#;
-    execute_sql_fb(sql, pool, "Eudamed", "with country codes found and labelled").await?;
+    execute_sql_fb(sql, ex, "Eudamed", "with country codes found and labelled").await?;
 
     let sql = r#"update ad.temp_idents
     set id_type_id =  2186,
@@ -427,7 +427,7 @@ pub async fn find_eu_wide_identities(pool: &Pool<Postgres>) -> Result<(), AppErr
         source_org = 'European Commission'
     where id_value ~'^CIV-'
     and id_type_id is null"#;
-    execute_sql_fb(sql, pool, "Malformed Eudamed", "labelled").await?;
+    execute_sql_fb(sql, ex, "Malformed Eudamed", "labelled").await?;
 
     // Tidy up
 
@@ -435,7 +435,7 @@ pub async fn find_eu_wide_identities(pool: &Pool<Postgres>) -> Result<(), AppErr
     set id_desc = null
     where (id_desc ~ 'CIV' or id_desc ~* 'EudaMed')
     and id_type_id is null"#;
-    execute_sql(sql, pool).await?;
+    ex.execute(sql).await?;
 
     info!("");
 
@@ -449,7 +449,7 @@ pub async fn find_eu_wide_identities(pool: &Pool<Postgres>) -> Result<(), AppErr
         source_org = 'European Medicines Agency'
         where id_value ~ '^EUPAS[0-9]{3,12}'
         or id_value ~ '^EUPAS [0-9]{3,12}'"#;
-    execute_sql_fb(sql, pool, "HMA-EMA RWD (EUPAS)", "found and labelled").await?;
+    execute_sql_fb(sql, ex, "HMA-EMA RWD (EUPAS)", "found and labelled").await?;
 
     info!("");
 
@@ -457,19 +457,19 @@ pub async fn find_eu_wide_identities(pool: &Pool<Postgres>) -> Result<(), AppErr
 }
 
 
-pub async fn find_dutch_identities(pool: &Pool<Postgres>) -> Result<(), AppError> {  
+pub async fn find_dutch_identities(ex: &SqlExecutor) -> Result<(), AppError> {  
 
     // DUTCH NTR
 
     let sql = r#"update ad.temp_idents
     set id_value = replace(id_value, ' ', '')
     where id_value ~ '^NTR [0-9]{1,4}$'"#;
-    execute_sql(sql, pool).await?;
+    ex.execute(sql).await?;
 
     let sql = r#"update ad.temp_idents
     set id_value = replace(id_value, '-', '')
     where id_value ~ '^NTR-[0-9]{1,4}$'"#;
-    execute_sql(sql, pool).await?;
+    ex.execute(sql).await?;
   
     let sql = r#"update ad.temp_idents
         set id_value = substring(id_value from 'NTR[0-9]{1,4}'),
@@ -478,19 +478,19 @@ pub async fn find_dutch_identities(pool: &Pool<Postgres>) -> Result<(), AppError
         source_org_id = 0,
         source_org = 'Centrale Commissie Mensgebonden Onderzoek'
         where id_value ~ '^NTR[0-9]{1,4}'"#;
-    execute_sql_fb(sql, pool, "NTR Dutch", "found and labelled").await?;    
+    execute_sql_fb(sql, ex, "NTR Dutch", "found and labelled").await?;    
     
     let sql = r#"update ad.temp_idents
         set id_value = substring(id_desc from 'NTR[0-9]{4}')
         where id_desc ~ 'NTR[0-9]{4}'
         and id_type_id is null"#;
-    execute_sql(sql, pool).await?;
+    ex.execute(sql).await?;
 
     let sql = r#"update ad.temp_idents
         set id_value = 'NTR'||id_value
         where id_desc = 'NTR'
         and id_type_id is null"#;
-    execute_sql(sql, pool).await?;
+    ex.execute(sql).await?;
 
     let sql = r#"update ad.temp_idents
         set id_value = substring(id_value from 'NTR[0-9]{1,4}'),
@@ -501,7 +501,7 @@ pub async fn find_dutch_identities(pool: &Pool<Postgres>) -> Result<(), AppError
         where id_value ~ '^NTR[0-9]{1,4}'
         and id_value !~ '^NTR[0-9]{5}'
         and id_type_id is null"#;
-    execute_sql_fb(sql, pool, "Additional NTR Dutch", "found after repairing data").await?; 
+    execute_sql_fb(sql, ex, "Additional NTR Dutch", "found after repairing data").await?; 
 
     let sql = r#"update ad.temp_idents
         set id_type_id = 2181,
@@ -510,7 +510,7 @@ pub async fn find_dutch_identities(pool: &Pool<Postgres>) -> Result<(), AppError
         source_org = 'Centrale Commissie Mensgebonden Onderzoek'
         where id_value ~ '^NTR[0-9]{5}'
         and id_type_id is null"#;
-    execute_sql_fb(sql, pool, "Malformed Dutch NTR", "labelled").await?; 
+    execute_sql_fb(sql, ex, "Malformed Dutch NTR", "labelled").await?; 
     info!("");
 
     // DUTCH NL - CCMO
@@ -554,7 +554,7 @@ pub async fn find_dutch_identities(pool: &Pool<Postgres>) -> Result<(), AppError
         ||'.'||substring(substring(id_value from 'NL[0-9]{10}'), 11, 2)
         where id_value ~ 'NL[0-9]{10}' and id_value !~ 'NL[0-9]{11}';"#;
 
-    execute_sql(sql, pool).await?; 
+    ex.execute(sql).await?; 
 
     // Then create a temp table
     // that will hold the other IDs often (70+) found with the CCMO IDs.
@@ -572,7 +572,7 @@ pub async fn find_dutch_identities(pool: &Pool<Postgres>) -> Result<(), AppError
         where id_value ~ 'NL[0-9]{5}\.[0-9]{3}\.[0-9]{2}' 
         and id_type_id is null
         and replace(id_value, substring(id_value from 'NL[0-9]{5}\.[0-9]{3}\.[0-9]{2}'), '') ~ '[1-9]';"#;
-    execute_sql_fb(sql, pool, "Additional Dutch", "(found with CCMO records) added to temp_idents").await?;    
+    execute_sql_fb(sql, ex, "Additional Dutch", "(found with CCMO records) added to temp_idents").await?;    
 
     let sql = r#"update ad.temp_adds set id_value = trim(id_value);
         update ad.temp_adds set id_value = trim(BOTH '-' from id_value);
@@ -589,13 +589,13 @@ pub async fn find_dutch_identities(pool: &Pool<Postgres>) -> Result<(), AppError
         from ad.temp_adds;
 
         drop table ad.temp_adds;"#;
-    execute_sql(sql, pool).await?; 
+    ex.execute(sql).await?; 
 
     let sql = r#"update ad.temp_idents
         set id_value = substring(id_value from 'NL[0-9]{5}\.[0-9]{3}\.[0-9]{2}')
         where id_value ~ 'NL[0-9]{5}\.[0-9]{3}\.[0-9]{2}'
         and id_type_id is null"#;
-    execute_sql(sql, pool).await?; 
+    ex.execute(sql).await?; 
 
     let sql = r#"update ad.temp_idents
         set id_value = substring(id_value from 'NL[0-9]{5}\.[0-9]{3}\.[0-9]{2}'),
@@ -605,7 +605,7 @@ pub async fn find_dutch_identities(pool: &Pool<Postgres>) -> Result<(), AppError
         source_org = 'CCMO'
         where id_value ~ 'NL[0-9]{5}\.[0-9]{3}\.[0-9]{2}'
         and id_type_id is null"#;
-    execute_sql_fb(sql, pool, "Dutch CCMO", "found and labelled").await?;  
+    execute_sql_fb(sql, ex, "Dutch CCMO", "found and labelled").await?;  
 
     let sql = r#"update ad.temp_idents
         set id_type_id = 2801,
@@ -614,7 +614,7 @@ pub async fn find_dutch_identities(pool: &Pool<Postgres>) -> Result<(), AppError
         where (id_value ~ 'NL[0-9]{3,4}\.[0-9]{3}\.[0-9]{2}'
         or id_value ~ 'NL[0-9]{6}\.[0-9]{3}\.[0-9]{2}')
         and id_type_id is null;"#;
-    execute_sql_fb(sql, pool, "Malformed Dutch CCMO", "found and labelled").await?;   
+    execute_sql_fb(sql, ex, "Malformed Dutch CCMO", "found and labelled").await?;   
     info!("");
 
     // DUTCH NL - Registry IDs
@@ -627,7 +627,7 @@ pub async fn find_dutch_identities(pool: &Pool<Postgres>) -> Result<(), AppError
         source_org = 'Centrale Commissie Mensgebonden Onderzoek'
         where id_value ~ '^NL[0-9]{4}'
         and length(id_value) < 7"#;
-    let res = execute_sql(sql, pool).await?;
+    let res = ex.execute(sql).await?;
     info!("{} NL Dutch identifiers found and labelled", res.rows_affected());	
     
     // DUTCH NL-OMON
@@ -639,7 +639,7 @@ pub async fn find_dutch_identities(pool: &Pool<Postgres>) -> Result<(), AppError
         source_org_id = 0,
         source_org = 'Centrale Commissie Mensgebonden Onderzoek'
         where id_value ~ 'NL-OMON[0-9]{1,5}'"#;
-    let res = execute_sql(sql, pool).await?;
+    let res = ex.execute(sql).await?;
     info!("{} NL-OMON Dutch identifiers found and labelled", res.rows_affected());	
     info!("");
 
@@ -647,7 +647,7 @@ pub async fn find_dutch_identities(pool: &Pool<Postgres>) -> Result<(), AppError
 }
 
 
-pub async fn find_german_identities(pool: &Pool<Postgres>) -> Result<(), AppError> {  
+pub async fn find_german_identities(ex: &SqlExecutor) -> Result<(), AppError> {  
     
     let sql = r#"update ad.temp_idents
         set id_value = substring(id_value from 'DRKS[0-9]{8}'),
@@ -656,13 +656,13 @@ pub async fn find_german_identities(pool: &Pool<Postgres>) -> Result<(), AppErro
         source_org_id = 105875,
         source_org = 'Federal Institute for Drugs and Medical Devices'
         where id_value ~ 'DRKS[0-9]{8}'"#;
-    execute_sql_fb(sql, pool, "DRKS German", "found and labelled").await?;
+    execute_sql_fb(sql, ex, "DRKS German", "found and labelled").await?;
     
     let sql = r#"update ad.temp_idents
         set id_value = 'DRKS'||id_value
         where id_desc ilike '%DRKS%'
         and id_type_id is null"#;
-    execute_sql(sql, pool).await?;
+    ex.execute(sql).await?;
 
     let sql = r#"update ad.temp_idents
         set id_value = substring(id_value from 'DRKS[0-9]{8}'),
@@ -672,7 +672,7 @@ pub async fn find_german_identities(pool: &Pool<Postgres>) -> Result<(), AppErro
         source_org = 'Federal Institute for Drugs and Medical Devices'
         where id_value ~ 'DRKS[0-9]{8}'
         and id_type_id is null"#;
-    execute_sql_fb(sql, pool, "Additional DRKS German", "found after repairing data").await?;
+    execute_sql_fb(sql, ex, "Additional DRKS German", "found after repairing data").await?;
     
     let sql = r#"update ad.temp_idents
         set id_type_id = 2124,
@@ -681,22 +681,22 @@ pub async fn find_german_identities(pool: &Pool<Postgres>) -> Result<(), AppErro
         source_org = 'Federal Institute for Drugs and Medical Devices'
         where (id_value ~ 'DRKS[0-9]{5,7}' or id_value ~ 'DRKS[0-9]{9}')
         and id_value !~ 'DRKS[0-9]{8}'"#;
-    execute_sql_fb(sql, pool, "Malformed German", "labelled").await?;    
+    execute_sql_fb(sql, ex, "Malformed German", "labelled").await?;    
     info!("");
 
     Ok(())
 }
 
 
-pub async fn find_isrctn_identities(pool: &Pool<Postgres>) -> Result<(), AppError> {  
+pub async fn find_isrctn_identities(ex: &SqlExecutor) -> Result<(), AppError> {  
 
-    replace_string_in_ident("ISCRTN", "ISRCTN", pool).await?;  
-    replace_string_in_ident("isrctn", "ISRCTN", pool).await?; 
-    replace_string_in_ident("ISRCTN : ", "ISRCTN", pool).await?;   // preliminary tidying (few recs)
-    replace_string_in_ident("ISRCTN: ", "ISRCTN", pool).await?;  
-    replace_string_in_ident("ISRCTN:", "ISRCTN", pool).await?;  
-    replace_string_in_ident("ISRCTN ", "ISRCTN", pool).await?; 
-    replace_string_in_ident("ISRCTN-", "ISRCTN", pool).await?;  
+    replace_string_in_ident("ISCRTN", "ISRCTN", ex).await?;  
+    replace_string_in_ident("isrctn", "ISRCTN", ex).await?; 
+    replace_string_in_ident("ISRCTN : ", "ISRCTN", ex).await?;   // preliminary tidying (few recs)
+    replace_string_in_ident("ISRCTN: ", "ISRCTN", ex).await?;  
+    replace_string_in_ident("ISRCTN:", "ISRCTN", ex).await?;  
+    replace_string_in_ident("ISRCTN ", "ISRCTN", ex).await?; 
+    replace_string_in_ident("ISRCTN-", "ISRCTN", ex).await?;  
 
     let sql = r#"update ad.temp_idents
         set id_value = substring(id_value from 'ISRCTN[0-9]{8}'),
@@ -705,14 +705,14 @@ pub async fn find_isrctn_identities(pool: &Pool<Postgres>) -> Result<(), AppErro
         source_org_id = 101421,
         source_org = 'Springer Nature'
         where id_value ~ 'ISRCTN[0-9]{8}'"#;
-    execute_sql_fb(sql, pool, "ISRCTN", "found and labelled").await?;
+    execute_sql_fb(sql, ex, "ISRCTN", "found and labelled").await?;
 
     let sql = r#"update ad.temp_idents
         set id_value = 'ISRCTN'||id_value
         where id_desc ilike '%ISRCTN%'
         and id_value not like 'NCT%'
         and id_type_id is null"#;
-    execute_sql(sql, pool).await?;
+    ex.execute(sql).await?;
 
     let sql = r#"update ad.temp_idents
         set id_value = substring(id_value from 'ISRCTN[0-9]{8}'),
@@ -722,7 +722,7 @@ pub async fn find_isrctn_identities(pool: &Pool<Postgres>) -> Result<(), AppErro
         source_org = 'Springer Nature'
         where id_value ~ 'ISRCTN[0-9]{8}'
         and id_type_id is null"#;
-    execute_sql_fb(sql, pool, "Additional ISRCTN", "ound after repairing data").await?;
+    execute_sql_fb(sql, ex, "Additional ISRCTN", "ound after repairing data").await?;
        
     let sql = r#"update ad.temp_idents
         set id_type_id = 2126,
@@ -732,7 +732,7 @@ pub async fn find_isrctn_identities(pool: &Pool<Postgres>) -> Result<(), AppErro
         where (id_value ~ 'ISRCTN[0-9]{4,7}'
         or id_value ~ 'ISRCTN[0-9]{9,10}')
         and id_type_id is null"#;
-    execute_sql_fb(sql, pool, "Malformed ISRCTN", "labelled").await?;
+    execute_sql_fb(sql, ex, "Malformed ISRCTN", "labelled").await?;
     info!("");
 
     Ok(())
@@ -1,12 +1,12 @@
 
-use super::idents_utils::{execute_sql, execute_sql_fb, replace_string_in_ident};
+use super::idents_utils::{execute_sql_fb, replace_string_in_ident};
 
-use sqlx::{Pool, Postgres};
+use crate::mdr::executor::SqlExecutor;
 use crate::AppError;
 use log::info;
 
 
-pub async fn find_japanese_registry_identities(pool: &Pool<Postgres>) -> Result<(), AppError> {  
+pub async fn find_japanese_registry_identities(ex: &SqlExecutor) -> Result<(), AppError> {  
 
     // UMIN 
 
@@ -18,9 +18,9 @@ pub async fn find_japanese_registry_identities(pool: &Pool<Postgres>) -> Result<
         source_org = 'University Hospital Medical Information Network'
         where id_value ~ '^C000[0-9]{6}'
         or (id_value ~ 'C000[0-9]{6}' and id_value like '%UMIN%')"#;
-    let res1 = execute_sql(sql, pool).await?;
+    let res1 = ex.execute(sql).await?;
 
-    replace_string_in_ident("UMIN 0", "UMIN0", pool).await?;
+    replace_string_in_ident("UMIN 0", "UMIN0", ex).await?;
 
     // UMIN Ids present in both the id_value and the id_desc fields
 
@@ -31,7 +31,7 @@ pub async fn find_japanese_registry_identities(pool: &Pool<Postgres>) -> Result<
         source_org_id = 100156,
         source_org = 'University Hospital Medical Information Network'
         where id_value ~ 'UMIN[0-9]{9}'"#;
-    let res2 = execute_sql(sql, pool).await?;
+    let res2 = ex.execute(sql).await?;
 
     let sql = r#"update ad.temp_idents
         set id_value = 'JPRN-'||substring(id_desc from 'UMIN[0-9]{9}'),
@@ -40,14 +40,14 @@ pub async fn find_japanese_registry_identities(pool: &Pool<Postgres>) -> Result<
         source_org_id = 100156,
         source_org = 'University Hospital Medical Information Network'
         where id_desc ~ 'UMIN[0-9]{9}'"#;
-    let res3 = execute_sql(sql, pool).await?;
+    let res3 = ex.execute(sql).await?;
 
     let sql = r#"update ad.temp_idents
         set id_value = 'UMIN'||id_value
         where id_desc ~ 'UMIN'
         and id_value ~ '[0-9]{9}'
         and id_type_id is null"#;
-    execute_sql(sql, pool).await?;
+    ex.execute(sql).await?;
 
     let sql = r#"update ad.temp_idents
         set id_value = 'JPRN-'||substring(id_value from 'UMIN[0-9]{9}'),
@@ -57,7 +57,7 @@ pub async fn find_japanese_registry_identities(pool: &Pool<Postgres>) -> Result<
         source_org = 'University Hospital Medical Information Network'
         where id_value ~ 'UMIN[0-9]{9}'
         and id_type_id is null"#;
-    let res4 = execute_sql(sql, pool).await?;
+    let res4 = ex.execute(sql).await?;
 
     info!("{} UMIN japanese identifiers found and labelled", res1.rows_affected() + 
                         res2.rows_affected() + res3.rows_affected() + res4.rows_affected());	
@@ -69,18 +69,18 @@ pub async fn find_japanese_registry_identities(pool: &Pool<Postgres>) -> Result<
         source_org = 'University Hospital Medical Information Network'
         where id_value ~ 'UMIN[0-9]{8}'
         and id_type_id is null"#;
-    execute_sql_fb(sql, pool, "Malformed UMIN", "labelled").await?;
+    execute_sql_fb(sql, ex, "Malformed UMIN", "labelled").await?;
     info!("");
 
     // jRCT
 
-    replace_string_in_ident("jRCT ", "jRCT", pool).await?; 
+    replace_string_in_ident("jRCT ", "jRCT", ex).await?; 
 
     let sql = r#"update ad.temp_idents
         set id_value = 'jRCT'||id_value
         where id_value ~ '^[0-9]{10}'
         and id_desc = 'jRCT'"#;
-    execute_sql(sql, pool).await?;
+    ex.execute(sql).await?;
 
     let sql = r#"update ad.temp_idents
         set id_value = 'JPRN-'||substring(id_value from 'jRCT[0-9]{10}'),
@@ -89,7 +89,7 @@ pub async fn find_japanese_registry_identities(pool: &Pool<Postgres>) -> Result<
         source_org_id = 0,
         source_org = 'Japan Registry of Clinical Trials'
         where id_value ~ 'jRCT[0-9]{10}'"#;
-    let res1 = execute_sql(sql, pool).await?;
+    let res1 = ex.execute(sql).await?;
 
     let sql = r#"update ad.temp_idents
         set id_value = 'JPRN-'||substring(id_value from 'jRCTs[0-9]{9}'),
@@ -98,16 +98,16 @@ pub async fn find_japanese_registry_identities(pool: &Pool<Postgres>) -> Result<
         source_org_id = 0,
         source_org = 'Japan Registry of Clinical Trials'
         where id_value ~ 'jRCTs[0-9]{9}'"#;
-    let res2 = execute_sql(sql, pool).await?;
+    let res2 = ex.execute(sql).await?;
 
     info!("{} jCRT japanese identifiers found and labelled", res1.rows_affected() + res2.rows_affected());	
     info!("");
 
-    replace_string_in_ident("JAPIC", "Japic", pool).await?; 
-    replace_string_in_ident("JapicCTI- ", "JapicCTI-", pool).await?; 
-    replace_string_in_ident("Japic CTI-", "JapicCTI-", pool).await?; 
-    replace_string_in_ident("JapicCTI0", "JapicCTI-0", pool).await?; 
-    replace_string_in_ident("JapicCTI-22-", "JapicCTI-22", pool).await?; 
+    replace_string_in_ident("JAPIC", "Japic", ex).await?; 
+    replace_string_in_ident("JapicCTI- ", "JapicCTI-", ex).await?; 
+    replace_string_in_ident("Japic CTI-", "JapicCTI-", ex).await?; 
+    replace_string_in_ident("JapicCTI0", "JapicCTI-0", ex).await?; 
+    replace_string_in_ident("JapicCTI-22-", "JapicCTI-22", ex).await?; 
 
     let sql = r#"update ad.temp_idents
         set id_value = substring(id_value from 'JapicCTI-[0-9]{6}'),
@@ -116,7 +116,7 @@ pub async fn find_japanese_registry_identities(pool: &Pool<Postgres>) -> Result<
         source_org_id = 100157,
         source_org = 'Japan Pharmaceutical Information Center'
         where id_value ~ 'JapicCTI-[0-9]{6}'"#;
-    let res1 = execute_sql(sql, pool).await?;
+    let res1 = ex.execute(sql).await?;
 
     let sql = r#"update ad.temp_idents
         set id_value = substring(id_value from 'JapicCTI-R[0-9]{6}'),
@@ -125,14 +125,14 @@ pub async fn find_japanese_registry_identities(pool: &Pool<Postgres>) -> Result<
         source_org_id = 100157,
         source_org = 'Japan Pharmaceutical Information Center'
         where id_value ~ 'JapicCTI-R[0-9]{6}'"#;
-    let res2 = execute_sql(sql, pool).await?;
+    let res2 = ex.execute(sql).await?;
 
     let sql = r#"update ad.temp_idents
         set id_value = 'JapicCTI-'||substring(id_value from '[0-9]{6}') 
         where id_desc ilike '%JAPIC%'
         and id_value ~ '[0-9]{6}'
         and id_type_id is null"#;
-    execute_sql(sql, pool).await?;
+    ex.execute(sql).await?;
 
     let sql = r#"update ad.temp_idents
         set id_type_id = 139,
@@ -141,7 +141,7 @@ pub async fn find_japanese_registry_identities(pool: &Pool<Postgres>) -> Result<
         source_org = 'Japan Pharmaceutical Information Center'
         where id_value ~ 'JapicCTI-[0-9]{6}'
         and id_type_id is null"#;
-    let res4 = execute_sql(sql, pool).await?;
+    let res4 = ex.execute(sql).await?;
 
     info!("{} JAPIC japanese identifiers found and labelled", res1.rows_affected() + res2.rows_affected() + res4.rows_affected());	
     
@@ -152,7 +152,7 @@ pub async fn find_japanese_registry_identities(pool: &Pool<Postgres>) -> Result<
         source_org = 'Japan Pharmaceutical Information Center'
         where id_value ~ 'JapicCTI-[0-9]{5}'
         and id_type_id is null"#;
-    execute_sql_fb(sql, pool, "Malformed UMIN", "labelled").await?;
+    execute_sql_fb(sql, ex, "Malformed UMIN", "labelled").await?;
     info!("");
 
 
@@ -163,19 +163,19 @@ pub async fn find_japanese_registry_identities(pool: &Pool<Postgres>) -> Result<
         source_org_id = 100158,
         source_org = 'Japan Medical Association Center for Clinical Trials'
         where id_value ~ 'JMA-IIA[0-9]{5}'"#;
-    execute_sql_fb(sql, pool, "JMA japanese", "found and labelled").await?;
+    execute_sql_fb(sql, ex, "JMA japanese", "found and labelled").await?;
 
     info!("");
     Ok(())
 }
 
 
-pub async fn find_chinese_registry_identities(pool: &Pool<Postgres>) -> Result<(), AppError> {  
+pub async fn find_chinese_registry_identities(ex: &SqlExecutor) -> Result<(), AppError> {  
 
     // ChiCTR
 
-    replace_string_in_ident("chiCTR", "ChiCTR", pool).await?; 
-    replace_string_in_ident("CHiCTR", "ChiCTR", pool).await?; 
+    replace_string_in_ident("chiCTR", "ChiCTR", ex).await?; 
+    replace_string_in_ident("CHiCTR", "ChiCTR", ex).await?; 
 
     let sql = r#"update ad.temp_idents
         set id_value = substring(id_value from 'ChiCTR[0-9]{10}'),
@@ -184,7 +184,7 @@ pub async fn find_chinese_registry_identities(pool: &Pool<Postgres>) -> Result<(
         source_org_id = 100494,
         source_org = 'West China Hospital'
         where id_value ~ 'ChiCTR[0-9]{10}'"#;
-    let res1 = execute_sql(sql, pool).await?;
+    let res1 = ex.execute(sql).await?;
 
     let sql = r#"update ad.temp_idents
         set id_value = substring(id_value from 'ChiCTR-[A-Z]{3,5}-[0-9]{8}'),
@@ -193,7 +193,7 @@ pub async fn find_chinese_registry_identities(pool: &Pool<Postgres>) -> Result<(
         source_org_id = 100494,
         source_org = 'West China Hospital'
         where id_value ~ 'ChiCTR-[A-Z]{3,5}-[0-9]{8}'"#;
-    let res2 = execute_sql(sql, pool).await?;
+    let res2 = ex.execute(sql).await?;
     info!("{} ChiCTR Chinese identifiers found and labelled", res1.rows_affected() + res2.rows_affected());	
 
     let sql = r#"update ad.temp_idents
@@ -203,7 +203,7 @@ pub async fn find_chinese_registry_identities(pool: &Pool<Postgres>) -> Result<(
         source_org = 'West China Hospital'
         where id_value ~ 'ChiCTR[0-9]{7,9}'
         and id_type_id is null"#;
-    execute_sql_fb(sql, pool, "Malformed ChiCTR", "labelled").await?;
+    execute_sql_fb(sql, ex, "Malformed ChiCTR", "labelled").await?;
     info!("");
 
    // ITMC 
@@ -215,14 +215,14 @@ pub async fn find_chinese_registry_identities(pool: &Pool<Postgres>) -> Result<(
         source_org_id = 0102245,
         source_org = 'China Academy of Chinese Medical Sciences'
         where id_value ~ 'ITMCTR[0-9]{10}'"#;
-    execute_sql_fb(sql, pool, "ITMCTR Trad Medicine", "found and labelled").await?;
+    execute_sql_fb(sql, ex, "ITMCTR Trad Medicine", "found and labelled").await?;
 
     // Hong Kong
     
     let sql = r#"update ad.temp_idents
     set id_value = replace(id_value, ' ', '')
     where id_value ilike '%HKUCTR%'"#;
-    execute_sql(sql, pool).await?;
+    ex.execute(sql).await?;
 
     let sql = r#"update ad.temp_idents
         set id_value = substring(id_value from 'HKUCTR-[0-9]{1,4}'),
@@ -232,14 +232,14 @@ pub async fn find_chinese_registry_identities(pool: &Pool<Postgres>) -> Result<(
         source_org = 'The University of Hong Kong'
         where id_value ~ 'HKUCTR-[0-9]{1,4}'
         or id_value ~ 'HKCTR-[0-9]{1,4}'"#;
-    execute_sql_fb(sql, pool, "HKUCTR Hong Kong", "found and labelled").await?;
+    execute_sql_fb(sql, ex, "HKUCTR Hong Kong", "found and labelled").await?;
     info!("");
 
     Ok(())
 }
 
 
-pub async fn find_other_asian_registry_identities(pool: &Pool<Postgres>) -> Result<(), AppError> {  
+pub async fn find_other_asian_registry_identities(ex: &SqlExecutor) -> Result<(), AppError> {  
 
     // CTRI
 
@@ -250,14 +250,14 @@ pub async fn find_other_asian_registry_identities(pool: &Pool<Postgres>) -> Resu
         source_org_id = 102044,
         source_org = 'Indian Council of Medical Research'
         where id_value ~ 'CTRI/20[0-9]{2}/[0-9]{2,3}/[0-9]{6}'"#;
-    execute_sql_fb(sql, pool, "CTRI Indian", "found and labelled").await?;
+    execute_sql_fb(sql, ex, "CTRI Indian", "found and labelled").await?;
 
     let sql = r#"update ad.temp_idents
         set id_value = 'CTRI/'||substring(id_value from '20[0-9]{2}/[0-9]{2}/[0-9]{6}')
         where id_value ~ '20[0-9]{2}/[0-9]{2}/[0-9]{6}'
         and (id_desc ~ 'CTRI' or id_desc ~ 'Ind')
         and id_type_id is null"#;
-    execute_sql(sql, pool).await?; 
+    ex.execute(sql).await?; 
 
     let sql = r#"update ad.temp_idents
         set id_value = replace (substring(id_value from 'CTRI/20[0-9]{2}/[0-9]{2,3}/[0-9]{6}'), '/', '-'),
@@ -266,7 +266,7 @@ pub async fn find_other_asian_registry_identities(pool: &Pool<Postgres>) -> Resu
         source_org_id = 102044,
         source_org = 'Indian Council of Medical Research'
         where id_value ~ 'CTRI/20[0-9]{2}/[0-9]{2,3}/[0-9]{6}'"#;
-    execute_sql_fb(sql, pool, "Additional CTRI Indian", "found and labelled after data repair").await?;
+    execute_sql_fb(sql, ex, "Additional CTRI Indian", "found and labelled after data repair").await?;
 
      let sql = r#"update ad.temp_idents
         set id_type_id = 2121,
@@ -275,7 +275,7 @@ pub async fn find_other_asian_registry_identities(pool: &Pool<Postgres>) -> Resu
         source_org = 'Indian Council of Medical Research'
         where id_value ~ 'REFCTRI' 
         and id_type_id is null"#;
-    execute_sql_fb(sql, pool, "Malformed CTRI", "labelled").await?;
+    execute_sql_fb(sql, ex, "Malformed CTRI", "labelled").await?;
 
     // Tidy up or these incorrectly labelled IDs are confusing!
 
@@ -284,12 +284,12 @@ pub async fn find_other_asian_registry_identities(pool: &Pool<Postgres>) -> Resu
         where ((id_desc ~ 'CTRI' and id_desc !~ 'OCTRI' and id_desc !~ 'ECTRI')
         or (id_desc ~ 'egistry' and id_desc ~ 'Ind'))
         and id_type_id is null"#;
-    execute_sql(sql, pool).await?; 
+    ex.execute(sql).await?; 
 
 
     // SRi-LANKA
 
-    replace_string_in_ident("SLCTR/ ", "SLCTR/", pool).await?; 
+    replace_string_in_ident("SLCTR/ ", "SLCTR/", ex).await?; 
 
     let sql = r#"update ad.temp_idents
         set id_value = replace(substring(id_value from 'SLCTR/20[0-9]{2}/[0-9]{3}'),  '/', '-'),
@@ -298,7 +298,7 @@ pub async fn find_other_asian_registry_identities(pool: &Pool<Postgres>) -> Resu
         source_org_id = 0,
         source_org = 'Sri Lanka Medical Association'
         where id_value ~ 'SLCTR/20[0-9]{2}/[0-9]{3}'"#;
-    execute_sql_fb(sql, pool, "SLCTR Sri Lankan", "found and labelled").await?;
+    execute_sql_fb(sql, ex, "SLCTR Sri Lankan", "found and labelled").await?;
     
     // KCT 
 
@@ -310,7 +310,7 @@ pub async fn find_other_asian_registry_identities(pool: &Pool<Postgres>) -> Resu
         source_org = 'Korea Disease Control and Prevention Agency '
         where id_value ~ 'KCT[0-9]{7}'
         and id_value !~ 'MKKCT[0-9]{7}'"#;
-    execute_sql_fb(sql, pool, "KCT Korean", "found and labelled").await?;
+    execute_sql_fb(sql, ex, "KCT Korean", "found and labelled").await?;
 
     // THAI
 
@@ -319,7 +319,7 @@ pub async fn find_other_asian_registry_identities(pool: &Pool<Postgres>) -> Resu
         where id_desc = 'TCTR'
         and id_value ~ '^20[0-9]{9}$'
         and id_type_id is null"#;
-    execute_sql(sql, pool).await?; 
+    ex.execute(sql).await?; 
 
     let sql = r#"update ad.temp_idents
         set id_value = substring(id_value from 'TCTR20[0-9]{9}'),
@@ -328,18 +328,18 @@ pub async fn find_other_asian_registry_identities(pool: &Pool<Postgres>) -> Resu
         source_org_id = 0,
         source_org = 'Central Research Ethics Committee, Thailand'
         where id_value ~ 'TCTR20[0-9]{9}'"#;
-    execute_sql_fb(sql, pool, "TCTR Thai", "found and labelled").await?;
+    execute_sql_fb(sql, ex, "TCTR Thai", "found and labelled").await?;
     info!("");
 
     Ok(())
 }
 
 
-pub async fn find_middle_eastern_registry_identities(pool: &Pool<Postgres>) -> Result<(), AppError> {  
+pub async fn find_middle_eastern_registry_identities(ex: &SqlExecutor) -> Result<(), AppError> {  
 
     // IRCT
 
-    replace_string_in_ident("IRCT2020-", "IRCT2020", pool).await?; 
+    replace_string_in_ident("IRCT2020-", "IRCT2020", ex).await?; 
 
     let sql = r#"update ad.temp_idents
         set id_value = substring(id_value from 'IRCT[0-9]{11,14}N[0-9]{1,2}'),
@@ -348,7 +348,7 @@ pub async fn find_middle_eastern_registry_identities(pool: &Pool<Postgres>) -> R
         source_org_id = 0,
         source_org = 'Iranian Ministry of Health and Medical Education'
         where id_value ~ 'IRCT[0-9]{11,14}N[0-9]{1,2}'"#;
-    execute_sql_fb(sql, pool, "IRCT Iranian", "found and labelled").await?;
+    execute_sql_fb(sql, ex, "IRCT Iranian", "found and labelled").await?;
 
     // LEBANESE
 
@@ -359,14 +359,14 @@ pub async fn find_middle_eastern_registry_identities(pool: &Pool<Postgres>) -> R
         source_org_id = 0,
         source_org = 'Lebanese Ministry of Public Health'
         where id_value ~ 'LBCTR20[0-9]{8}'"#;
-    execute_sql_fb(sql, pool, "LBCTR Lebanese", "found and labelled").await?;
+    execute_sql_fb(sql, ex, "LBCTR Lebanese", "found and labelled").await?;
     info!("");
 
     Ok(())
 }
 
 
-pub async fn find_latin_american_registry_identities(pool: &Pool<Postgres>) -> Result<(), AppError> {  
+pub async fn find_latin_american_registry_identities(ex: &SqlExecutor) -> Result<(), AppError> {  
     
     // RBR
 
@@ -377,7 +377,7 @@ pub async fn find_latin_american_registry_identities(pool: &Pool<Postgres>) -> R
         source_org_id = 109251,
         source_org = 'Instituto Oswaldo Cruz'
         where id_value ~ 'RBR-[0-9a-z]{6,8}'"#;
-    execute_sql_fb(sql, pool, "RBR Brazilian", "found and labelled").await?;
+    execute_sql_fb(sql, ex, "RBR Brazilian", "found and labelled").await?;
 
     // PERU
 
@@ -386,7 +386,7 @@ pub async fn find_latin_american_registry_identities(pool: &Pool<Postgres>) -> R
         where id_desc ~ 'REPEC'
         and id_value ~ '^[0-9]{5}$'
         and id_type_id is null"#;
-    execute_sql(sql, pool).await?; 
+    ex.execute(sql).await?; 
 
     let sql = r#"update ad.temp_idents
         set id_value = substring(id_value from 'PER-[0-9]{3}-[0-9]{2}'),
@@ -395,7 +395,7 @@ pub async fn find_latin_american_registry_identities(pool: &Pool<Postgres>) -> R
         source_org_id = 0,
         source_org = 'National Institute of Health, Peru'
         where id_value ~ '^PER-[0-9]{3}-[0-9]{2}'"#;
-    execute_sql_fb(sql, pool, "PER Peruvian", "found and labelled").await?;
+    execute_sql_fb(sql, ex, "PER Peruvian", "found and labelled").await?;
 
     // CUBA
 
@@ -406,14 +406,14 @@ pub async fn find_latin_american_registry_identities(pool: &Pool<Postgres>) -> R
         source_org_id = 0,
         source_org = 'The National Coordinating Center of Clinical Trials, Cuba'
         where id_value ~ 'RPCEC[0-9]{8}'"#;
-    execute_sql_fb(sql, pool, "RPCEC Cuban", "found and labelled").await?;
+    execute_sql_fb(sql, ex, "RPCEC Cuban", "found and labelled").await?;
     info!("");
 
     Ok(())
 }
 
 
-pub async fn find_other_registry_identities(pool: &Pool<Postgres>) -> Result<(), AppError> {  
+pub async fn find_other_registry_identities(ex: &SqlExecutor) -> Result<(), AppError> {  
 
     // WHO number 
 
@@ -424,7 +424,7 @@ pub async fn find_other_registry_identities(pool: &Pool<Postgres>) -> Result<(),
         source_org_id = 100114,
         source_org = 'World Health Organisation'
         where id_value ~ '1111-[0-9]{4}-[0-9]{4}'"#;
-    execute_sql_fb(sql, pool, "WHO UTN", "found and labelled").await?;
+    execute_sql_fb(sql, ex, "WHO UTN", "found and labelled").await?;
 
     // Some data tidying
 
@@ -432,32 +432,32 @@ pub async fn find_other_registry_identities(pool: &Pool<Postgres>) -> Result<(),
         set id_value = substring(substring(id_value from 'U1111[0-9]{8}'), 1, 5)||'-'||substring(substring(id_value from 'U1111[0-9]{8}'), 6, 4)
         ||'-'||substring(substring(id_value from 'U1111[0-9]{8}'), 10, 4)
         where id_value ~ 'U1111[0-9]{8}' and id_type_id is null;"#;
-    execute_sql(sql, pool).await?; 
+    ex.execute(sql).await?; 
 
     let sql = r#"update ad.temp_idents
         set id_value = substring(substring(id_value from 'U1111-[0-9]{8}'), 1, 10)||'-'||substring(substring(id_value from 'U1111-[0-9]{8}'), 11, 4)
         where id_value ~ 'U1111-[0-9]{8}' and id_type_id is null;"#;
-    execute_sql(sql, pool).await?; 
+    ex.execute(sql).await?; 
 
     let sql = r#"update ad.temp_idents
         set id_value = replace(substring(id_value from 'U1111 [0-9]{4} [0-9]{4}'), ' ', '-')
         where id_value ~ 'U1111 [0-9]{4} [0-9]{4}' and id_type_id is null;"#;
-    execute_sql(sql, pool).await?; 
+    ex.execute(sql).await?; 
 
     let sql = r#"update ad.temp_idents
         set id_value = replace(substring(id_value from 'U1111-[0-9]{4}- [0-9]{4}'), ' ', '')
         where id_value ~ 'U1111-[0-9]{4}- [0-9]{4}' and id_type_id is null;"#;
-    execute_sql(sql, pool).await?; 
+    ex.execute(sql).await?; 
 
     let sql = r#"update ad.temp_idents
         set id_value = 'U1111'||substring(substring(id_value from 'U111-[0-9]{4}-[0-9]{4}'), 5, 10)
         where id_value ~ 'U111-[0-9]{4}-[0-9]{4}' and id_type_id is null;"#;
-    execute_sql(sql, pool).await?; 
+    ex.execute(sql).await?; 
 
     let sql = r#"update ad.temp_idents
         set id_value = 'U1111'||substring(substring(id_value from 'U111-[0-9]{4} [0-9]{4}'), 5, 5)||'-'||substring(substring(id_value from 'U111-[0-9]{4} [0-9]{4}'), 11, 4)
         where id_value ~ 'U111-[0-9]{4} [0-9]{4}' and id_type_id is null;"#;
-    execute_sql(sql, pool).await?; 
+    ex.execute(sql).await?; 
 
     let sql = r#"update ad.temp_idents
         set id_value = 'U'||substring(id_value from '1111-[0-9]{4}-[0-9]{4}'),
@@ -467,7 +467,7 @@ pub async fn find_other_registry_identities(pool: &Pool<Postgres>) -> Result<(),
         source_org = 'World Health Organisation'
         where id_value ~ '1111-[0-9]{4}-[0-9]{4}'
         and id_type_id is null"#;
-    execute_sql_fb(sql, pool, "Additional WHO UTN", "found and labelled after data repair").await?;
+    execute_sql_fb(sql, ex, "Additional WHO UTN", "found and labelled after data repair").await?;
 
     let sql = r#"update ad.temp_idents
         set id_type_id = 2115,
@@ -477,15 +477,15 @@ pub async fn find_other_registry_identities(pool: &Pool<Postgres>) -> Result<(),
         where (id_value ~ 'U1111-[0-9]{4}$'
         or id_value ~ 'U1111-[0-9]{4}-[0-9]{3}$')
         and id_type_id is null"#;
-    execute_sql_fb(sql, pool, "Malformed WHO UTN", "labelled").await?;
+    execute_sql_fb(sql, ex, "Malformed WHO UTN", "labelled").await?;
     info!("");
 
     // ACTRN
 
-    replace_string_in_ident("ACTRN0", "ACTRN", pool).await?;  // preliminary tidying
-    replace_string_in_ident("ACTR0", "ACTRN", pool).await?; 
-    replace_string_in_ident("ACTRNO", "ACTRN", pool).await?;  // of a few records
-    replace_string_in_ident("ACTRN 1", "ACTRN1", pool).await?;  
+    replace_string_in_ident("ACTRN0", "ACTRN", ex).await?;  // preliminary tidying
+    replace_string_in_ident("ACTR0", "ACTRN", ex).await?; 
+    replace_string_in_ident("ACTRNO", "ACTRN", ex).await?;  // of a few records
+    replace_string_in_ident("ACTRN 1", "ACTRN1", ex).await?;  
 
     let sql = r#"update ad.temp_idents
         set id_value = substring(id_value from 'ACTRN[0-9]{14}'),
@@ -494,14 +494,14 @@ pub async fn find_other_registry_identities(pool: &Pool<Postgres>) -> Result<(),
         source_org_id = 100690,
         source_org = 'National Health and Medical Research Council, Australia'
         where id_value ~ 'ACTRN[0-9]{14}'"#;
-    execute_sql_fb(sql, pool, "ACTRN Australian / NZ", "found and labelled").await?;
+    execute_sql_fb(sql, ex, "ACTRN Australian / NZ", "found and labelled").await?;
 
     let sql = r#"update ad.temp_idents
         set id_value = 'ACTRN'||substring(id_value from '[0-9]{14}')
         where ((id_desc ~ 'ACTR' and id_desc !~ 'PACTR')  or id_desc ~ 'Aust')
         and id_value ~ '[0-9]{14}'
         and id_type_id is null"#;
-    execute_sql(sql, pool).await?; 
+    ex.execute(sql).await?; 
     
     let sql = r#"update ad.temp_idents
         set id_value = substring(id_value from 'ACTRN[0-9]{14}'),
@@ -511,7 +511,7 @@ pub async fn find_other_registry_identities(pool: &Pool<Postgres>) -> Result<(),
         source_org = 'National Health and Medical Research Council, Australia'
         where id_value ~ 'ACTRN[0-9]{14}'
         and id_type_id is null"#;
-    execute_sql_fb(sql, pool, "Additional CTRN Australian / NZ", "found after data repair").await?;
+    execute_sql_fb(sql, ex, "Additional CTRN Australian / NZ", "found after data repair").await?;
 
     let sql = r#"update ad.temp_idents
         set id_value = substring(id_value from 'ACTRN[0-9]{12,13}'),
@@ -521,7 +521,7 @@ pub async fn find_other_registry_identities(pool: &Pool<Postgres>) -> Result<(),
         source_org = 'National Health and Medical Research Council, Australia'
         where id_value ~ 'ACTRN[0-9]{12,13}'
         and id_type_id is null"#;
-    execute_sql_fb(sql, pool, "Malformed CTRN Australian / NZ", "labelled").await?;
+    execute_sql_fb(sql, ex, "Malformed CTRN Australian / NZ", "labelled").await?;
 
     info!("");
 
@@ -531,7 +531,7 @@ pub async fn find_other_registry_identities(pool: &Pool<Postgres>) -> Result<(),
         set id_value = 'PACTR'||id_value
         where id_desc ~ 'PACTR'
         and id_value ~ '^[0-9]{15,16}$'"#;
-    execute_sql(sql, pool).await?; 
+    ex.execute(sql).await?; 
     
     let sql = r#"update ad.temp_idents
         set id_value = substring(id_value from 'PACTR[0-9]{15,16}'),
@@ -540,7 +540,7 @@ pub async fn find_other_registry_identities(pool: &Pool<Postgres>) -> Result<(),
         source_org_id = 0,
         source_org = 'Cochrane South Africa'
         where id_value ~ 'PACTR[0-9]{15,16}'"#;
-    execute_sql_fb(sql, pool, "PACTR Pan African", "found and labelled").await?;
+    execute_sql_fb(sql, ex, "PACTR Pan African", "found and labelled").await?;
 
 
     // South African National register
@@ -552,7 +552,7 @@ pub async fn find_other_registry_identities(pool: &Pool<Postgres>) -> Result<(),
         source_org_id = 0,
         source_org = 'Cochrane South Africa'
         where id_value like '%DOH-27%'"#;
-    execute_sql_fb(sql, pool, "South African National CTR", "found and labelled").await?;
+    execute_sql_fb(sql, ex, "South African National CTR", "found and labelled").await?;
 
     info!("");
     Ok(())
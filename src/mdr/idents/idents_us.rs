@@ -1,12 +1,12 @@
-use super::idents_utils::{execute_sql, execute_sql_fb, replace_string_in_ident};
+use super::idents_utils::{execute_sql_fb, replace_string_in_ident};
 
 
-use sqlx::{Pool, Postgres};
+use crate::mdr::executor::SqlExecutor;
 use crate::AppError;
 use log::info;
 
 
-pub async fn find_us_nci_identifiers(pool: &Pool<Postgres>) -> Result<(), AppError> { 
+pub async fn find_us_nci_identifiers(ex: &SqlExecutor) -> Result<(), AppError> { 
        
     // NCI CTRP
 
@@ -17,7 +17,7 @@ pub async fn find_us_nci_identifiers(pool: &Pool<Postgres>) -> Result<(), AppErr
         source_org_id = 100162,
         source_org = 'National Cancer Institute'
         where id_value ~ 'NCI-20[0-9]{2}-[0-9]{5}' "#;
-    execute_sql_fb(sql, pool, "NCI CTRP", "found and labelled").await?;
+    execute_sql_fb(sql, ex, "NCI CTRP", "found and labelled").await?;
 
     // PDQ
 
@@ -28,7 +28,7 @@ pub async fn find_us_nci_identifiers(pool: &Pool<Postgres>) -> Result<(), AppErr
         source_org_id = 100162,
         source_org = 'National Cancer Institute'
         where id_value ~ 'CDR[0-9]{9,10}'"#;
-    execute_sql_fb(sql, pool, "CDR NCI PDQ", "found and labelled").await?;
+    execute_sql_fb(sql, ex, "CDR NCI PDQ", "found and labelled").await?;
      
     // NCI grants
 
@@ -42,7 +42,7 @@ pub async fn find_us_nci_identifiers(pool: &Pool<Postgres>) -> Result<(), AppErr
             or id_value ~ '^REBACCC')
             and id_type_id is null
             and id_class = 'OTHER_GRANT'"#;
-    execute_sql_fb(sql, pool, "NCI grant", "found and labelled").await?;
+    execute_sql_fb(sql, ex, "NCI grant", "found and labelled").await?;
 
     let sql = r#"update ad.temp_idents
         set id_type_id = 178,
@@ -54,18 +54,18 @@ pub async fn find_us_nci_identifiers(pool: &Pool<Postgres>) -> Result<(), AppErr
             or id_value ~ '^REBACCC')
             and id_type_id is null
             and (id_class is null or id_class <> 'OTHER_GRANT')"#;
-    execute_sql_fb(sql, pool, "Other NCI", "found and labelled").await?;
+    execute_sql_fb(sql, ex, "Other NCI", "found and labelled").await?;
     info!("");
     Ok(())
 
 }
 
 
-pub async fn find_us_cdc_identifiers(pool: &Pool<Postgres>) -> Result<(), AppError> { 
+pub async fn find_us_cdc_identifiers(ex: &SqlExecutor) -> Result<(), AppError> { 
   
-    replace_string_in_ident("CDC - N", "CDC-N", pool).await?;  
-    replace_string_in_ident("CDC N", "CDC-N", pool).await?;  
-    replace_string_in_ident("CDC IRB", "CDC-IRB", pool).await?;  
+    replace_string_in_ident("CDC - N", "CDC-N", ex).await?;  
+    replace_string_in_ident("CDC N", "CDC-N", ex).await?;  
+    replace_string_in_ident("CDC IRB", "CDC-IRB", ex).await?;  
 
     let sql = r#"update ad.temp_idents
         set id_type_id = 406,
@@ -82,7 +82,7 @@ pub async fn find_us_cdc_identifiers(pool: &Pool<Postgres>) -> Result<(), AppErr
             or id_value ilike 'CDC-O%'
             or id_value ilike 'CDC %')
             and id_class = 'OTHER_GRANT'"#;
-    execute_sql_fb(sql, pool, "CDC grant", "found and labelled").await?;
+    execute_sql_fb(sql, ex, "CDC grant", "found and labelled").await?;
    
     let sql = r#"update ad.temp_idents
         set id_type_id = 177,
@@ -98,13 +98,13 @@ pub async fn find_us_cdc_identifiers(pool: &Pool<Postgres>) -> Result<(), AppErr
             or id_value ilike 'CDC-O%'
             or id_value ilike 'CDC %')
             and (id_class is null or id_class <> 'OTHER_GRANT')"#;
-    execute_sql_fb(sql, pool, "Other CDC", "found and labelled").await?;
+    execute_sql_fb(sql, ex, "Other CDC", "found and labelled").await?;
     info!("");
     Ok(())
 }
 
 
-pub async fn find_nih_grant_identifiers(pool: &Pool<Postgres>) -> Result<(), AppError> { 
+pub async fn find_nih_grant_identifiers(ex: &SqlExecutor) -> Result<(), AppError> { 
 
     let sql = r#"update ad.temp_idents
         set id_type_id = 401,
@@ -112,7 +112,7 @@ pub async fn find_nih_grant_identifiers(pool: &Pool<Postgres>) -> Result<(), App
         source_org_id = 100134,
         source_org = 'National Institutes of Health'
         where id_class = 'NIH'"#;
-    let res1 = execute_sql(sql, pool).await?;
+    let res1 = ex.execute(sql).await?;
 
     let sql = r#"update ad.temp_idents
         set id_type_id = 401,
@@ -125,7 +125,7 @@ pub async fn find_nih_grant_identifiers(pool: &Pool<Postgres>) -> Result<(), App
         or id_desc ilike 'U.S. NIH Grant%'
         or (id_class = 'OTHER_GRANT' 
         and id_desc in ('nih', 'NIH', 'National Institutes of Health (NIH)', 'US NIH'))"#;
-    let res2 = execute_sql(sql, pool).await?;
+    let res2 = ex.execute(sql).await?;
 
     // attempts to get similar ids to the NIH ones created immediately above,
     // where they do not have type_id_descriptions indicating NIH
@@ -142,7 +142,7 @@ pub async fn find_nih_grant_identifiers(pool: &Pool<Postgres>) -> Result<(), App
             having count(id) >= 30) p
         where substring(a.id_value, 1, 4) = p.pref
         and a.id_type_id is null; "#;
-    let res3 = execute_sql(sql, pool).await?;
+    let res3 = ex.execute(sql).await?;
     info!("{} NIH grant identifiers found and labelled", res1.rows_affected() + res2.rows_affected() + res3.rows_affected());	
     info!("");
 
@@ -150,7 +150,7 @@ pub async fn find_nih_grant_identifiers(pool: &Pool<Postgres>) -> Result<(), App
 }
 
 
-pub async fn find_fda_identifiers(pool: &Pool<Postgres>) -> Result<(), AppError> { 
+pub async fn find_fda_identifiers(ex: &SqlExecutor) -> Result<(), AppError> { 
 
     let sql = r#"update ad.temp_idents
         set id_type_id = 403,
@@ -160,7 +160,7 @@ pub async fn find_fda_identifiers(pool: &Pool<Postgres>) -> Result<(), AppError>
         where id_class = 'FDA'
         or id_value ~ '^75F4'
         or id_value ~ '^HHSF'"#;
-    let res1 = execute_sql(sql, pool).await?;
+    let res1 = ex.execute(sql).await?;
 
     let sql = r#"update ad.temp_idents
         set id_type_id = 403,
@@ -172,7 +172,7 @@ pub async fn find_fda_identifiers(pool: &Pool<Postgres>) -> Result<(), AppError>
         'US FDA', 'USFDA', 'United States FDA', 'U.S. Food and Drug Administration',
         'US Food and Drug Administration', 'US FOOD AND DRUG ADMN')
         and id_value not ilike '%nih%'"#;
-    let res2 = execute_sql(sql, pool).await?;
+    let res2 = ex.execute(sql).await?;
     info!("{} FDA grant identifiers found and labelled", res1.rows_affected() + res2.rows_affected());	
 
     // FDA Orphan drug IDs
@@ -185,7 +185,7 @@ pub async fn find_fda_identifiers(pool: &Pool<Postgres>) -> Result<(), AppError>
         where id_desc ~ 'OOPD'
         or (id_desc ~ 'Orphan' and id_desc ~ 'FDA')
         or id_value ~ 'FD-R-[0-9]{4,7}'"#;
-    execute_sql_fb(sql, pool, "FDA orphan drug", "found and labelled").await?;
+    execute_sql_fb(sql, ex, "FDA orphan drug", "found and labelled").await?;
 
     // FDA IND / IDEIDs
 
@@ -207,7 +207,7 @@ pub async fn find_fda_identifiers(pool: &Pool<Postgres>) -> Result<(), AppError>
         and id_desc !~ 'Chin'  and id_desc !~ 'FDASU' and id_desc !~ 'Shire' and id_desc !~ 'Madrid' and id_desc !~ 'OHRP'
         and id_desc !~ 'Inulin'  and id_desc !~ 'JB' and id_desc !~ 'SFDA' and id_desc !~ 'CFDA' 
         and id_desc !~ 'TFDA' and id_desc !~ 'KFDA' and id_desc !~ 'FDAAA'"#;
-        execute_sql_fb(sql, pool, "FDA IND / IDE", "found and labelled").await?;
+        execute_sql_fb(sql, ex, "FDA IND / IDE", "found and labelled").await?;
 
     // Remaining FDA identifiers
 
@@ -223,7 +223,7 @@ pub async fn find_fda_identifiers(pool: &Pool<Postgres>) -> Result<(), AppError>
         and id_desc !~ 'Chin'  and id_desc !~ 'FDASU' and id_desc !~ 'Shire' and id_desc !~ 'Madrid' and id_desc !~ 'OHRP'
         and id_desc !~ 'Inulin'  and id_desc !~ 'JB' and id_desc !~ 'SFDA' and id_desc !~ 'CFDA' 
         and id_desc !~ 'TFDA' and id_desc !~ 'KFDA' and id_desc !~ 'FDAAA' and id_desc !~ 'State Food ' and id_value !~ '^VCU'"#;
-        execute_sql_fb(sql, pool, "Other FDA", "found and labelled").await?;
+        execute_sql_fb(sql, ex, "Other FDA", "found and labelled").await?;
 
     info!("");
     Ok(())
@@ -231,7 +231,7 @@ pub async fn find_fda_identifiers(pool: &Pool<Postgres>) -> Result<(), AppError>
 }
 
 
-pub async fn find_other_us_grant_identifiers(pool: &Pool<Postgres>) -> Result<(), AppError> { 
+pub async fn find_other_us_grant_identifiers(ex: &SqlExecutor) -> Result<(), AppError> { 
       
     // AHRQ
 
@@ -243,13 +243,13 @@ pub async fn find_other_us_grant_identifiers(pool: &Pool<Postgres>) -> Result<()
         where id_class = 'AHRQ'
         or id_desc ilike '%AHRQ%'
         or id_desc ilike '%Research Quality%'"#;
-    execute_sql_fb(sql, pool, "AHRQ grant", "found and labelled").await?;
+    execute_sql_fb(sql, ex, "AHRQ grant", "found and labelled").await?;
     
     let sql = r#"update ad.temp_idents
         set id_value = trim(replace(id_value, 'AHRQ', ''))
         where id_type_id = 402
         and id_value like '%AHRQ%'"#;
-    execute_sql(sql, pool).await?;
+    ex.execute(sql).await?;
    
     // SAMHSA
 
@@ -259,7 +259,7 @@ pub async fn find_other_us_grant_identifiers(pool: &Pool<Postgres>) -> Result<()
         source_org_id = 108270,
         source_org = 'Substance Abuse and Mental Health Services Administration'
         where id_class = 'SAMHSA'"#;
-    let res1 = execute_sql(sql, pool).await?;
+    let res1 = ex.execute(sql).await?;
 
     let sql = r#"update ad.temp_idents
         set id_type_id = 404,
@@ -269,7 +269,7 @@ pub async fn find_other_us_grant_identifiers(pool: &Pool<Postgres>) -> Result<()
         where id_class = 'OTHER_GRANT' 
         and (id_desc ilike '%SAMHSA%'
         or id_desc ilike '%substance abuse and mental health%')"#;
-    let res2 = execute_sql(sql, pool).await?;
+    let res2 = ex.execute(sql).await?;
 
     info!("{} SAMHSA grant identifiers found and labelled", res1.rows_affected() + res2.rows_affected());	
 
@@ -283,7 +283,7 @@ pub async fn find_other_us_grant_identifiers(pool: &Pool<Postgres>) -> Result<()
         where id_value ilike 'W81XWH%'
         or id_value ilike 'CDMRP%'
         or id_value ilike 'HT9425%'"#;
-    let res1 = execute_sql(sql, pool).await?;
+    let res1 = ex.execute(sql).await?;
 
     let sql = r#"update ad.temp_idents
         set id_type_id = 405,
@@ -294,7 +294,7 @@ pub async fn find_other_us_grant_identifiers(pool: &Pool<Postgres>) -> Result<()
         or id_desc ilike '%dept of defense%'
         or id_desc ilike '%dod%')
         and id_type_id is null"#;
-    let res2 = execute_sql(sql, pool).await?;
+    let res2 = ex.execute(sql).await?;
 
     info!("{} Department of Defense grant identifiers found and labelled", res1.rows_affected() + res2.rows_affected());	
     info!("");
@@ -7,17 +7,18 @@ mod idents_co;
 mod idents_oth;
 mod idents_utils;
 
-use super::utils::{execute_sql, execute_phased_transfer, vacuum_table};
-use idents_utils::{transfer_coded_identifiers, execute_temp_phased_transfer, 
+use super::utils::{save_checkpoint, restore_checkpoint};
+use super::stages::{self, StagePlan};
+use idents_utils::{transfer_coded_identifiers, 
           replace_string_in_ident, remove_both_ldtr_char_from_ident, remove_leading_char_from_ident, 
           switch_number_suffix_to_desc, execute_sql_fb, execute_sql_sfb};
 
-use sqlx::{Pool, Postgres};
+use super::executor::SqlExecutor;
 use crate::AppError;
 use log::info;
 
 
-pub async fn build_titles_table (pool: &Pool<Postgres>) -> Result<(), AppError> {  
+pub async fn build_titles_table (ex: &SqlExecutor) -> Result<(), AppError> {  
 
     let sql = r#"SET client_min_messages TO WARNING; 
     DROP TABLE IF EXISTS ad.study_titles;
@@ -33,14 +34,14 @@ pub async fn build_titles_table (pool: &Pool<Postgres>) -> Result<(), AppError>
     );
     CREATE INDEX study_titles_sid ON ad.study_titles(sd_sid);"#;
 
-	execute_sql(sql, pool).await?;
+	ex.execute(sql).await?;
     info!("study titles table (re)created");
     Ok(())
 
 }
 
 
-pub async fn build_idents_table (pool: &Pool<Postgres>) -> Result<(), AppError> {  
+pub async fn build_idents_table (ex: &SqlExecutor) -> Result<(), AppError> {  
 
         let sql = r#"SET client_min_messages TO WARNING; 
         DROP TABLE IF EXISTS ad.study_identifiers;
@@ -60,7 +61,7 @@ pub async fn build_idents_table (pool: &Pool<Postgres>) -> Result<(), AppError>
         );
         CREATE INDEX study_identifiers_sid ON ad.study_identifiers(sd_sid);"#;
 
-       execute_sql(sql, pool).await?;
+       ex.execute(sql).await?;
         info!("study identifiers table (re)created");
 
 
@@ -69,7 +70,7 @@ pub async fn build_idents_table (pool: &Pool<Postgres>) -> Result<(), AppError>
 }
 
  
-pub async fn load_titles_data (max_id: u64, pool: &Pool<Postgres>) -> Result<(), AppError> {  
+pub async fn load_titles_data (max_id: u64, ex: &SqlExecutor) -> Result<(), AppError> {  
 
     let chunk_size = 2000000;
 
@@ -78,22 +79,22 @@ pub async fn load_titles_data (max_id: u64, pool: &Pool<Postgres>) -> Result<(),
     let sql = r#"insert into ad.study_titles (sd_sid, title_type_id, title_text, is_default, comments)
         select nct_id, 15, brief_title, true, 'brief title in clinicaltrials.gov'
         from ctgov.studies c "#;
-    execute_phased_transfer(sql, max_id, chunk_size, " where ", "brief titles added", "ad.study_titles", pool).await?;
+    ex.phased_transfer(sql, max_id, chunk_size, " where ", "brief titles added", "ad.study_titles").await?;
 
     let sql = r#"insert into ad.study_titles (sd_sid, title_type_id, title_text, is_default, comments)
         select nct_id, 16, official_title, false, 'official title in clinicaltrials.gov'
         from ctgov.studies c
         where c.official_title is not null and c.official_title <> c.brief_title "#;
 
-    execute_phased_transfer(sql, max_id, chunk_size, " and ", "oficial titles added", "ad.study_titles", pool).await?;
+    ex.phased_transfer(sql, max_id, chunk_size, " and ", "oficial titles added", "ad.study_titles").await?;
 
     let sql = r#"insert into ad.study_titles (sd_sid, title_type_id, title_text, is_default)
         select nct_id, 14, acronym, false
         from ctgov.studies c 
         where acronym is not null "#;
 
-    execute_phased_transfer(sql, max_id, chunk_size, " and ", "acronyms added", "ad.study_titles", pool).await?;
-    vacuum_table("study_titles", pool).await?;
+    ex.phased_transfer(sql, max_id, chunk_size, " and ", "acronyms added", "ad.study_titles").await?;
+    ex.vacuum_table("study_titles").await?;
 
     Ok(())
 
@@ -101,260 +102,139 @@ pub async fn load_titles_data (max_id: u64, pool: &Pool<Postgres>) -> Result<(),
 
 
 
-pub async fn load_idents_data (processing: &str, max_id: u64, pool: &Pool<Postgres>) -> Result<(), AppError> {  
+pub async fn load_idents_data (plan: &StagePlan, data_date: &str, max_id: u64, ex: &SqlExecutor) -> Result<(), AppError> {  
 
     let chunk_size = 2000000;
 
-    if processing == "full" {
+    // The initial processing can be replaced by restoring the tables saved at the
+    // 'idents_base' checkpoint, when developing the later company identifier steps.
+
+    let base_tables = stages::checkpoint_tables("idents_base");
+
+    if !plan.restores("idents_base") {
 
         // Insert the nct ids themselves.
         
         let sql = r#"insert into ad.study_identifiers (sd_sid, id_value, id_type_id, id_type, source_org_id, source_org, id_date)
                                 select nct_id, nct_id, 120, 'NCT ID', 100133, 'National Library of Medicine', study_first_posted_date from ctgov.studies c "#;
-        execute_phased_transfer(sql, max_id, chunk_size, " where ", "nct ids", "ad.study_identifiers", pool).await?;
+        ex.phased_transfer(sql, max_id, chunk_size, " where ", "nct ids", "ad.study_identifiers").await?;
 
         // Insert the old NCT aliases (that have a distinct source id in the ctgov.id_information table). As this is the only insert that makes
         // use of the source_org_id field this allows that field to be ignored and not transferred to the temp_idents table (see below).
 
         let sql = r#"insert into ad.study_identifiers (sd_sid, id_value, id_type_id, id_type, source_org_id, source_org)
                                 select nct_id, id_value, 180, 'Obsolete NCT ID', 100133, 'National Library of Medicine' from ctgov.id_information c where id_source = 'nct_alias' "#;
-        execute_phased_transfer(sql, max_id, chunk_size, " and ", "old NCT aliases", "ad.study_identifiers", pool).await?;
+        ex.phased_transfer(sql, max_id, chunk_size, " and ", "old NCT aliases", "ad.study_identifiers").await?;
     
         // Create a copy of the ctgov identifier table (ad.temp_idents) - 
         // Working with this makes it much easier to develop and test routines 
         // to characterise the identifiers.
 
-        create_copy_of_identifiers(max_id, chunk_size, pool).await?;
+        create_copy_of_identifiers(max_id, chunk_size, ex).await?;
 
         // Using the temp_idents table, tidy up the identifier
         // text and remove duplicates and obvious non identifiers
         
-        split_doubled_values(pool).await?;
-        tidy_identifier_text(pool).await?;
-        remove_obvious_non_identifiers(pool).await?;   // Doing this first simplifies the step below
-        make_identifier_prefixes_more_consistent(pool).await?;
-        make_identifier_text_more_consistent(pool).await?;
-        remove_duplicate_identifiers(pool).await?;  // The data source contains about 11000 duplicated identifiers
-        remove_obvious_non_identifiers2(pool).await?; 
+        split_doubled_values(ex).await?;
+        tidy_identifier_text(ex).await?;
+        remove_obvious_non_identifiers(ex).await?;   // Doing this first simplifies the step below
+        make_identifier_prefixes_more_consistent(ex).await?;
+        make_identifier_text_more_consistent(ex).await?;
+        remove_duplicate_identifiers(ex).await?;  // The data source contains about 11000 duplicated identifiers
+        remove_obvious_non_identifiers2(ex).await?; 
 
-        code_very_short_unidentifiable_ids(pool).await?; 
-        transfer_coded_identifiers(pool).await?;
+        code_very_short_unidentifiable_ids(ex).await?; 
+        transfer_coded_identifiers(ex).await?;
 
         // identify NCI, NIH and other us agency identifiers
 
-        idents_us::find_us_nci_identifiers(pool).await?;
-        idents_us::find_us_cdc_identifiers(pool).await?;
-        idents_us::find_nih_grant_identifiers(pool).await?;
-        idents_us::find_fda_identifiers(pool).await?;
-        idents_us::find_other_us_grant_identifiers(pool).await?;
-        transfer_coded_identifiers(pool).await?;
-
-        idents_eu::find_ansm_identities(pool).await?;
-        idents_eu::find_eu_wide_identities(pool).await?;
-        idents_eu::find_dutch_identities(pool).await?;
-        idents_eu::find_german_identities(pool).await?;
-        idents_eu::find_isrctn_identities(pool).await?;
-        transfer_coded_identifiers(pool).await?;
-
-        idents_rg::find_japanese_registry_identities(pool).await?;
-        idents_rg::find_chinese_registry_identities(pool).await?;
-        idents_rg::find_other_asian_registry_identities(pool).await?;
-        idents_rg::find_middle_eastern_registry_identities(pool).await?;
-        idents_rg::find_latin_american_registry_identities(pool).await?;
-        idents_rg::find_other_registry_identities(pool).await?;
-        transfer_coded_identifiers(pool).await?;
-
-        idents_ca::find_swog_identities(pool).await?;
-        idents_ca::find_cog_identities(pool).await?;
-        idents_ca::find_can_and_tog_identities(pool).await?;
-        idents_ca::find_nat_identities(pool).await?;
-        idents_ca::find_boog_and_trog_identities(pool).await?;
-        idents_ca::find_gog_and_nrj_identities(pool).await?;
-        transfer_coded_identifiers(pool).await?;
-
-        idents_oth::find_zonmw_identities(pool).await?;
-        idents_oth::find_eortc_identities(pool).await?;
-        idents_oth::find_cruk_identities(pool).await?;
-        idents_oth::find_basel_ctu_identities(pool).await?;
-        idents_oth::find_swiss_basec_identities(pool).await?;
-        idents_oth::find_chinadrugtrials_nmpa_identities(pool).await?;
-        idents_oth::find_daides_identities(pool).await?;
-        idents_oth::find_taiwanese_identities(pool).await?;
-        transfer_coded_identifiers(pool).await?;
-
-        idents_co::find_eli_lilly_identities(pool).await?;
-        transfer_coded_identifiers(pool).await?;
+        idents_us::find_us_nci_identifiers(ex).await?;
+        idents_us::find_us_cdc_identifiers(ex).await?;
+        idents_us::find_nih_grant_identifiers(ex).await?;
+        idents_us::find_fda_identifiers(ex).await?;
+        idents_us::find_other_us_grant_identifiers(ex).await?;
+        transfer_coded_identifiers(ex).await?;
+
+        idents_eu::find_ansm_identities(ex).await?;
+        idents_eu::find_eu_wide_identities(ex).await?;
+        idents_eu::find_dutch_identities(ex).await?;
+        idents_eu::find_german_identities(ex).await?;
+        idents_eu::find_isrctn_identities(ex).await?;
+        transfer_coded_identifiers(ex).await?;
+
+        idents_rg::find_japanese_registry_identities(ex).await?;
+        idents_rg::find_chinese_registry_identities(ex).await?;
+        idents_rg::find_other_asian_registry_identities(ex).await?;
+        idents_rg::find_middle_eastern_registry_identities(ex).await?;
+        idents_rg::find_latin_american_registry_identities(ex).await?;
+        idents_rg::find_other_registry_identities(ex).await?;
+        transfer_coded_identifiers(ex).await?;
+
+        idents_ca::find_swog_identities(ex).await?;
+        idents_ca::find_cog_identities(ex).await?;
+        idents_ca::find_can_and_tog_identities(ex).await?;
+        idents_ca::find_nat_identities(ex).await?;
+        idents_ca::find_boog_and_trog_identities(ex).await?;
+        idents_ca::find_gog_and_nrj_identities(ex).await?;
+        transfer_coded_identifiers(ex).await?;
+
+        idents_oth::find_zonmw_identities(ex).await?;
+        idents_oth::find_eortc_identities(ex).await?;
+        idents_oth::find_cruk_identities(ex).await?;
+        idents_oth::find_basel_ctu_identities(ex).await?;
+        idents_oth::find_swiss_basec_identities(ex).await?;
+        idents_oth::find_chinadrugtrials_nmpa_identities(ex).await?;
+        idents_oth::find_daides_identities(ex).await?;
+        idents_oth::find_taiwanese_identities(ex).await?;
+        transfer_coded_identifiers(ex).await?;
+
+        idents_co::find_eli_lilly_identities(ex).await?;
+        transfer_coded_identifiers(ex).await?;
        
-        park_spare_idents_data(max_id, chunk_size, pool).await?;
+        if plan.saves("idents_base") {
+            save_checkpoint("idents_base", &base_tables, data_date, ex).await?;
+        }
     }
     else {
-        reuse_spare_idents_data(max_id, chunk_size, pool).await?;
+        restore_checkpoint("idents_base", &base_tables, ex).await?;
     }
   
-    idents_co::find_incyte_identities(pool).await?;
-    idents_co::find_novartis_identities(pool).await?;
-    idents_co::find_alcon_identities(pool).await?;
-    idents_co::find_pfizer_identities(pool).await?;
-    idents_co::find_gsk_identities(pool).await?;
-    idents_co::find_takeda_identities(pool).await?;
-    idents_co::find_roche_identities(pool).await?;
-    idents_co::find_az_identities(pool).await?;
-    idents_co::find_takeda_identities(pool).await?;
-    idents_co::find_jandj_identities(pool).await?;
-    idents_co::find_jannsen_identities(pool).await?;
-    idents_co::find_sanofi_identities(pool).await?;
-    idents_co::find_bms_identities(pool).await?;
-    idents_co::find_abbvie_identities(pool).await?;
-
-    transfer_coded_identifiers(pool).await?;
+    idents_co::find_incyte_identities(ex).await?;
+    idents_co::find_novartis_identities(ex).await?;
+    idents_co::find_alcon_identities(ex).await?;
+    idents_co::find_pfizer_identities(ex).await?;
+    idents_co::find_gsk_identities(ex).await?;
+    idents_co::find_takeda_identities(ex).await?;
+    idents_co::find_roche_identities(ex).await?;
+    idents_co::find_az_identities(ex).await?;
+    idents_co::find_takeda_identities(ex).await?;
+    idents_co::find_jandj_identities(ex).await?;
+    idents_co::find_jannsen_identities(ex).await?;
+    idents_co::find_sanofi_identities(ex).await?;
+    idents_co::find_bms_identities(ex).await?;
+    idents_co::find_abbvie_identities(ex).await?;
+
+    transfer_coded_identifiers(ex).await?;
 
     // Find non registry Ids, e.g. from funders, regulators, registries
 /*
-    find_eu_regulators_identities(pool).await?;
-    find_ethics_oversight_identities(pool).await?;
-    find_other_identities(pool).await?;
-    transfer_coded_identifiers(pool).await?;
+    find_eu_regulators_identities(ex).await?;
+    find_ethics_oversight_identities(ex).await?;
+    find_other_identities(ex).await?;
+    transfer_coded_identifiers(ex).await?;
  */
 
     let _sql = r#"drop table if exists ad.temp_idents;"#;
-    // execute_sql(sql, pool).await?;
+    // ex.execute(sql).await?;
 
-    vacuum_table("study_identifiers", pool).await?;
+    ex.vacuum_table("study_identifiers").await?;
 
     Ok(())
 
 }
 
 
-async fn park_spare_idents_data(max_id: u64, chunk_size: u64, pool: &Pool<Postgres>) -> Result<(), AppError> { 
- 
-    // copy ad.temp_idents to ad.spare_temp_idents
-
-    let sql = r#"SET client_min_messages TO WARNING; 
-    drop table if exists ad.spare_temp_idents;
-    create table ad.spare_temp_idents
-    (
-          id                     INT             NOT NULL GENERATED BY DEFAULT AS IDENTITY
-        , sd_sid                 VARCHAR         NOT NULL
-        , id_value               VARCHAR         NULL
-        , id_type_id             INT             NULL
-        , id_type                VARCHAR         NULL
-        , source_org_id          INT             NULL
-        , source_org             VARCHAR         NULL
-        , id_class               VARCHAR         NULL
-        , id_desc                VARCHAR         NULL
-        , id_link                VARCHAR         NULL
-    );
-    CREATE INDEX spare_temp_idents_id ON ad.spare_temp_idents(id);
-    CREATE INDEX spare_temp_idents_sid ON ad.spare_temp_idents(sd_sid);"#;
-    execute_sql(sql, pool).await?;
-
-    let sql = r#"insert into ad.spare_temp_idents (id, sd_sid, id_value, id_class, id_desc, id_link)
-    select id, sd_sid, id_value, id_class, id_desc, id_link
-    from ad.temp_idents c "#;
-    execute_temp_phased_transfer(sql, max_id, chunk_size, " where ", "temp_idents", pool).await?;
-
-    // copy ad.study_identiiers to ad.spare_study_identifiers
-
-    let sql = r#"SET client_min_messages TO WARNING; 
-        drop table if exists ad.spare_study_identifiers;
-        create table ad.spare_study_identifiers(
-          id                     INT             NOT NULL GENERATED BY DEFAULT AS IDENTITY
-        , sd_sid                 VARCHAR         NOT NULL
-        , id_value               VARCHAR         NULL
-        , id_type_id             INT             NULL
-        , id_type                VARCHAR         NULL
-        , source_org_id          INT             NULL
-        , source_org             VARCHAR         NULL
-        , source_ror_id          VARCHAR         NULL
-        , id_date                VARCHAR         NULL
-        , id_link                VARCHAR         NULL
-        , added_on               TIMESTAMPTZ     NOT NULL default now()
-        , coded_on               TIMESTAMPTZ     NULL                                     
-        );
-        CREATE INDEX spare_study_identifiers_sid ON ad.spare_study_identifiers(sd_sid);"#;
-        execute_sql(sql, pool).await?;
-
-    let sql = r#"insert into ad.spare_study_identifiers (id, sd_sid, id_value, 
-        id_type_id, id_type, source_org_id, source_org, source_ror_id, id_date, 
-        id_link, added_on, coded_on)
-        select id, sd_sid, id_value, id_type_id, id_type, source_org_id, source_org, 
-        source_ror_id, id_date, id_link, added_on, coded_on
-        from ad.study_identifiers c "#;
-    execute_temp_phased_transfer(sql, max_id, chunk_size, " where ", "study_identifiers", pool).await?;
-
-    let sql = r#"SET client_min_messages TO NOTICE;"#;
-    execute_sql(sql, pool).await?;
-
-    Ok(())
-
-}
-
-
-async fn reuse_spare_idents_data(max_id: u64, chunk_size: u64, pool: &Pool<Postgres>) -> Result<(), AppError> { 
-
-    // Load the data from ad.spare_temp_idents to ad._temp_idents
-
-    let sql = r#"SET client_min_messages TO WARNING; 
-	drop table if exists ad.temp_idents;
-	create table ad.temp_idents
-	(
-          id                     INT             NOT NULL GENERATED BY DEFAULT AS IDENTITY
-		, sd_sid                 VARCHAR         NOT NULL
-        , id_value               VARCHAR         NULL
-        , id_type_id             INT             NULL
-        , id_type                VARCHAR         NULL
-        , source_org_id          INT             NULL
-        , source_org             VARCHAR         NULL
-        , id_class               VARCHAR         NULL
-        , id_desc                VARCHAR         NULL
-        , id_link                VARCHAR         NULL
-	);
-    CREATE INDEX temp_idents_id ON ad.temp_idents(id);
-    CREATE INDEX temp_idents_sid ON ad.temp_idents(sd_sid);"#;
-   execute_sql(sql, pool).await?;
-
-    let sql = r#"insert into ad.temp_idents (id, sd_sid, id_value, id_class, id_desc, id_link)
-    select id, sd_sid, id_value, id_class, id_desc, id_link
-    from ad.spare_temp_idents c "#;
-    execute_temp_phased_transfer(sql, max_id, chunk_size, " where ", "temp_idents", pool).await?;
-
-    // load the data from ad.spare_study_identifiers to ad.study_identifiers
-
-    let sql = r#"SET client_min_messages TO WARNING; 
-        DROP TABLE IF EXISTS ad.study_identifiers;
-        CREATE TABLE ad.study_identifiers(
-          id                     INT             NOT NULL GENERATED BY DEFAULT AS IDENTITY
-        , sd_sid                 VARCHAR         NOT NULL
-        , id_value               VARCHAR         NULL
-        , id_type_id             INT             NULL
-        , id_type                VARCHAR         NULL
-        , source_org_id          INT             NULL
-        , source_org             VARCHAR         NULL
-        , source_ror_id          VARCHAR         NULL
-        , id_date                VARCHAR         NULL
-        , id_link                VARCHAR         NULL
-        , added_on               TIMESTAMPTZ     NOT NULL default now()
-        , coded_on               TIMESTAMPTZ     NULL                                     
-        );
-        CREATE INDEX study_identifiers_sid ON ad.study_identifiers(sd_sid);"#;
-
-       execute_sql(sql, pool).await?;
-        info!("study identifiers table (re)created");
-        
-    let sql = r#"insert into ad.study_identifiers (id, sd_sid, id_value, 
-        id_type_id, id_type, source_org_id, source_org, source_ror_id, id_date, 
-        id_link, added_on, coded_on)
-        select id, sd_sid, id_value, id_type_id, id_type, source_org_id, source_org, 
-        source_ror_id, id_date, id_link, added_on, coded_on
-        from ad.spare_study_identifiers c "#;
-    execute_temp_phased_transfer(sql, max_id, chunk_size, " where ", "study_identifiers", pool).await?;
-
-    Ok(())
-}
-
-
-async fn create_copy_of_identifiers(max_id: u64, chunk_size: u64, pool: &Pool<Postgres>) -> Result<(), AppError> { 
+async fn create_copy_of_identifiers(max_id: u64, chunk_size: u64, ex: &SqlExecutor) -> Result<(), AppError> { 
 
     let sql = r#"SET client_min_messages TO WARNING; 
 	drop table if exists ad.temp_idents;
@@ -373,29 +253,29 @@ async fn create_copy_of_identifiers(max_id: u64, chunk_size: u64, pool: &Pool<Po
 	);
     CREATE INDEX temp_idents_id ON ad.temp_idents(id);
     CREATE INDEX temp_idents_sid ON ad.temp_idents(sd_sid);"#;
-    execute_sql(sql, pool).await?;
+    ex.execute(sql).await?;
 
 	let sql = r#"insert into ad.temp_idents (id, sd_sid, id_value, id_class, id_desc, id_link)
 	select id, nct_id, id_value, id_type, id_type_description, id_link
 	from ctgov.id_information c where id_source <> 'nct_alias' "#;
-    execute_phased_transfer(sql, max_id, chunk_size, " and ", 
-        "ctgov identifier records", "ad.temp_idents", pool).await?;
+    ex.phased_transfer(sql, max_id, chunk_size, " and ", 
+        "ctgov identifier records", "ad.temp_idents").await?;
 
     let sql = r#"SET client_min_messages TO NOTICE;"#;
-    execute_sql(sql, pool).await?;
+    ex.execute(sql).await?;
     
     Ok(())
 }
 
 
-async fn split_doubled_values(pool: &Pool<Postgres>) -> Result<(), AppError> {  
+async fn split_doubled_values(ex: &SqlExecutor) -> Result<(), AppError> {  
 
     // There seem to be about 720 doubled identifier records, with each
     // pair normally split by a semi-colon. 
 
     // As an initial step, remove semi-colons from the start and end of identifiers.
 
-    remove_both_ldtr_char_from_ident(';', pool).await?;
+    remove_both_ldtr_char_from_ident(';', ex).await?;
 
     // A small group of identifiers including '(V/v)ersion' or 'v' followed by a number have a semi-colon 
     // before the date. In these case the semi-colon should just be removed.
@@ -406,7 +286,7 @@ async fn split_doubled_values(pool: &Pool<Postgres>) -> Result<(), AppError> {
         or id_value ~ 'v [0-9]' or id_value ~ '^v[0-9]'
         or id_value ~ 'v.[0-9]')
         and id_value ilike '%;%';"#;
-    execute_sql(sql, pool).await?;
+    ex.execute(sql).await?;
 
     // There is a large group of doubled identifiers that come from the 
     // Clinical Trials Unit in Basel. In each one id is an internal (Basel) CTU
@@ -419,7 +299,7 @@ async fn split_doubled_values(pool: &Pool<Postgres>) -> Result<(), AppError> {
         from ad.temp_idents
         where id_value ~ '; ?[a-z]{2}(1|2)[0-9]{1}[A-Za-z1-7]+$'
         and id_value ~ '[0-9]{4}-[0-9]{5}';"#;
-    execute_sql(sql, pool).await?;
+    ex.execute(sql).await?;
 
     let sql = r#"insert into ad.temp_idents
         (id, sd_sid, id_value, id_class, id_desc)
@@ -427,12 +307,12 @@ async fn split_doubled_values(pool: &Pool<Postgres>) -> Result<(), AppError> {
         from ad.temp_idents
         where id_value ~ '; ?[a-z]{2}(1|2)[0-9]{1}[A-Za-z1-7]+$'
         and id_value ~ '[0-9]{4}-[0-9]{5}';"#;
-    execute_sql(sql, pool).await?;
+    ex.execute(sql).await?;
 
     let sql = r#"delete from ad.temp_idents
         where id_value ~ '; ?[a-z]{2}(1|2)[0-9]{1}[A-Za-z1-7]+$'
         and id_value ~ '[0-9]{4}-[0-9]{5}';"#;
-    let res1 = execute_sql(sql, pool).await?;
+    let res1 = ex.execute(sql).await?;
 
     // There is a much smaller but similar group of paired records linked by a comma
 
@@ -442,7 +322,7 @@ async fn split_doubled_values(pool: &Pool<Postgres>) -> Result<(), AppError> {
         from ad.temp_idents
         where id_value ~ ', ?[a-z]{2}(1|2)[0-9]{1}[A-Za-z1-7]+$'
         and id_value ~ '[0-9]{4}-[0-9]{5}';"#;
-    execute_sql(sql, pool).await?;
+    ex.execute(sql).await?;
 
     let sql = r#"insert into ad.temp_idents
         (id, sd_sid, id_value, id_class, id_desc)
@@ -450,12 +330,12 @@ async fn split_doubled_values(pool: &Pool<Postgres>) -> Result<(), AppError> {
         from ad.temp_idents
         where id_value ~ ', ?[a-z]{2}(1|2)[0-9]{1}[A-Za-z1-7]+$'
         and id_value ~ '[0-9]{4}-[0-9]{5}';"#;
-    execute_sql(sql, pool).await?;
+    ex.execute(sql).await?;
 
     let sql = r#"delete from ad.temp_idents
         where id_value ~ ', ?[a-z]{2}(1|2)[0-9]{1}[A-Za-z1-7]+$'
         and id_value ~ '[0-9]{4}-[0-9]{5}';"#;
-    let res2 = execute_sql(sql, pool).await?;
+    let res2 = ex.execute(sql).await?;
 
     info!("{} Swiss double identifier records split", res1.rows_affected() + res2.rows_affected());
 
@@ -469,11 +349,11 @@ async fn split_doubled_values(pool: &Pool<Postgres>) -> Result<(), AppError> {
         id_class, id_desc
         from ad.temp_idents
         where id_value ilike '%;%';"#;
-    execute_sql(sql, pool).await?;
+    ex.execute(sql).await?;
 
     let sql = r#"delete from ad.temp_idents 
         where id_value ilike '%;%'"#;
-    let res = execute_sql(sql, pool).await?;
+    let res = ex.execute(sql).await?;
     info!("{} remaining records with semi-colons split", res.rows_affected());
 
     info!("");
@@ -481,68 +361,68 @@ async fn split_doubled_values(pool: &Pool<Postgres>) -> Result<(), AppError> {
 }
 
 
-async fn tidy_identifier_text(pool: &Pool<Postgres>) -> Result<(), AppError> {  
+async fn tidy_identifier_text(ex: &SqlExecutor) -> Result<(), AppError> {  
         
     // Remove single quotes from the beginning and end of identifiers.
 
     let sql = r#"update ad.temp_idents
         set id_value = trim(BOTH '''' from id_value)
         where id_value like '%''' or id_value like '''%'"#;
-    let res = execute_sql(sql, pool).await?;
+    let res = ex.execute(sql).await?;
     info!("{} single quote characters removed from start or end of identifiers", res.rows_affected());
 
     // Tidy up the spurious characters to be found in the identifiers.
 
-    remove_both_ldtr_char_from_ident('"', pool).await?;
-    remove_both_ldtr_char_from_ident('-', pool).await?;
-    remove_both_ldtr_char_from_ident(',', pool).await?;
-    remove_both_ldtr_char_from_ident('.', pool).await?;
-    remove_both_ldtr_char_from_ident('#', pool).await?;
-
-    remove_leading_char_from_ident('!', pool).await?;
-    remove_leading_char_from_ident('+', pool).await?;
-    remove_leading_char_from_ident('&', pool).await?;
-    remove_leading_char_from_ident('*', pool).await?;
-    remove_leading_char_from_ident(':', pool).await?;
-    remove_leading_char_from_ident('/', pool).await?;
-    remove_leading_char_from_ident('|', pool).await?;
-    remove_leading_char_from_ident('´', pool).await?;
-
-    replace_string_in_ident(")(", "", pool).await?;
-    replace_string_in_ident(" (", " ", pool).await?;
-    replace_string_in_ident(") ", " ", pool).await?;
-    replace_string_in_ident("(", " ", pool).await?;
-    replace_string_in_ident(")", " ", pool).await?;
-    replace_string_in_ident("【", " ", pool).await?;
-    replace_string_in_ident("】", " ", pool).await?;
-    replace_string_in_ident("[", " ", pool).await?;
-    replace_string_in_ident("]", " ", pool).await?;
-    replace_string_in_ident("--", "-", pool).await?;
+    remove_both_ldtr_char_from_ident('"', ex).await?;
+    remove_both_ldtr_char_from_ident('-', ex).await?;
+    remove_both_ldtr_char_from_ident(',', ex).await?;
+    remove_both_ldtr_char_from_ident('.', ex).await?;
+    remove_both_ldtr_char_from_ident('#', ex).await?;
+
+    remove_leading_char_from_ident('!', ex).await?;
+    remove_leading_char_from_ident('+', ex).await?;
+    remove_leading_char_from_ident('&', ex).await?;
+    remove_leading_char_from_ident('*', ex).await?;
+    remove_leading_char_from_ident(':', ex).await?;
+    remove_leading_char_from_ident('/', ex).await?;
+    remove_leading_char_from_ident('|', ex).await?;
+    remove_leading_char_from_ident('´', ex).await?;
+
+    replace_string_in_ident(")(", "", ex).await?;
+    replace_string_in_ident(" (", " ", ex).await?;
+    replace_string_in_ident(") ", " ", ex).await?;
+    replace_string_in_ident("(", " ", ex).await?;
+    replace_string_in_ident(")", " ", ex).await?;
+    replace_string_in_ident("【", " ", ex).await?;
+    replace_string_in_ident("】", " ", ex).await?;
+    replace_string_in_ident("[", " ", ex).await?;
+    replace_string_in_ident("]", " ", ex).await?;
+    replace_string_in_ident("--", "-", ex).await?;
 
     let sql = r#"update ad.temp_idents
         set id_value = substring(id_value, 2)
         where id_value ~ '^_';"#;
-    execute_sql(sql, pool).await?;
+    ex.execute(sql).await?;
     info!("{} underscore characters removed from start of identifiers", res.rows_affected());
     info!("");
 
     // Seems to need to be done twice, albeit for a very small number of records.
 
-    replace_string_in_ident("  ", " ", pool).await?;
-    replace_string_in_ident("  ", " ", pool).await?;  
+    replace_string_in_ident("  ", " ", ex).await?;
+    replace_string_in_ident("  ", " ", ex).await?;  
 
     // Final trim.
 
     let sql = r#"update ad.temp_idents
         set id_value = trim(id_value);"#;
-    execute_sql(sql, pool).await?;
+    ex.execute(sql).await?;
     info!("");
 
     Ok(())
 }
 
 
-async fn remove_obvious_non_identifiers(pool: &Pool<Postgres>) -> Result<(), AppError> {  
+async fn remove_obvious_non_identifiers(ex: &SqlExecutor) -> Result<(), AppError> {  
 
     // Remove those that are almost certainly dummies.
 
@@ -552,20 +432,20 @@ async fn remove_obvious_non_identifiers(pool: &Pool<Postgres>) -> Result<(), App
         '00000000', '000000000', '0000000000', '000000000000', '0000-00000')
         or id_value ilike '%12345678%' 
         or id_value ilike '%87654321%';"#;
-    execute_sql_fb(sql, pool, "dummy", "(e.g. all 0s, or obvious sequences) removed").await?;
+    execute_sql_fb(sql, ex, "dummy", "(e.g. all 0s, or obvious sequences) removed").await?;
    
     // Remove those that are too small to be useful (unless type is given).
 
     let sql = r#"delete from ad.temp_idents 
         where length(id_value) < 3
         and id_desc is not null"#;
-    execute_sql_fb(sql, pool, "very short", "(1 or 2 characters) removed").await?;
+    execute_sql_fb(sql, ex, "very short", "(1 or 2 characters) removed").await?;
    
     // Remove those including '@' - e.g. mostly email addresses, plus some odd formulations
 
     let sql = r#"delete from ad.temp_idents 
         where id_value like '%@%';"#;
-    let res = execute_sql(&sql, pool).await?;
+    let res = ex.execute(sql).await?;
     info!("{} email addresses and other odd identifier values with '@' removed", res.rows_affected());
 
     // Remove identifiers that are study acronyms.
@@ -575,7 +455,7 @@ async fn remove_obvious_non_identifiers(pool: &Pool<Postgres>) -> Result<(), App
         where i.sd_sid = t.sd_sid
         and t.title_type_id = 14
         and upper(i.id_value) = upper(t.title_text);"#;
-    execute_sql_sfb(sql, pool, "removed as having the same value as the study acronym").await?;
+    execute_sql_sfb(sql, ex, "removed as having the same value as the study acronym").await?;
    
     // Remove identifiers that are study brief titles.
 
@@ -584,7 +464,7 @@ async fn remove_obvious_non_identifiers(pool: &Pool<Postgres>) -> Result<(), App
         where i.sd_sid = t.sd_sid
         and t.title_type_id in (15, 16)
         and upper(i.id_value) = upper(t.title_text);"#;
-    execute_sql_sfb(sql, pool, "removed as having the same value as a study title").await?;
+    execute_sql_sfb(sql, ex, "removed as having the same value as a study title").await?;
 
     // Remove identifiers that refer (mostly) to a person.
 
@@ -595,7 +475,7 @@ async fn remove_obvious_non_identifiers(pool: &Pool<Postgres>) -> Result<(), App
             or id_value like '%prof %'
             or id_value like '%Prof %'
             or id_value ilike '%professor%'"#;
-    execute_sql_sfb(sql, pool, "removed as referring to a person").await?;
+    execute_sql_sfb(sql, ex, "removed as referring to a person").await?;
 
     // Some records have a digit in the id_desc field and only text (inc. spaces and punctuation)
     // in the id_value field. These are generally where the data in the two fields have been inverted
@@ -606,7 +486,7 @@ async fn remove_obvious_non_identifiers(pool: &Pool<Postgres>) -> Result<(), App
         id_desc = id_value
         where id_value ~ '^[A-Za-z\s\.&,_/#-]+$'
         and id_desc ~ '[0-9]'"#;
-    let res = execute_sql(&sql, pool).await?;
+    let res = ex.execute(sql).await?;
     info!("{} identifiers and identifier descriptions reversed", res.rows_affected());
 
     // Some of the swapped group have a date in the id_value field and the type 'No NIH funding'.
@@ -614,7 +494,7 @@ async fn remove_obvious_non_identifiers(pool: &Pool<Postgres>) -> Result<(), App
 
     let sql = r#"delete from ad.temp_idents
         where id_desc = 'No NIH funding'"#;
-    let res = execute_sql(&sql, pool).await?;
+    let res = ex.execute(sql).await?;
     info!("{}'No NIH funding' records deleted", res.rows_affected());
 
     // Get rid of all remaining identifiers that include only letters, spaces, and punctuation other than hyphens.
@@ -626,14 +506,14 @@ async fn remove_obvious_non_identifiers(pool: &Pool<Postgres>) -> Result<(), App
     
     let sql = r#"delete from ad.temp_idents
         where id_value ~ '^[A-Za-z\s\.&,_/]+$'"#;
-    execute_sql_sfb(sql, pool, "consisting only of letters, spaces, and certain punctuation removed").await?;
+    execute_sql_sfb(sql, ex, "consisting only of letters, spaces, and certain punctuation removed").await?;
     
     // For future clarity remove id_desc where it is simply the same as the id_value
 
     let sql = r#"update ad.temp_idents
         set id_desc = null
         where id_desc = id_value"#;
-    let res = execute_sql(sql, pool).await?;
+    let res = ex.execute(sql).await?;
     info!("{}' descriptions removed when they are identical to the id value", res.rows_affected());
      
     // Some id_values are simply the trial acronym followed by ' trial', or ' Trial',
@@ -646,7 +526,7 @@ async fn remove_obvious_non_identifiers(pool: &Pool<Postgres>) -> Result<(), App
             and id_value !~ '^U1111'
             and id_value !~ '^AGO'
             and id_value !~ '^WUCC'"#;
-    execute_sql_sfb(sql, pool, "removed when they are simply the trial name followed by ' trial'").await?;
+    execute_sql_sfb(sql, ex, "removed when they are simply the trial name followed by ' trial'").await?;
     
     info!("");
     Ok(())
@@ -654,7 +534,7 @@ async fn remove_obvious_non_identifiers(pool: &Pool<Postgres>) -> Result<(), App
 }
 
 
-async fn make_identifier_prefixes_more_consistent(pool: &Pool<Postgres>) -> Result<(), AppError> {  
+async fn make_identifier_prefixes_more_consistent(ex: &SqlExecutor) -> Result<(), AppError> {  
     
     // Tries to make the use of the terms like 'number' consistent,
     // Replaces ID, no:, grant no, ref., etc. with the single term 'number'.
@@ -668,94 +548,94 @@ async fn make_identifier_prefixes_more_consistent(pool: &Pool<Postgres>) -> Resu
         set id_class = 'temp',
         id_link = id_value
         where id_value in ('No Grant-2', 'no Grant 4', 'No Grant 5', 'no Grant 6')"#;
-    execute_sql(sql, pool).await?;
+    ex.execute(sql).await?;
    
-    replace_string_in_ident("2024GRANT089", "2024 GRANT 089", pool).await?;
-    replace_string_in_ident("BMAChartiableGrant2016", "BMA Charitable Grant 2016", pool).await?;
+    replace_string_in_ident("2024GRANT089", "2024 GRANT 089", ex).await?;
+    replace_string_in_ident("BMAChartiableGrant2016", "BMA Charitable Grant 2016", ex).await?;
     info!("");
 
-    replace_string_in_ident("Grant Fund", "grant ", pool).await?;
-    replace_string_in_ident("Grant Agreement n.", "grant number ", pool).await?;
-    replace_string_in_ident("Grant Agreement Nº:", "grant number ", pool).await?;
-    replace_string_in_ident("Grant Agreement", "grant number ", pool).await?;
-    replace_string_in_ident("Grant N.", "grant number ", pool).await?;
-    replace_string_in_ident("Grant:", "grant ", pool).await?;
-    replace_string_in_ident("Grant ID", "grant ", pool).await?;
-    replace_string_in_ident("Grant-", "grant ", pool).await?;
-    replace_string_in_ident("Grant ", "grant ", pool).await?;
-    replace_string_in_ident("GRANT", "grant ", pool).await?;
-    replace_string_in_ident("grant:", "grant number ", pool).await?;
-    replace_string_in_ident("grant no", "grant number ", pool).await?;
-    replace_string_in_ident("grant ", "grant number ", pool).await?;
-    replace_string_in_ident("grant,", "grant number ", pool).await?;
-    replace_string_in_ident("grant/", "grant number ", pool).await?;
+    replace_string_in_ident("Grant Fund", "grant ", ex).await?;
+    replace_string_in_ident("Grant Agreement n.", "grant number ", ex).await?;
+    replace_string_in_ident("Grant Agreement Nº:", "grant number ", ex).await?;
+    replace_string_in_ident("Grant Agreement", "grant number ", ex).await?;
+    replace_string_in_ident("Grant N.", "grant number ", ex).await?;
+    replace_string_in_ident("Grant:", "grant ", ex).await?;
+    replace_string_in_ident("Grant ID", "grant ", ex).await?;
+    replace_string_in_ident("Grant-", "grant ", ex).await?;
+    replace_string_in_ident("Grant ", "grant ", ex).await?;
+    replace_string_in_ident("GRANT", "grant ", ex).await?;
+    replace_string_in_ident("grant:", "grant number ", ex).await?;
+    replace_string_in_ident("grant no", "grant number ", ex).await?;
+    replace_string_in_ident("grant ", "grant number ", ex).await?;
+    replace_string_in_ident("grant,", "grant number ", ex).await?;
+    replace_string_in_ident("grant/", "grant number ", ex).await?;
     info!("");
 
-    replace_string_in_ident("PROJECT NUMBER", "project number ", pool).await?;
-    replace_string_in_ident("Project Number:", "project number ", pool).await?;
-    replace_string_in_ident("Project Number", "project number ", pool).await?;
-    replace_string_in_ident("PROJECT NO. ", "project number ", pool).await?;
-    replace_string_in_ident("Project n.", "project number ", pool).await?;
-    replace_string_in_ident("Project Nr.", "project number ", pool).await?;
-    replace_string_in_ident("Project No-", "project number ", pool).await?;
-    replace_string_in_ident("Project nº", "project number ", pool).await?;
-    replace_string_in_ident("PROJECT NO ", "project number ", pool).await?;
-    replace_string_in_ident("Project nr.", "project number ", pool).await?;
-    replace_string_in_ident("Project nr", "project number ", pool).await?;
-    replace_string_in_ident("project no", "project number ", pool).await?;
-    replace_string_in_ident("projectnummer", "project number ", pool).await?;
-    replace_string_in_ident("projectnr.", "project number ", pool).await?;
-    replace_string_in_ident("projectnr", "project number ", pool).await?;
-    replace_string_in_ident("project nr", "project number ", pool).await?;
-
-    replace_string_in_ident("PROJECT00", "project number 00", pool).await?;
-    replace_string_in_ident("Project Code:", "project number ", pool).await?;
-    replace_string_in_ident("Project ID162820", "project number 162820", pool).await?;
-    replace_string_in_ident("ProjectID ", "project number ", pool).await?;
-    replace_string_in_ident("Project-ID ", "project number ", pool).await?;
-    replace_string_in_ident("Project ID-", "project number ", pool).await?;
-    replace_string_in_ident("PROJECT ID:", "project number ", pool).await?;
-    replace_string_in_ident("Project id:", "project number ", pool).await?;
-    replace_string_in_ident("Project ID:", "project number ", pool).await?;
-    replace_string_in_ident("project ID:", "project number ", pool).await?;
-    replace_string_in_ident("project-ID", "project number ", pool).await?;
-
-    replace_string_in_ident("Study No", "study number", pool).await?;
+    replace_string_in_ident("PROJECT NUMBER", "project number ", ex).await?;
+    replace_string_in_ident("Project Number:", "project number ", ex).await?;
+    replace_string_in_ident("Project Number", "project number ", ex).await?;
+    replace_string_in_ident("PROJECT NO. ", "project number ", ex).await?;
+    replace_string_in_ident("Project n.", "project number ", ex).await?;
+    replace_string_in_ident("Project Nr.", "project number ", ex).await?;
+    replace_string_in_ident("Project No-", "project number ", ex).await?;
+    replace_string_in_ident("Project nº", "project number ", ex).await?;
+    replace_string_in_ident("PROJECT NO ", "project number ", ex).await?;
+    replace_string_in_ident("Project nr.", "project number ", ex).await?;
+    replace_string_in_ident("Project nr", "project number ", ex).await?;
+    replace_string_in_ident("project no", "project number ", ex).await?;
+    replace_string_in_ident("projectnummer", "project number ", ex).await?;
+    replace_string_in_ident("projectnr.", "project number ", ex).await?;
+    replace_string_in_ident("projectnr", "project number ", ex).await?;
+    replace_string_in_ident("project nr", "project number ", ex).await?;
+
+    replace_string_in_ident("PROJECT00", "project number 00", ex).await?;
+    replace_string_in_ident("Project Code:", "project number ", ex).await?;
+    replace_string_in_ident("Project ID162820", "project number 162820", ex).await?;
+    replace_string_in_ident("ProjectID ", "project number ", ex).await?;
+    replace_string_in_ident("Project-ID ", "project number ", ex).await?;
+    replace_string_in_ident("Project ID-", "project number ", ex).await?;
+    replace_string_in_ident("PROJECT ID:", "project number ", ex).await?;
+    replace_string_in_ident("Project id:", "project number ", ex).await?;
+    replace_string_in_ident("Project ID:", "project number ", ex).await?;
+    replace_string_in_ident("project ID:", "project number ", ex).await?;
+    replace_string_in_ident("project-ID", "project number ", ex).await?;
+
+    replace_string_in_ident("Study No", "study number", ex).await?;
     info!("");
-    replace_string_in_ident("N°", "number ", pool).await?;
-    replace_string_in_ident("n°", "number ", pool).await?;
-    replace_string_in_ident(" id ", " number ", pool).await?;
-    replace_string_in_ident(" ID ", " number ", pool).await?;
-
-    replace_string_in_ident("Ref. No.", "number ", pool).await?;
-    replace_string_in_ident("Ref. No ", "number ", pool).await?;
-
-    replace_string_in_ident("No.", " number ", pool).await?;
-    replace_string_in_ident("No:", " number ", pool).await?;
-    replace_string_in_ident("No :", " number ", pool).await?;
-    replace_string_in_ident("no.", " number ", pool).await?;
-    replace_string_in_ident("no:", " number ", pool).await?;
-    replace_string_in_ident("no :", " number ", pool).await?;
-    replace_string_in_ident("NO :", " number ", pool).await?;
-    replace_string_in_ident("NO:", " number ", pool).await?;
+    replace_string_in_ident("N°", "number ", ex).await?;
+    replace_string_in_ident("n°", "number ", ex).await?;
+    replace_string_in_ident(" id ", " number ", ex).await?;
+    replace_string_in_ident(" ID ", " number ", ex).await?;
+
+    replace_string_in_ident("Ref. No.", "number ", ex).await?;
+    replace_string_in_ident("Ref. No ", "number ", ex).await?;
+
+    replace_string_in_ident("No.", " number ", ex).await?;
+    replace_string_in_ident("No:", " number ", ex).await?;
+    replace_string_in_ident("No :", " number ", ex).await?;
+    replace_string_in_ident("no.", " number ", ex).await?;
+    replace_string_in_ident("no:", " number ", ex).await?;
+    replace_string_in_ident("no :", " number ", ex).await?;
+    replace_string_in_ident("NO :", " number ", ex).await?;
+    replace_string_in_ident("NO:", " number ", ex).await?;
 
     let sql = r#"update ad.temp_idents
         set id_value = replace(id_value, 'no', ' number ')
         where id_value ~ ' no [0-9]'
         or id_value ~ '^no [0-9]';"#;
-    let res = execute_sql(sql, pool).await?;
+    let res = ex.execute(sql).await?;
     info!("{} 'no 's folowed by a number replaced with 'number'", res.rows_affected());
 
     let sql = r#"update ad.temp_idents
         set id_value = replace(id_value, 'No', ' number ')
         where id_value ~ ' No [0-9]'
         or id_value ~ '^No [0-9]';"#;
-    let res = execute_sql(sql, pool).await?;
+    let res = ex.execute(sql).await?;
     info!("{} 'No 's folowed by a number replaced with 'number'", res.rows_affected());
     info!("");
 
-    replace_string_in_ident("#:", " number ", pool).await?;
-    replace_string_in_ident("# :", " number ", pool).await?;
+    replace_string_in_ident("#:", " number ", ex).await?;
+    replace_string_in_ident("# :", " number ", ex).await?;
    
     // Almost all #s can be replaced by a 'number' unless that generates 
     // a split in a number, hi.e. hashes immediately preceded and followed by [0-9] 
@@ -766,60 +646,60 @@ async fn make_identifier_prefixes_more_consistent(pool: &Pool<Postgres>) -> Resu
         set id_value = replace(id_value, '#', ' number ')
         where id_value like '%#%'
         and id_value !~ '[0-9]#[0-9]';"#;
-    let res = execute_sql(sql, pool).await?;
+    let res = ex.execute(sql).await?;
     info!("{} '#'s not embedded in digits replaced with 'number'", res.rows_affected());
     info!("");
      
-    replace_string_in_ident("Reference Number", "number ", pool).await?;
-    replace_string_in_ident("Reference number", "number ", pool).await?;
-    replace_string_in_ident("reference number", "number ", pool).await?;
+    replace_string_in_ident("Reference Number", "number ", ex).await?;
+    replace_string_in_ident("Reference number", "number ", ex).await?;
+    replace_string_in_ident("reference number", "number ", ex).await?;
 
-    replace_string_in_ident("Reference", "number ", pool).await?;
-    replace_string_in_ident("REFERENCE", "number ", pool).await?;
-    replace_string_in_ident("reference", "number ", pool).await?;
+    replace_string_in_ident("Reference", "number ", ex).await?;
+    replace_string_in_ident("REFERENCE", "number ", ex).await?;
+    replace_string_in_ident("reference", "number ", ex).await?;
     
-    replace_string_in_ident("Ref Nº:", "number ", pool).await?;
-    replace_string_in_ident("Ref nº", "number ", pool).await?;
-    replace_string_in_ident("Ref No ", "number ", pool).await?;
-    replace_string_in_ident("Ref.Nr.", "number ", pool).await?;
-    replace_string_in_ident("Ref,", "number ", pool).await?;
-    replace_string_in_ident("Ref-Nr:", "number ", pool).await?;
-    replace_string_in_ident("Ref Code:", "number ", pool).await?;
-    replace_string_in_ident("ref. number ", "number ", pool).await?;
-    replace_string_in_ident("Ref. number ", "number ", pool).await?;
-    replace_string_in_ident("Ref_ No_ ", "number ", pool).await?;
-
-    replace_string_in_ident("Ref:", "number ", pool).await?;
-    replace_string_in_ident("ref:", "number ", pool).await?;
-    replace_string_in_ident("Ref.", "number ", pool).await?;
-    replace_string_in_ident("ref.", "number ", pool).await?;
-    replace_string_in_ident("REF:", "number ", pool).await?;
-    replace_string_in_ident(" REF ", "number ", pool).await?;
-    replace_string_in_ident(" ref ", "number ", pool).await?;
-    replace_string_in_ident(" Ref ", "number ", pool).await?;
+    replace_string_in_ident("Ref Nº:", "number ", ex).await?;
+    replace_string_in_ident("Ref nº", "number ", ex).await?;
+    replace_string_in_ident("Ref No ", "number ", ex).await?;
+    replace_string_in_ident("Ref.Nr.", "number ", ex).await?;
+    replace_string_in_ident("Ref,", "number ", ex).await?;
+    replace_string_in_ident("Ref-Nr:", "number ", ex).await?;
+    replace_string_in_ident("Ref Code:", "number ", ex).await?;
+    replace_string_in_ident("ref. number ", "number ", ex).await?;
+    replace_string_in_ident("Ref. number ", "number ", ex).await?;
+    replace_string_in_ident("Ref_ No_ ", "number ", ex).await?;
+
+    replace_string_in_ident("Ref:", "number ", ex).await?;
+    replace_string_in_ident("ref:", "number ", ex).await?;
+    replace_string_in_ident("Ref.", "number ", ex).await?;
+    replace_string_in_ident("ref.", "number ", ex).await?;
+    replace_string_in_ident("REF:", "number ", ex).await?;
+    replace_string_in_ident(" REF ", "number ", ex).await?;
+    replace_string_in_ident(" ref ", "number ", ex).await?;
+    replace_string_in_ident(" Ref ", "number ", ex).await?;
 
     let sql = r#"update ad.temp_idents
         set id_value = replace(id_value, 'Ref ', '')
         where id_value ~ '^Ref ';"#;
-    let res = execute_sql(sql, pool).await?;
+    let res = ex.execute(sql).await?;
     info!("{} 'Initial 'Ref's removed", res.rows_affected());
     info!("");
 
-    replace_string_in_ident("agreement", "", pool).await?;
-    replace_string_in_ident("  ", " ", pool).await?;
+    replace_string_in_ident("agreement", "", ex).await?;
+    replace_string_in_ident("  ", " ", ex).await?;
 
-    replace_string_in_ident("number -", " number ", pool).await?;
-    replace_string_in_ident("number :", "number ", pool).await?;
-    replace_string_in_ident("number:", "number ", pool).await?;
-    replace_string_in_ident("number  number", " number ", pool).await?;
-    replace_string_in_ident("number number", " number ", pool).await?;
-    replace_string_in_ident("number Number", " number ", pool).await?;
-    replace_string_in_ident("number NUMBER", " number ", pool).await?;
+    replace_string_in_ident("number -", " number ", ex).await?;
+    replace_string_in_ident("number :", "number ", ex).await?;
+    replace_string_in_ident("number:", "number ", ex).await?;
+    replace_string_in_ident("number  number", " number ", ex).await?;
+    replace_string_in_ident("number number", " number ", ex).await?;
+    replace_string_in_ident("number Number", " number ", ex).await?;
+    replace_string_in_ident("number NUMBER", " number ", ex).await?;
 
     // Need to repeat this...
 
-    replace_string_in_ident("  ", " ", pool).await?;
-    replace_string_in_ident("  ", " ", pool).await?;
+    replace_string_in_ident("  ", " ", ex).await?;
+    replace_string_in_ident("  ", " ", ex).await?;
 
     // repair from the top of the routine
     let sql = r#"update ad.temp_idents
@@ -827,27 +707,27 @@ async fn make_identifier_prefixes_more_consistent(pool: &Pool<Postgres>) -> Resu
         id_value = id_link,
         id_link = null
         where id_class = 'temp'"#;
-    execute_sql(sql, pool).await?;
+    ex.execute(sql).await?;
 
     // Final trim.
 
     let sql = r#"update ad.temp_idents
         set id_value = trim(id_value);"#;
-    execute_sql(sql, pool).await?;
+    ex.execute(sql).await?;
     info!("");
 
     Ok(())
 }
 
 
-async fn make_identifier_text_more_consistent(pool: &Pool<Postgres>) -> Result<(), AppError> {  
+async fn make_identifier_text_more_consistent(ex: &SqlExecutor) -> Result<(), AppError> {  
 
     // Remove spurious 'number 's that simply prefix an identifier
 
     let sql = r#"update ad.temp_idents
         set id_value = replace(id_value, 'number ', '')
         where id_value ~ '^number ';"#;
-    let res = execute_sql(sql, pool).await?;
+    let res = ex.execute(sql).await?;
     info!("{} Initial 'number's removed", res.rows_affected());
     
     // Create a table with the 'XXX type number' description, and the identifiers themselves, 
@@ -866,7 +746,7 @@ async fn make_identifier_text_more_consistent(pool: &Pool<Postgres>) -> Result<(
             and id_value !~ '^[0-9]'
             and id_value !~ '^A[0-9]'
             order by id_value;"#;
-    execute_sql(sql, pool).await?;
+    ex.execute(sql).await?;
 
     // Use that split_numbers table to update the temp_idents table.
 
@@ -883,7 +763,7 @@ async fn make_identifier_text_more_consistent(pool: &Pool<Postgres>) -> Result<(
             from ad.temp_split_numbers s
             where a.id = s.id;
             drop table if exists ad.temp_split_numbers;"#;
-    let res = execute_sql(sql, pool).await?;
+    let res = ex.execute(sql).await?;
     info!("{} Identifier types transferred to id description from value", res.rows_affected());
 
     // Need to deal with those id values that end with 'number' - no easy way
@@ -892,54 +772,54 @@ async fn make_identifier_text_more_consistent(pool: &Pool<Postgres>) -> Result<(
     let sql: &'static str = r#"update ad.temp_idents
         set id_value = replace(id_value, 'Number', 'number')
         where id_value ~ 'Number$'"#;
-    let res = execute_sql(sql, pool).await?;
+    let res = ex.execute(sql).await?;
     info!("{} Final 'Number's changed to lower case", res.rows_affected());
 
-    switch_number_suffix_to_desc("UPenn IRB Protocol number", pool).await?;
-    switch_number_suffix_to_desc("UPittsburgh IRB number", pool).await?;
-    switch_number_suffix_to_desc("NIH grant number", pool).await?;
-    switch_number_suffix_to_desc("Contract number", pool).await?;
-    switch_number_suffix_to_desc("IDRCB number", pool).await?;
-    switch_number_suffix_to_desc("UCSD number", pool).await?;
-    switch_number_suffix_to_desc("REC number", pool).await?;
-    switch_number_suffix_to_desc("REK number", pool).await?;
-    switch_number_suffix_to_desc("Station number", pool).await?;
-    switch_number_suffix_to_desc("EUDRACT number", pool).await?;
-    switch_number_suffix_to_desc("EU CT number", pool).await?;
-    switch_number_suffix_to_desc("EudraCT-number", pool).await?;
-    switch_number_suffix_to_desc("EudraCT number", pool).await?;
-    switch_number_suffix_to_desc("Eudra CT number", pool).await?;
-    switch_number_suffix_to_desc("Scripps SOPRS number", pool).await?;
-    switch_number_suffix_to_desc("Study number", pool).await?;
-    switch_number_suffix_to_desc("Logan IRB number", pool).await?;
-    switch_number_suffix_to_desc("Award number", pool).await?;
-    switch_number_suffix_to_desc("Sponsor number", pool).await?;
-    switch_number_suffix_to_desc("Institution number", pool).await?;
-    switch_number_suffix_to_desc("VA IRB number", pool).await?;
-    switch_number_suffix_to_desc("Chilean number", pool).await?;
-    switch_number_suffix_to_desc("Clinical Trial number", pool).await?;
-    switch_number_suffix_to_desc("IMI WP8A number", pool).await?;
-    switch_number_suffix_to_desc("UCSF RAS number", pool).await?;
-    switch_number_suffix_to_desc("no UCI HS number", pool).await?;
-    switch_number_suffix_to_desc("UHN REB number", pool).await?;
-    switch_number_suffix_to_desc("CR number", pool).await?;
-    switch_number_suffix_to_desc("IRB number", pool).await?;
-    switch_number_suffix_to_desc("Hospice number", pool).await?;
-    switch_number_suffix_to_desc("Protocol number", pool).await?;
-    switch_number_suffix_to_desc("protocol number", pool).await?;
-    switch_number_suffix_to_desc("grant number", pool).await?;
-
-    switch_number_suffix_to_desc("MBW-number", pool).await?;
-    switch_number_suffix_to_desc("NIAID CRMS ID number", pool).await?;
-    switch_number_suffix_to_desc("DDX exemption from MRHA number", pool).await?;
-    switch_number_suffix_to_desc("number", pool).await?;
+    switch_number_suffix_to_desc("UPenn IRB Protocol number", ex).await?;
+    switch_number_suffix_to_desc("UPittsburgh IRB number", ex).await?;
+    switch_number_suffix_to_desc("NIH grant number", ex).await?;
+    switch_number_suffix_to_desc("Contract number", ex).await?;
+    switch_number_suffix_to_desc("IDRCB number", ex).await?;
+    switch_number_suffix_to_desc("UCSD number", ex).await?;
+    switch_number_suffix_to_desc("REC number", ex).await?;
+    switch_number_suffix_to_desc("REK number", ex).await?;
+    switch_number_suffix_to_desc("Station number", ex).await?;
+    switch_number_suffix_to_desc("EUDRACT number", ex).await?;
+    switch_number_suffix_to_desc("EU CT number", ex).await?;
+    switch_number_suffix_to_desc("EudraCT-number", ex).await?;
+    switch_number_suffix_to_desc("EudraCT number", ex).await?;
+    switch_number_suffix_to_desc("Eudra CT number", ex).await?;
+    switch_number_suffix_to_desc("Scripps SOPRS number", ex).await?;
+    switch_number_suffix_to_desc("Study number", ex).await?;
+    switch_number_suffix_to_desc("Logan IRB number", ex).await?;
+    switch_number_suffix_to_desc("Award number", ex).await?;
+    switch_number_suffix_to_desc("Sponsor number", ex).await?;
+    switch_number_suffix_to_desc("Institution number", ex).await?;
+    switch_number_suffix_to_desc("VA IRB number", ex).await?;
+    switch_number_suffix_to_desc("Chilean number", ex).await?;
+    switch_number_suffix_to_desc("Clinical Trial number", ex).await?;
+    switch_number_suffix_to_desc("IMI WP8A number", ex).await?;
+    switch_number_suffix_to_desc("UCSF RAS number", ex).await?;
+    switch_number_suffix_to_desc("no UCI HS number", ex).await?;
+    switch_number_suffix_to_desc("UHN REB number", ex).await?;
+    switch_number_suffix_to_desc("CR number", ex).await?;
+    switch_number_suffix_to_desc("IRB number", ex).await?;
+    switch_number_suffix_to_desc("Hospice number", ex).await?;
+    switch_number_suffix_to_desc("Protocol number", ex).await?;
+    switch_number_suffix_to_desc("protocol number", ex).await?;
+    switch_number_suffix_to_desc("grant number", ex).await?;
+
+    switch_number_suffix_to_desc("MBW-number", ex).await?;
+    switch_number_suffix_to_desc("NIAID CRMS ID number", ex).await?;
+    switch_number_suffix_to_desc("DDX exemption from MRHA number", ex).await?;
+    switch_number_suffix_to_desc("number", ex).await?;
     
     // the last two are odd in that they have an empty Version number suffix, which can be removed
 
     let sql: &'static str = r#"update ad.temp_idents
         set id_value = replace(id_value, ', Version', '')
         where id_value ~ ', Version$'"#;
-    let res = execute_sql(sql, pool).await?;
+    let res = ex.execute(sql).await?;
     info!("{} Empty version numbers removed", res.rows_affected());
 
     // Repeat removal of spurious 'number 's that simply prefix an identifier
@@ -947,26 +827,26 @@ async fn make_identifier_text_more_consistent(pool: &Pool<Postgres>) -> Result<(
     let sql = r#"update ad.temp_idents
         set id_value = replace(id_value, 'number ', '')
         where id_value ~ '^number ';"#;
-    let res = execute_sql(sql, pool).await?;
+    let res = ex.execute(sql).await?;
     info!("{} Initial 'number's removed", res.rows_affected());
       
     // clean up beginnings
 
-    remove_leading_char_from_ident('.', pool).await?;
-    remove_leading_char_from_ident(':', pool).await?;
+    remove_leading_char_from_ident('.', ex).await?;
+    remove_leading_char_from_ident(':', ex).await?;
     
     // Final trim.
 
     let sql = r#"update ad.temp_idents
         set id_value = trim(id_value);"#;
-    execute_sql(sql, pool).await?;
+    ex.execute(sql).await?;
 
     info!("");
     Ok(())
 }
 
 
-async fn remove_duplicate_identifiers(pool: &Pool<Postgres>) -> Result<(), AppError> { 
+async fn remove_duplicate_identifiers(ex: &SqlExecutor) -> Result<(), AppError> { 
 
     // Identify duplicated (study id + identifier)s, and link them to the 
     // full identifier records in temp_idents, creating temp_id_dups.
@@ -984,7 +864,7 @@ async fn remove_duplicate_identifiers(pool: &Pool<Postgres>) -> Result<(), AppEr
         on a.sd_sid = d.sd_sid
         and a.id_value = d.id_value
         order by sd_sid, id_value;"#;
-    execute_sql(sql, pool).await?;
+    ex.execute(sql).await?;
 
     // The id_desc field in ad.temp_id_dups 
     // should be updated to include all the id_desc entries for each 
@@ -998,7 +878,7 @@ async fn remove_duplicate_identifiers(pool: &Pool<Postgres>) -> Result<(), AppEr
             group by sd_sid, id_value) descs
         where d.sd_sid = descs.sd_sid
         and d.id_value = descs.id_value"#;
-    execute_sql(sql, pool).await?;
+    ex.execute(sql).await?;
     
     // Create a table of the minimum id records for each duplicate record
     // (Some are duplicated more than once).
@@ -1009,7 +889,7 @@ async fn remove_duplicate_identifiers(pool: &Pool<Postgres>) -> Result<(), AppEr
         select sd_sid, id_value, id_desc, min(id) as min_id 
         from ad.temp_id_dups 
         group by sd_sid, id_value, id_desc;"#;
-    execute_sql(sql, pool).await?;
+    ex.execute(sql).await?;
 
     // Use the minimum table to update the id_desc field in the main table,
     // of the minimum id records (the ones that will be retained).
@@ -1019,7 +899,7 @@ async fn remove_duplicate_identifiers(pool: &Pool<Postgres>) -> Result<(), AppEr
         from ad.temp_id_dup_mins m
         where a.id = m.min_id
         and m.id_desc <> '';"#;
-    execute_sql(sql, pool).await?;
+    ex.execute(sql).await?;
    
     // Use the minimum table to remove the minimum id version of each 
     // duplicated record from temp_id_dups.
@@ -1027,7 +907,7 @@ async fn remove_duplicate_identifiers(pool: &Pool<Postgres>) -> Result<(), AppEr
     let sql = r#"delete from ad.temp_id_dups d
         using ad.temp_id_dup_mins m
         where d.id = m.min_id;"#;
-    execute_sql(sql, pool).await?;
+    ex.execute(sql).await?;
 
     // Use the remaining temp_id_dups records to delete the 
     // spurious records from temp_idents, leaving only the 
@@ -1036,19 +916,19 @@ async fn remove_duplicate_identifiers(pool: &Pool<Postgres>) -> Result<(), AppEr
     let sql = r#"delete from ad.temp_idents c
         using ad.temp_id_dups d
         where c.id = d.id"#;
-    let res = execute_sql(sql, pool).await?;
+    let res = ex.execute(sql).await?;
     info!("{} duplicated identifiers removed", res.rows_affected());
 
     let sql = r#"drop table if exists ad.temp_id_dup_mins;
         drop table if exists ad.temp_id_dups;
         SET client_min_messages TO NOTICE;"#;
-    execute_sql(sql, pool).await?;
+    ex.execute(sql).await?;
 
     // Also remove those records where the identifier is the same as the NCT id.
 
     let sql = r#"delete from ad.temp_idents 
         where id_value = sd_sid"#;
-    let res = execute_sql(sql, pool).await?;
+    let res = ex.execute(sql).await?;
     info!("{} records deleted where identifier = NCT Id", res.rows_affected());
 
     info!("");
@@ -1056,7 +936,7 @@ async fn remove_duplicate_identifiers(pool: &Pool<Postgres>) -> Result<(), AppEr
 }
 
 
-async fn remove_obvious_non_identifiers2(pool: &Pool<Postgres>) -> Result<(), AppError> {  
+async fn remove_obvious_non_identifiers2(ex: &SqlExecutor) -> Result<(), AppError> {  
    
     // Remove a few additional records that now don't have values (or meaningful values).
 
@@ -1064,7 +944,7 @@ async fn remove_obvious_non_identifiers2(pool: &Pool<Postgres>) -> Result<(), Ap
         where id_value is null
         or id_value = ''
         or id_value ~ '^[0-]+$';"#;
-    let res = execute_sql(sql, pool).await?;
+    let res = ex.execute(sql).await?;
     info!("{} Additional empty or meaningless identifier records removed", res.rows_affected());
 
     // Some records have a digit in the id_desc field and only text (inc. spaces and punctuation)
@@ -1076,21 +956,21 @@ async fn remove_obvious_non_identifiers2(pool: &Pool<Postgres>) -> Result<(), Ap
         id_desc = id_value
         where id_value ~ '^[A-Za-z\s\.&,_/#-]+$'
         and id_desc ~ '[0-9]'"#;
-    let res = execute_sql(&sql, pool).await?;
+    let res = ex.execute(sql).await?;
     info!("{} identifiers and identifier descriptions reversed", res.rows_affected());
 
     // Get rid of all remaining identifiers that include only letters, spaces, and punctuation other than hyphens.
     
     let sql = r#"delete from ad.temp_idents
         where id_value ~ '^[A-Za-z\s\.&,_/]+$'"#;
-    execute_sql_sfb(sql, pool, "consisting only of letters, spaces, and certain punctuation removed").await?;
+    execute_sql_sfb(sql, ex, "consisting only of letters, spaces, and certain punctuation removed").await?;
     
     // Get rid of a small group that seem to be part of a list of conditions / symptoms
 
     let sql = r#"delete from ad.temp_idents
         where id_value ~ '^[0-9]\. '
         and id_value !~ '^6. NUP'"#;
-    execute_sql_sfb(sql, pool, "that appear to be list items removed").await?;
+    execute_sql_sfb(sql, ex, "that appear to be list items removed").await?;
     
     info!("");
     Ok(())
@@ -1098,7 +978,7 @@ async fn remove_obvious_non_identifiers2(pool: &Pool<Postgres>) -> Result<(), Ap
 }
 
 
-async fn code_very_short_unidentifiable_ids(pool: &Pool<Postgres>) -> Result<(), AppError> { 
+async fn code_very_short_unidentifiable_ids(ex: &SqlExecutor) -> Result<(), AppError> { 
 
     // There are many small identifiers, that if given without any further information,
     // are impossible to characterise as they could have a wide variety of sources.
@@ -1114,7 +994,7 @@ async fn code_very_short_unidentifiable_ids(pool: &Pool<Postgres>) -> Result<(),
         where id_value ~ '^[0-9]+$'
         and length (id_value) < 7
         and id_desc is null"#;
-    execute_sql_sfb(sql, pool, "with only numbers (length < 7) and no description labelled").await?;
+    execute_sql_sfb(sql, ex, "with only numbers (length < 7) and no description labelled").await?;
     
     let sql = r#"update ad.temp_idents
         set id_type_id = 990,
@@ -1125,7 +1005,7 @@ async fn code_very_short_unidentifiable_ids(pool: &Pool<Postgres>) -> Result<(),
         and length (id_value) < 6
         and id_desc is null
         and id_type_id is null"#;
-    execute_sql_sfb(sql, pool, "with only numbers and hyphens (length < 6) and no description labelled").await?;
+    execute_sql_sfb(sql, ex, "with only numbers and hyphens (length < 6) and no description labelled").await?;
 
     let sql = r#"update ad.temp_idents
         set id_type_id = 990,
@@ -1136,7 +1016,7 @@ async fn code_very_short_unidentifiable_ids(pool: &Pool<Postgres>) -> Result<(),
         and length(id_value) < 6
         and id_desc is null
         and id_type_id is null"#;
-    execute_sql_sfb(sql, pool, "with only numbers and periods (length < 6) and no description labelled").await?;
+    execute_sql_sfb(sql, ex, "with only numbers and periods (length < 6) and no description labelled").await?;
 
     let sql = r#"update ad.temp_idents
         set id_type_id = 990,
@@ -1147,7 +1027,7 @@ async fn code_very_short_unidentifiable_ids(pool: &Pool<Postgres>) -> Result<(),
         and length (id_value) < 5
         and id_desc is null
         and id_type_id is null"#;
-    execute_sql_sfb(sql, pool, "with length < 5 and no description labelled").await?;
+    execute_sql_sfb(sql, ex, "with length < 5 and no description labelled").await?;
 
     info!("");
     Ok(())
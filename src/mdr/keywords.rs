@@ -1,11 +1,31 @@
-use super::utils;
 
-use sqlx::{Pool, Postgres};
+use super::dry_run;
+use super::executor::SqlExecutor;
 use crate::AppError;
-use log::info;
+use log::{info, warn};
+use serde::Deserialize;
 
+// The mapping of ctgov design and phase values to mdr study features,
+// with one row per source value (see load_features_data).
 
-pub async fn build_topics_table (pool: &Pool<Postgres>) -> Result<(), AppError> {  
+static FEATURE_MAP: &str = include_str!("feature_map.csv");
+
+// The controlled terminology type for MeSH (from the mdr lookup tables).
+
+const MESH_CT_TYPE: i32 = 14;
+
+// Topic types, for keywords and the broad types of intervention.
+
+const TOPIC_KEYWORD: i32 = 11;
+const TOPIC_CHEMICAL: i32 = 12;
+const TOPIC_DEVICE: i32 = 13;
+const TOPIC_PROCEDURE: i32 = 14;
+const TOPIC_BEHAVIOURAL: i32 = 15;
+const TOPIC_GENETIC: i32 = 16;
+const TOPIC_OTHER_INTERVENTION: i32 = 17;
+
+
+pub async fn build_topics_table (ex: &SqlExecutor) -> Result<(), AppError> {  
 
     let sql = r#"SET client_min_messages TO WARNING; 
     DROP TABLE IF EXISTS ad.study_topics;
@@ -23,14 +43,82 @@ pub async fn build_topics_table (pool: &Pool<Postgres>) -> Result<(), AppError>
     );
     CREATE INDEX study_topics_sid ON ad.study_topics(sd_sid);"#;
 
-    utils::execute_sql(sql, pool).await?;
+    ex.execute(sql).await?;
     info!("study topics table (re)created");
     
     Ok(())
 
 }
 
-pub async fn build_conditions_table (pool: &Pool<Postgres>) -> Result<(), AppError> {  
+
+pub async fn load_topics_data (ex: &SqlExecutor) -> Result<(), AppError> {
+
+    // Topics are the study's interventions, typed by the intervention type, and its
+    // keywords. Interventions are added first, so that a keyword that repeats an
+    // intervention is the record removed as a duplicate. As for conditions, topics
+    // matching the MeSH terms AACT had listed for the study's interventions (kept in
+    // ctgov.mesh_interventions by the simplify stage) are marked as MeSH terms, and
+    // the other MeSH terms added as topics, without MeSH codes, as these are not in AACT.
+
+    let sql = format!(r#"insert into ad.study_topics(sd_sid, topic_type_id, original_value)
+        select nct_id,
+        case upper(replace(intervention_type, ' ', '_'))
+            when 'DRUG' then {chem}
+            when 'BIOLOGICAL' then {chem}
+            when 'DIETARY_SUPPLEMENT' then {chem}
+            when 'COMBINATION_PRODUCT' then {chem}
+            when 'DEVICE' then {device}
+            when 'DIAGNOSTIC_TEST' then {device}
+            when 'PROCEDURE' then {proc}
+            when 'RADIATION' then {proc}
+            when 'BEHAVIORAL' then {behav}
+            when 'GENETIC' then {genetic}
+            else {other}
+        end, name
+        from ctgov.interventions
+        where name is not null
+        order by nct_id, id;"#, chem = TOPIC_CHEMICAL, device = TOPIC_DEVICE, proc = TOPIC_PROCEDURE,
+        behav = TOPIC_BEHAVIOURAL, genetic = TOPIC_GENETIC, other = TOPIC_OTHER_INTERVENTION);
+    execute_kw_fb(&sql, "study_topics", "added from ctgov interventions", ex).await?;
+
+    let sql = format!(r#"insert into ad.study_topics(sd_sid, topic_type_id, original_value)
+        select nct_id, {}, name from ctgov.keywords
+        where name is not null
+        order by nct_id, id;"#, TOPIC_KEYWORD);
+    execute_kw_fb(&sql, "study_topics", "added from ctgov keywords", ex).await?;
+
+    clean_original_values("study_topics", ex).await?;
+    execute_kw_fb(r#"delete from ad.study_topics
+        where lower(original_value) in ('placebo', 'placebos', 'placebo comparator', 'sham', 'control',
+        'standard of care', 'usual care', 'no intervention');"#, "study_topics", "removed as placebos or controls", ex).await?;
+    execute_kw_fb(&duplicate_values_sql("study_topics"), "study_topics", "removed as duplicates", ex).await?;
+    info!("");
+
+    let sql = format!(r#"update ad.study_topics t
+        set original_ct_type_id = {},
+        mesh_value = m.mesh_term
+        from ctgov.mesh_interventions m
+        where t.sd_sid = m.nct_id
+        and lower(t.original_value) = lower(m.mesh_term);"#, MESH_CT_TYPE);
+    execute_kw_fb(&sql, "study_topics", "matched to the study's MeSH terms", ex).await?;
+
+    let sql = format!(r#"insert into ad.study_topics(sd_sid, topic_type_id, original_value, original_ct_type_id, mesh_value)
+        select distinct m.nct_id, {}, m.mesh_term, {}, m.mesh_term
+        from ctgov.mesh_interventions m
+        where not exists (select 1 from ad.study_topics t
+                          where t.sd_sid = m.nct_id
+                          and lower(t.original_value) = lower(m.mesh_term))
+        order by m.nct_id, m.mesh_term;"#, TOPIC_CHEMICAL, MESH_CT_TYPE);
+    execute_kw_fb(&sql, "study_topics", "added from the study's other MeSH terms", ex).await?;
+    info!("");
+
+    ex.vacuum_table("study_topics").await?;
+
+    Ok(())
+}
+
+
+pub async fn build_conditions_table (ex: &SqlExecutor) -> Result<(), AppError> {  
 
     let sql = r#"SET client_min_messages TO WARNING; 
     DROP TABLE IF EXISTS ad.study_conditions;
@@ -47,7 +135,7 @@ pub async fn build_conditions_table (pool: &Pool<Postgres>) -> Result<(), AppErr
     );
     CREATE INDEX study_conditions_sid ON ad.study_conditions(sd_sid);"#;
 
-    utils::execute_sql(sql, pool).await?;
+    ex.execute(sql).await?;
     info!("study conditions table (re)created");
     
     Ok(())
@@ -55,7 +143,97 @@ pub async fn build_conditions_table (pool: &Pool<Postgres>) -> Result<(), AppErr
 }
 
 
-pub async fn build_features_table (pool: &Pool<Postgres>) -> Result<(), AppError> {  
+pub async fn load_conditions_data (ex: &SqlExecutor) -> Result<(), AppError> {
+
+    // The conditions are as listed by the study in ctgov.conditions. They are cleaned
+    // and de-duplicated, and then matched against the MeSH terms AACT had listed for
+    // the study (kept in ctgov.mesh_conditions by the simplify stage). Matching conditions
+    // are marked as MeSH terms, and any other MeSH terms added as conditions in their
+    // own right. MeSH codes are not available in AACT, and ICD coding is left to the
+    // encode stage.
+
+    let sql = r#"insert into ad.study_conditions(sd_sid, original_value)
+        select nct_id, name from ctgov.conditions
+        where name is not null
+        order by nct_id, id;"#;
+    execute_kw_fb(sql, "study_conditions", "added from ctgov conditions", ex).await?;
+
+    clean_original_values("study_conditions", ex).await?;
+    execute_kw_fb(&duplicate_values_sql("study_conditions"), "study_conditions", "removed as duplicates", ex).await?;
+    info!("");
+
+    let sql = format!(r#"update ad.study_conditions c
+        set original_ct_type_id = {}
+        from ctgov.mesh_conditions m
+        where c.sd_sid = m.nct_id
+        and lower(c.original_value) = lower(m.mesh_term);"#, MESH_CT_TYPE);
+    execute_kw_fb(&sql, "study_conditions", "matched to the study's MeSH terms", ex).await?;
+
+    let sql = format!(r#"insert into ad.study_conditions(sd_sid, original_value, original_ct_type_id)
+        select distinct m.nct_id, m.mesh_term, {}
+        from ctgov.mesh_conditions m
+        where not exists (select 1 from ad.study_conditions c
+                          where c.sd_sid = m.nct_id
+                          and lower(c.original_value) = lower(m.mesh_term))
+        order by m.nct_id, m.mesh_term;"#, MESH_CT_TYPE);
+    execute_kw_fb(&sql, "study_conditions", "added from the study's other MeSH terms", ex).await?;
+    info!("");
+
+    ex.vacuum_table("study_conditions").await?;
+
+    Ok(())
+}
+
+
+async fn clean_original_values (table: &str, ex: &SqlExecutor) -> Result<(), AppError> {
+
+    // Used for both conditions and topics, which are often entered as lists, with
+    // stray bullets and punctuation. Empty values and placeholders are removed.
+
+    let cleaning = [
+        (r#"regexp_replace(trim(original_value), '\s+', ' ', 'g')"#, r#"original_value ~ '^\s|\s$|\s{2,}'"#, "had spacing regularised"),
+        (r#"trim(replace(original_value, '"', ''))"#, r#"original_value like '%"%'"#, "had double quotes removed"),
+        (r#"replace(original_value, '''', '’')"#, r#"original_value like '%''%'"#, "had apostrophes replaced by right single quotes"),
+        (r#"trim(regexp_replace(original_value, '^[-*•·>.,;:]+', ''))"#, r#"original_value ~ '^[-*•·>.,;:]'"#, "had leading bullets or punctuation removed"),
+        (r#"trim(regexp_replace(original_value, '[.,;:]+$', ''))"#, r#"original_value ~ '[.,;:]$'"#, "had trailing punctuation removed"),
+    ];
+    for (new_value, condition, fb) in cleaning {
+        let sql = format!("update ad.{} set original_value = {} where {};", table, new_value, condition);
+        execute_kw_fb(&sql, table, fb, ex).await?;
+    }
+
+    let sql = format!(r#"delete from ad.{}
+        where original_value is null
+        or original_value = ''
+        or lower(original_value) in ('n/a', 'na', 'none', 'not applicable', 'unknown', 'other',
+        'not specified', 'tbd', 'condition', 'disease', '-');"#, table);
+    execute_kw_fb(&sql, table, "removed as empty or placeholders", ex).await?;
+
+    Ok(())
+}
+
+
+async fn execute_kw_fb (sql: &str, table: &str, fb: &str, ex: &SqlExecutor) -> Result<(), AppError> {
+
+    let r = ex.execute(sql).await?.rows_affected();
+    info!("{} {} records {}", r, table, fb);
+    Ok(())
+}
+
+
+fn duplicate_values_sql (table: &str) -> String {
+
+    // The earlier record (the lower id) is retained.
+
+    format!(r#"delete from ad.{} a
+    using ad.{} b
+    where a.sd_sid = b.sd_sid
+    and lower(a.original_value) = lower(b.original_value)
+    and a.id > b.id;"#, table, table)
+}
+
+
+pub async fn build_features_table (ex: &SqlExecutor) -> Result<(), AppError> {  
 
     let sql = r#"SET client_min_messages TO WARNING; 
     DROP TABLE IF EXISTS ad.study_features;
@@ -69,10 +247,162 @@ pub async fn build_features_table (pool: &Pool<Postgres>) -> Result<(), AppError
     );
     CREATE INDEX study_features_sid ON ad.study_features(sd_sid);"#;
 
-    utils::execute_sql(sql, pool).await?;
+    ex.execute(sql).await?;
     info!("study features table (re)created");
     
     Ok(())
 
 }
 
+
+pub async fn load_features_data (ex: &SqlExecutor) -> Result<(), AppError> {
+
+    // Feature values are mapped from the ctgov design and phase fields using the
+    // lookup in feature_map.csv (compiled into the program), loaded into a temporary
+    // table. Source values are compared in upper case without spaces or punctuation,
+    // so that both AACT's current ('NON_RANDOMIZED') and earlier ('Non-Randomized')
+    // vocabularies can be matched. Values not in the lookup are reported.
+
+    let map = read_feature_map()?;
+
+    let sql = r#"SET client_min_messages TO WARNING;
+    DROP TABLE IF EXISTS ad.temp_feature_map;
+    CREATE TABLE ad.temp_feature_map(
+      source_column          VARCHAR         NOT NULL
+    , match_value            VARCHAR         NOT NULL
+    , feature_type_id        INT             NOT NULL
+    , feature_value_id       INT             NOT NULL
+    );"#;
+    ex.execute(sql).await?;
+
+    let columns = vec![
+        map.iter().map(|m| m.source_column.clone()).collect(),
+        map.iter().map(|m| feature_match_value(&m.source_value)).collect(),
+        map.iter().map(|m| m.feature_type_id.to_string()).collect(),
+        map.iter().map(|m| m.feature_value_id.to_string()).collect(),
+    ];
+    ex.execute_with_arrays(insert_feature_map_sql(), &columns).await?;
+
+    let mut source_columns: Vec<&str> = Vec::new();
+    for m in map.iter() {
+        if !source_columns.contains(&m.source_column.as_str()) {
+            source_columns.push(&m.source_column);
+        }
+    }
+
+    for source_column in source_columns {
+        let Some((table, column)) = source_column.split_once('.') else { continue };
+        let sql = format!(r#"insert into ad.study_features(sd_sid, feature_type_id, feature_value_id)
+            select s.nct_id, m.feature_type_id, m.feature_value_id
+            from ctgov.{} s
+            inner join ad.temp_feature_map m
+            on m.source_column = '{}'
+            and m.match_value = upper(regexp_replace(s.{}, '[^A-Za-z0-9]', '', 'g'))
+            order by s.nct_id;"#, table, source_column, column);
+        let r = ex.execute(&sql).await?.rows_affected();
+        info!("{} study features added from {}", r, source_column);
+        report_unmapped_values(table, column, ex).await?;
+    }
+    info!("");
+
+    ex.execute("DROP TABLE IF EXISTS ad.temp_feature_map;").await?;
+    ex.vacuum_table("study_features").await?;
+
+    Ok(())
+}
+
+
+async fn report_unmapped_values (table: &str, column: &str, ex: &SqlExecutor) -> Result<(), AppError> {
+
+    // Unmapped values usually indicate a change to AACT's vocabulary,
+    // and need to be added to feature_map.csv.
+
+    if dry_run::is_active() {
+        return Ok(());
+    }
+
+    let sql = format!(r#"select s.{col}::varchar, count(*) from ctgov.{tab} s
+        where s.{col} is not null and trim(s.{col}) <> ''
+        and not exists (select 1 from ad.temp_feature_map m
+                        where m.source_column = '{tab}.{col}'
+                        and m.match_value = upper(regexp_replace(s.{col}, '[^A-Za-z0-9]', '', 'g')))
+        group by s.{col} order by count(*) desc"#, tab = table, col = column);
+    let unmapped: Vec<(String, i64)> = ex.fetch_rows(&sql).await?;
+    for (value, n) in unmapped {
+        warn!("{} {}.{} records have a value not in the feature map: '{}'", n, table, column, value);
+    }
+    Ok(())
+}
+
+
+#[derive(Debug, Deserialize)]
+struct FeatureMapEntry {
+    source_column: String,
+    source_value: String,
+    feature_type_id: i32,
+    feature_value_id: i32,
+}
+
+
+fn read_feature_map() -> Result<Vec<FeatureMapEntry>, AppError> {
+
+    let mut rdr = csv::Reader::from_reader(FEATURE_MAP.as_bytes());
+    let mut entries = Vec::new();
+    for result in rdr.deserialize() {
+        entries.push(result?);
+    }
+    Ok(entries)
+}
+
+
+fn feature_match_value(value: &str) -> String {
+
+    // Must match the upper(regexp_replace(...)) applied to the source values in sql.
+
+    value.chars().filter(char::is_ascii_alphanumeric).collect::<String>().to_uppercase()
+}
+
+
+fn insert_feature_map_sql <'a>() -> &'a str {
+    r#"insert into ad.temp_feature_map(source_column, match_value, feature_type_id, feature_value_id)
+    select source_column, match_value, feature_type_id::int, feature_value_id::int
+    from unnest($1::varchar[], $2::varchar[], $3::varchar[], $4::varchar[])
+    as t(source_column, match_value, feature_type_id, feature_value_id);"#
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_feature_map_is_unambiguous() {
+
+        // Each source value must map to a single feature, and each feature
+        // value must belong to a single feature type.
+
+        let map = read_feature_map().unwrap();
+        assert!(!map.is_empty());
+        let mut keys: Vec<(String, String)> = map.iter()
+            .map(|m| (m.source_column.clone(), feature_match_value(&m.source_value)))
+            .collect();
+        keys.sort();
+        let n = keys.len();
+        keys.dedup();
+        assert_eq!(keys.len(), n);
+
+        for m in map.iter() {
+            assert!(m.source_column.split_once('.').is_some());
+            assert!(map.iter().all(|o| o.feature_value_id != m.feature_value_id || o.feature_type_id == m.feature_type_id));
+        }
+    }
+
+    #[test]
+    fn check_feature_match_value() {
+        assert_eq!(feature_match_value("Non-Randomized"), "NONRANDOMIZED");
+        assert_eq!(feature_match_value("NON_RANDOMIZED"), "NONRANDOMIZED");
+        assert_eq!(feature_match_value("Phase 1/Phase 2"), "PHASE1PHASE2");
+        assert_eq!(feature_match_value("N/A"), "NA");
+    }
+}
@@ -1,11 +1,26 @@
-use super::utils;
 
-use sqlx::{Pool, Postgres};
+use super::dry_run;
+use super::executor::SqlExecutor;
 use crate::AppError;
 use log::info;
 
+// Reference types (from the mdr lookup tables).
 
-pub async fn build_rels_table (pool: &Pool<Postgres>) -> Result<(), AppError> {  
+const REF_TYPE_RESULT: i32 = 202;
+const REF_TYPE_BACKGROUND: i32 = 203;
+const REF_TYPE_DERIVED: i32 = 204;
+
+// Relationship types, of the study to the target study. Duplicate
+// (including obsolete aliases) and generically related are symmetric.
+
+const REL_DUPLICATE: i32 = 21;
+const REL_EXTENSION: i32 = 22;
+const REL_SUB_STUDY: i32 = 23;
+const REL_FOLLOW_UP: i32 = 24;
+const REL_RELATED: i32 = 25;
+
+
+pub async fn build_rels_table (ex: &SqlExecutor) -> Result<(), AppError> {  
 
     let sql = r#"SET client_min_messages TO WARNING; 
     DROP TABLE IF EXISTS ad.study_relationships;
@@ -19,14 +34,124 @@ pub async fn build_rels_table (pool: &Pool<Postgres>) -> Result<(), AppError> {
     CREATE INDEX study_relationships_sid ON ad.study_relationships(sd_sid);
     CREATE INDEX study_relationships_target_sid ON ad.study_relationships(target_sd_sid);"#;
 
-	utils::execute_sql(sql, pool).await?;
+	ex.execute(sql).await?;
     info!("study relationships table (re)created");
     
     Ok(())
 
 }
 
-pub async fn build_refs_table (pool: &Pool<Postgres>) -> Result<(), AppError> {  
+
+pub async fn load_rels_data (ex: &SqlExecutor) -> Result<(), AppError> {
+
+    // Relationships are found from the NCT ids that refer to other studies: the obsolete
+    // NCT aliases recorded by the idents stage (id_type 180), NCT ids among the unclassified
+    // identifiers left in ad.temp_idents, and NCT ids mentioned in the titles and brief
+    // summaries. Each reference is collected with the text preceding it, which is used to
+    // classify the relationship.
+
+    let sql = r#"SET client_min_messages TO WARNING;
+    DROP TABLE IF EXISTS ad.temp_rels;
+    CREATE TABLE ad.temp_rels(
+      sd_sid                 VARCHAR         NOT NULL
+    , target_sd_sid          VARCHAR         NOT NULL
+    , context                VARCHAR         NULL
+    , source                 VARCHAR         NOT NULL
+    );"#;
+    ex.execute(sql).await?;
+
+    let sql = r#"insert into ad.temp_rels(sd_sid, target_sd_sid, context, source)
+        select sd_sid, upper(id_value), 'alias', 'alias'
+        from ad.study_identifiers
+        where id_type_id = 180;"#;
+    execute_links_fb(sql, "study relationship", "found from obsolete NCT aliases", ex).await?;
+
+    for (source, select_sql) in [
+        ("identifiers", "select sd_sid, coalesce(id_desc, '')||' '||id_value from ad.temp_idents"),
+        ("titles", "select nct_id, official_title from ctgov.studies union all select nct_id, brief_title from ctgov.studies"),
+        ("summaries", "select nct_id, description from ctgov.brief_summaries"),
+    ] {
+        let sql = format!(r#"insert into ad.temp_rels(sd_sid, target_sd_sid, context, source)
+            select t.sd_sid, 'NCT'||m[2], lower(m[1]), '{}'
+            from ({}) as t(sd_sid, txt),
+            regexp_matches(t.txt, '(.{{0,80}}?)\mNCT[ -]?([0-9]{{8}})\M', 'gi') as m;"#, source, select_sql);
+        execute_links_fb(&sql, "study relationship", &format!("found from NCT ids in {}", source), ex).await?;
+    }
+    info!("");
+
+    let sql = format!(r#"insert into ad.study_relationships(sd_sid, relationship_type_id, target_sd_sid)
+        select distinct sd_sid, {}, target_sd_sid
+        from ad.temp_rels
+        where sd_sid <> target_sd_sid
+        order by sd_sid, target_sd_sid;"#, rel_type_sql("context"));
+    execute_links_fb(&sql, "study relationship", "classified and added", ex).await?;
+
+    // Symmetric relationships are added in the reverse direction, if the target is
+    // a study in this snapshot (obsolete aliases are not, for instance).
+
+    let sql = format!(r#"insert into ad.study_relationships(sd_sid, relationship_type_id, target_sd_sid)
+        select r.target_sd_sid, r.relationship_type_id, r.sd_sid
+        from ad.study_relationships r
+        inner join ctgov.studies s
+        on r.target_sd_sid = s.nct_id
+        where r.relationship_type_id in ({}, {})
+        and not exists (select 1 from ad.study_relationships x
+                        where x.sd_sid = r.target_sd_sid
+                        and x.target_sd_sid = r.sd_sid
+                        and x.relationship_type_id = r.relationship_type_id)
+        order by r.target_sd_sid;"#, REL_DUPLICATE, REL_RELATED);
+    execute_links_fb(&sql, "study relationship", "added as reciprocals of symmetric relationships", ex).await?;
+
+    // A generic relationship is not needed if a more specific one has been found.
+
+    let sql = format!(r#"delete from ad.study_relationships a
+        using ad.study_relationships b
+        where a.sd_sid = b.sd_sid
+        and a.target_sd_sid = b.target_sd_sid
+        and a.relationship_type_id = {}
+        and b.relationship_type_id <> {};"#, REL_RELATED, REL_RELATED);
+    execute_links_fb(&sql, "study relationship", "removed as generic duplicates of specific relationships", ex).await?;
+    report_rel_type_counts(ex).await?;
+    info!("");
+
+    ex.execute("DROP TABLE IF EXISTS ad.temp_rels;").await?;
+    ex.vacuum_table("study_relationships").await?;
+
+    Ok(())
+}
+
+
+async fn report_rel_type_counts (ex: &SqlExecutor) -> Result<(), AppError> {
+
+    if dry_run::is_active() {
+        return Ok(());
+    }
+
+    let sql = r#"select relationship_type_id, count(*) from ad.study_relationships
+                 group by relationship_type_id order by relationship_type_id"#;
+    let counts: Vec<(i32, i64)> = ex.fetch_rows(sql).await?;
+    for (type_id, n) in counts {
+        info!("{} study relationships of type {}", n, type_id);
+    }
+    Ok(())
+}
+
+
+fn rel_type_sql (column: &str) -> String {
+
+    // The context is the (lower cased) text preceding the NCT id, up to 80 characters.
+
+    format!(r#"case
+            when {c} = 'alias' or {c} ~ 'duplicate|same study|alias|also registered|previously registered|re-?registered' then {dup}
+            when {c} ~ 'extension|roll-?over' then {ext}
+            when {c} ~ 'sub-? ?stud|ancillary|companion' then {sub}
+            when {c} ~ 'follow-? ?up|long-? ?term' then {fup}
+            else {rel}
+        end"#, c = column, dup = REL_DUPLICATE, ext = REL_EXTENSION, sub = REL_SUB_STUDY,
+        fup = REL_FOLLOW_UP, rel = REL_RELATED)
+}
+
+pub async fn build_refs_table (ex: &SqlExecutor) -> Result<(), AppError> {  
 
     let sql = r#"SET client_min_messages TO WARNING; 
     DROP TABLE IF EXISTS ad.study_references;
@@ -38,11 +163,12 @@ pub async fn build_refs_table (pool: &Pool<Postgres>) -> Result<(), AppError> {
     , doi                    VARCHAR         NULL	
     , type_id                INT             NULL
     , comments               VARCHAR         NULL
+    , duplicate_of           INT             NULL
     , added_on               TIMESTAMPTZ     NOT NULL default now()
     );
     CREATE INDEX study_references_sid ON ad.study_references(sd_sid);"#;
 
-	utils::execute_sql(sql, pool).await?;
+	ex.execute(sql).await?;
     info!("study refs table (re)created");
     
     Ok(())
@@ -50,7 +176,90 @@ pub async fn build_refs_table (pool: &Pool<Postgres>) -> Result<(), AppError> {
 }
 
 
-pub async fn build_links_table (pool: &Pool<Postgres>) -> Result<(), AppError> {  
+pub async fn load_refs_data (ex: &SqlExecutor) -> Result<(), AppError> {
+
+    // References are as listed in ctgov.study_references, with the reference type
+    // (results, background or derived) mapped to type_id. PMIDs are normalised to
+    // their digits, or taken from the citation if not given separately. DOIs are
+    // extracted from the citation text, whether given as a doi.org URL, with a 'doi:'
+    // prefix or on their own.
+
+    let sql = format!(r#"insert into ad.study_references(sd_sid, pmid, citation, type_id)
+        select nct_id, trim(pmid), trim(citation),
+        case lower(replace(reference_type, '_', ' '))
+            when 'result' then {res}
+            when 'results reference' then {res}
+            when 'background' then {back}
+            when 'derived' then {der}
+        end
+        from ctgov.study_references
+        order by nct_id, id;"#, res = REF_TYPE_RESULT, back = REF_TYPE_BACKGROUND, der = REF_TYPE_DERIVED);
+    execute_refs_fb(&sql, "added from ctgov study references", ex).await?;
+    execute_refs_fb("delete from ad.study_references where coalesce(pmid, '') = '' and coalesce(citation, '') = '';",
+                    "removed as having neither pmid nor citation", ex).await?;
+    execute_refs_fb(r#"update ad.study_references set citation = regexp_replace(citation, '\s+', ' ', 'g')
+        where citation ~ '\s{2,}';"#, "had citation spacing regularised", ex).await?;
+    info!("");
+
+    ex.execute("update ad.study_references set pmid = null where pmid = '';").await?;
+    execute_refs_fb(r#"update ad.study_references set pmid = ltrim(regexp_replace(pmid, '^\s*(pmid|pubmed)\s*(id)?\s*[:#]?\s*', '', 'i'), '0')
+        where pmid ~* '^\s*(pmid|pubmed)' or pmid ~ '^0';"#, "had pmid prefixes or leading zeroes removed", ex).await?;
+    execute_refs_fb(r#"update ad.study_references set comments = 'Invalid pmid: '||pmid, pmid = null
+        where pmid !~ '^[1-9][0-9]{0,8}$';"#, "had invalid pmids removed (and kept in comments)", ex).await?;
+    execute_refs_fb(r#"update ad.study_references set pmid = substring(citation from '(?i)pmid\s*:?\s*([1-9][0-9]{0,8})\M')
+        where pmid is null
+        and citation ~* 'pmid\s*:?\s*[1-9][0-9]{0,8}\M';"#, "had pmids taken from the citation", ex).await?;
+    info!("");
+
+    execute_refs_fb(r#"update ad.study_references set doi = substring(citation from '10\.[0-9]{4,9}/[^\s"<>]+')
+        where citation ~ '10\.[0-9]{4,9}/';"#, "had dois extracted from the citation", ex).await?;
+    execute_refs_fb(r#"update ad.study_references set doi = regexp_replace(doi, '[.,;:''’)\]]+$', '')
+        where doi ~ '[)\]][.,;:''’]*$'
+        and doi !~ '[(\[]';"#, "had unmatched closing brackets removed from dois", ex).await?;
+    execute_refs_fb(r#"update ad.study_references set doi = regexp_replace(doi, '[.,;:''’]+$', '')
+        where doi ~ '[.,;:''’]$';"#, "had trailing punctuation removed from dois", ex).await?;
+    info!("");
+
+    execute_refs_fb(duplicate_refs_sql(), "flagged as duplicating an earlier reference for the study", ex).await?;
+    info!("");
+
+    ex.vacuum_table("study_references").await?;
+
+    Ok(())
+}
+
+
+async fn execute_refs_fb (sql: &str, fb: &str, ex: &SqlExecutor) -> Result<(), AppError> {
+
+    let r = ex.execute(sql).await?.rows_affected();
+    info!("{} study reference records {}", r, fb);
+    Ok(())
+}
+
+
+fn duplicate_refs_sql <'a>() -> &'a str {
+
+    // A reference duplicates an earlier one for the same study if they have the same
+    // pmid, the same doi, or the same citation (ignoring case, spacing and punctuation).
+    // duplicate_of is set to the id of the earliest of the matching references.
+
+    r#"update ad.study_references a
+    set duplicate_of = (select min(b.id) from ad.study_references b
+                        where b.sd_sid = a.sd_sid
+                        and b.id < a.id
+                        and (b.pmid = a.pmid
+                        or lower(b.doi) = lower(a.doi)
+                        or lower(regexp_replace(b.citation, '\W', '', 'g')) = lower(regexp_replace(a.citation, '\W', '', 'g'))))
+    where exists (select 1 from ad.study_references b
+                  where b.sd_sid = a.sd_sid
+                  and b.id < a.id
+                  and (b.pmid = a.pmid
+                  or lower(b.doi) = lower(a.doi)
+                  or lower(regexp_replace(b.citation, '\W', '', 'g')) = lower(regexp_replace(a.citation, '\W', '', 'g'))));"#
+}
+
+
+pub async fn build_links_table (ex: &SqlExecutor) -> Result<(), AppError> {  
 
     let sql = r#"SET client_min_messages TO WARNING; 
     DROP TABLE IF EXISTS ad.study_links;
@@ -63,7 +272,7 @@ pub async fn build_links_table (pool: &Pool<Postgres>) -> Result<(), AppError> {
     );
     CREATE INDEX study_links_sid ON ad.study_links(sd_sid);"#;
 
-	utils::execute_sql(sql, pool).await?;
+	ex.execute(sql).await?;
     info!("study links table (re)created");
     
     Ok(())
@@ -71,7 +280,158 @@ pub async fn build_links_table (pool: &Pool<Postgres>) -> Result<(), AppError> {
 }
 
 
-pub async fn build_ipd_available_table (pool: &Pool<Postgres>) -> Result<(), AppError> {  
+pub async fn load_links_data (ex: &SqlExecutor) -> Result<(), AppError> {
+
+    // Links are the web links listed for the study (ctgov.links), and the documents
+    // listed as available with it (ctgov.documents), labelled by their type and id.
+
+    let sql = r#"insert into ad.study_links(sd_sid, link_label, link_url)
+        select nct_id, description, url from ctgov.links
+        order by nct_id, id;"#;
+    execute_links_fb(sql, "study link", "added from ctgov links", ex).await?;
+
+    let sql = r#"insert into ad.study_links(sd_sid, link_label, link_url)
+        select nct_id, 
+        trim(coalesce(document_type, 'Document')||coalesce(' ('||nullif(trim(document_id), '')||')', '')),
+        url 
+        from ctgov.documents
+        order by nct_id, id;"#;
+    execute_links_fb(sql, "study link", "added from ctgov documents", ex).await?;
+    info!("");
+
+    execute_links_fb(r#"update ad.study_links set link_label = nullif(trim(regexp_replace(link_label, '\s+', ' ', 'g')), '')
+        where link_label ~ '^\s|\s$|\s{2,}' or link_label = '';"#, "study link", "had label spacing regularised", ex).await?;
+    execute_links_fb(r#"update ad.study_links set link_label = trim(regexp_replace(link_label, '[.,;:\-]+$', ''))
+        where link_label ~ '[.,;:\-]$';"#, "study link", "had trailing punctuation removed from labels", ex).await?;
+    execute_links_fb(r#"update ad.study_links set link_label = trim(replace(link_label, '"', ''))
+        where link_label like '%"%';"#, "study link", "had double quotes removed from labels", ex).await?;
+    execute_links_fb(r#"update ad.study_links set link_label = replace(link_label, '''', '’')
+        where link_label like '%''%';"#, "study link", "had apostrophes in labels replaced by right single quotes", ex).await?;
+    execute_links_fb(r#"update ad.study_links set link_label = null
+        where link_label = link_url;"#, "study link", "had labels that repeated the url removed", ex).await?;
+    info!("");
+
+    normalise_urls("study_links", "link_url", "study link", ex).await?;
+    execute_links_fb(&duplicate_urls_sql("study_links", "link_url"), "study link", "removed as duplicating an earlier link", ex).await?;
+    info!("");
+
+    ex.vacuum_table("study_links").await?;
+
+    Ok(())
+}
+
+
+pub async fn load_ipd_available_data (ex: &SqlExecutor) -> Result<(), AppError> {
+
+    // The IPD available are the documents provided to CTG with the study (ctgov.provided_documents),
+    // and the types of information that the study plans to share with its IPD
+    // (ctgov.ipd_information_types), the latter with the URL given for IPD sharing. The
+    // document or information type is classified into one of a standard set of ipd types.
+
+    let sql = format!(r#"insert into ad.study_ipd_available(sd_sid, ipd_type, ipd_url, ipd_comment)
+        select nct_id, {}, url,
+        trim(coalesce(document_type, '')||coalesce(' (dated '||document_date::varchar||')', ''))
+        from ctgov.provided_documents
+        order by nct_id, id;"#, ipd_type_sql("document_type"));
+    execute_links_fb(&sql, "ipd available", "added from ctgov provided documents", ex).await?;
+
+    let sql = format!(r#"insert into ad.study_ipd_available(sd_sid, ipd_type, ipd_url, ipd_comment)
+        select t.nct_id, {}, s.ipd_url, 'Planned to be shared with the IPD'
+        from ctgov.ipd_information_types t
+        inner join ctgov.studies s
+        on t.nct_id = s.nct_id
+        order by t.nct_id, t.id;"#, ipd_type_sql("t.name"));
+    execute_links_fb(&sql, "ipd available", "added from ctgov ipd information types", ex).await?;
+    info!("");
+
+    execute_links_fb("update ad.study_ipd_available set ipd_comment = null where ipd_comment = '';",
+                     "ipd available", "had empty comments removed", ex).await?;
+    normalise_urls("study_ipd_available", "ipd_url", "ipd available", ex).await?;
+    execute_links_fb(r#"delete from ad.study_ipd_available a
+        using ad.study_ipd_available b
+        where a.sd_sid = b.sd_sid
+        and a.ipd_type = b.ipd_type
+        and a.ipd_url is not distinct from b.ipd_url
+        and a.id > b.id;"#, "ipd available", "removed as duplicates", ex).await?;
+    info!("");
+
+    ex.vacuum_table("study_ipd_available").await?;
+
+    Ok(())
+}
+
+
+pub async fn normalise_urls (table: &str, column: &str, rec_type: &str, ex: &SqlExecutor) -> Result<(), AppError> {
+
+    // Spaces are removed, a missing scheme added to 'www.' addresses, the scheme put into
+    // lower case and trailing punctuation removed. Values that are still not web addresses
+    // are removed (set to null for ipd records, where the record is still of use).
+
+    let steps = [
+        (r#"regexp_replace({c}, '\s+', '', 'g')"#, r#"{c} ~ '\s'"#, "had spaces removed from urls"),
+        (r#"'https://'||{c}"#, r#"{c} ~* '^www\.'"#, "had 'https://' added to urls"),
+        (r#"lower(substring({c} from '^[A-Za-z]+://'))||substring({c} from '^[A-Za-z]+://(.*)$')"#,
+         r#"{c} ~ '^[A-Za-z]+://' and {c} !~ '^[a-z]+://'"#, "had url schemes put into lower case"),
+        (r#"regexp_replace({c}, '[.,;:]+$', '')"#, r#"{c} ~ '[.,;:]$'"#, "had trailing punctuation removed from urls"),
+    ];
+    for (new_value, condition, fb) in steps {
+        let sql = format!("update ad.{} set {} = {} where {};", table, column,
+                          new_value.replace("{c}", column), condition.replace("{c}", column));
+        execute_links_fb(&sql, rec_type, fb, ex).await?;
+    }
+
+    let sql = if table == "study_links" {
+        format!(r#"delete from ad.{} where {} is null or {} !~* '^(https?|ftp)://[^/]+\.';"#, table, column, column)
+    } else {
+        format!(r#"update ad.{} set {} = null where {} !~* '^(https?|ftp)://[^/]+\.';"#, table, column, column)
+    };
+    execute_links_fb(&sql, rec_type, "had missing or invalid urls removed", ex).await?;
+
+    Ok(())
+}
+
+
+async fn execute_links_fb (sql: &str, rec_type: &str, fb: &str, ex: &SqlExecutor) -> Result<(), AppError> {
+
+    let r = ex.execute(sql).await?.rows_affected();
+    info!("{} {} records {}", r, rec_type, fb);
+    Ok(())
+}
+
+
+pub fn ipd_type_sql (column: &str) -> String {
+
+    // Classifies both the document types of provided documents ('Study Protocol and
+    // Statistical Analysis Plan') and the ipd information type codes ('STUDY_PROTOCOL').
+
+    let c = format!("upper(replace({}, '_', ' '))", column);
+    format!(r#"case
+            when {c} ~ 'PROTOCOL' and {c} ~ '\mSAP\M|STATISTICAL' then 'Study protocol and statistical analysis plan'
+            when {c} ~ 'PROTOCOL' then 'Study protocol'
+            when {c} ~ '\mSAP\M|STATISTICAL' then 'Statistical analysis plan'
+            when {c} ~ '\mICF\M|CONSENT' then 'Informed consent form'
+            when {c} ~ '\mCSR\M|CLINICAL STUDY REPORT' then 'Clinical study report'
+            when {c} ~ 'ANALYTIC' then 'Analytic code'
+            when {c} ~ 'DATA ?SET|\mIPD\M|INDIVIDUAL PARTICIPANT' then 'Individual participant data set'
+            else 'Other'
+        end"#, c = c)
+}
+
+
+fn duplicate_urls_sql (table: &str, column: &str) -> String {
+
+    // Urls are compared ignoring the scheme, any initial 'www.' and any trailing '/'.
+
+    let comp = |alias: &str| format!(r#"lower(regexp_replace({}.{}, '^[a-z]+://(www\.)?|/$', '', 'g'))"#, alias, column);
+    format!(r#"delete from ad.{t} a
+    using ad.{t} b
+    where a.sd_sid = b.sd_sid
+    and {a} = {b}
+    and a.id > b.id;"#, t = table, a = comp("a"), b = comp("b"))
+}
+
+
+pub async fn build_ipd_available_table (ex: &SqlExecutor) -> Result<(), AppError> {  
 
     let sql = r#"SET client_min_messages TO WARNING; 
     DROP TABLE IF EXISTS ad.study_ipd_available;
@@ -86,7 +446,7 @@ pub async fn build_ipd_available_table (pool: &Pool<Postgres>) -> Result<(), App
     );
     CREATE INDEX study_ipd_available_sid ON ad.study_ipd_available(sd_sid);"#;
 
-	utils::execute_sql(sql, pool).await?;
+	ex.execute(sql).await?;
     info!("study ipd available table (re)created");
     
     Ok(())
@@ -1,16 +1,10 @@
-use sqlx::{Pool, Postgres, postgres::PgQueryResult};
+use crate::mdr::executor::SqlExecutor;
 use crate::AppError;
 use log::info;
 
+// Sql is executed by the common mdr executor, so that each step is recorded in the run log.
 
-pub async fn execute_sql(sql: &str, pool: &Pool<Postgres>) -> Result<PgQueryResult, AppError> {
-    
-    sqlx::raw_sql(&sql).execute(pool)
-        .await.map_err(|e| AppError::SqlxError(e, sql.to_string()))
-}
-
-
-pub async fn replace_in_fac_proc(s1: &str, s2: &str, sql_where: &str, rb: bool, fb: &str, pool: &Pool<Postgres>) -> Result<(), AppError> {
+pub async fn replace_in_fac_proc(s1: &str, s2: &str, sql_where: &str, rb: bool, fb: &str, ex: &SqlExecutor) -> Result<(), AppError> {
     
     let sql1 = format!("update ad.locs set fac_proc = replace(fac_proc, '{}', '{}') where ", s1, s2);
    
@@ -24,8 +18,7 @@ pub async fn replace_in_fac_proc(s1: &str, s2: &str, sql_where: &str, rb: bool,
     };
     let sql = sql1 + &sql2 + "; ";
 
-    let r = sqlx::raw_sql(&sql).execute(pool)
-        .await.map_err(|e| AppError::SqlxError(e, sql.to_string()))?.rows_affected();
+    let r = ex.execute(&sql).await?.rows_affected();
     
     // Feedback line provided (unless rb = false and fb = "")
 
@@ -55,7 +48,7 @@ pub async fn replace_in_fac_proc(s1: &str, s2: &str, sql_where: &str, rb: bool,
 }
 
 
-pub async fn remove_leading_char_in_fac_proc(s: &str, fb: bool, pool: &Pool<Postgres>) -> Result<(), AppError> {
+pub async fn remove_leading_char_in_fac_proc(s: &str, fb: bool, ex: &SqlExecutor) -> Result<(), AppError> {
 
     let mut sql = format!(r#"update ad.locs set fac_proc = substring(fac_proc, 2) where fac_proc ~ '^{}'; "#, s); 
     
@@ -63,8 +56,7 @@ pub async fn remove_leading_char_in_fac_proc(s: &str, fb: bool, pool: &Pool<Post
         sql = format!(r#"update ad.locs set fac_proc = substring(fac_proc, 2) where fac_proc ~ '^\.'; "#); 
     } 
 
-    let r = sqlx::raw_sql(&sql).execute(pool)
-           .await.map_err(|e| AppError::SqlxError(e, sql.to_string()))?.rows_affected();
+    let r = ex.execute(&sql).await?.rows_affected();
     
     if fb {
         if r > 1 {
@@ -79,7 +71,7 @@ pub async fn remove_leading_char_in_fac_proc(s: &str, fb: bool, pool: &Pool<Post
 }
 
 
-pub async fn remove_trailing_char_in_fac_proc(s: &str, fb: bool, pool: &Pool<Postgres>) -> Result<(), AppError> {
+pub async fn remove_trailing_char_in_fac_proc(s: &str, fb: bool, ex: &SqlExecutor) -> Result<(), AppError> {
 
     let mut sql = format!(r#"update ad.locs set fac_proc = substring(fac_proc, 1, length(fac_proc) - 1) where fac_proc ~ '{}$'; "#, s); 
     
@@ -87,8 +79,7 @@ pub async fn remove_trailing_char_in_fac_proc(s: &str, fb: bool, pool: &Pool<Pos
         sql = format!(r#"update ad.locs set fac_proc = substring(fac_proc, 1, length(fac_proc) - 1) where fac_proc ~ '\.$'; "#); 
     } 
 
-    let r = sqlx::raw_sql(&sql).execute(pool)
-           .await.map_err(|e| AppError::SqlxError(e, sql.to_string()))?.rows_affected();
+    let r = ex.execute(&sql).await?.rows_affected();
 
     if fb {
         if r > 1 {
@@ -102,17 +93,17 @@ pub async fn remove_trailing_char_in_fac_proc(s: &str, fb: bool, pool: &Pool<Pos
     Ok({})
 }
 
-pub async fn replace_list_items_with_target(target: &str, ws: &[&str], pool: &Pool<Postgres>) -> Result<(), AppError> {
+pub async fn replace_list_items_with_target(target: &str, ws: &[&str], ex: &SqlExecutor) -> Result<(), AppError> {
 
     for w in ws {
-        replace_in_fac_proc( w, target, "r", true, "", pool).await?; 
+        replace_in_fac_proc( w, target, "r", true, "", ex).await?; 
     }
 
     Ok({})
 }
 
 
-pub async fn replace_list_items_with_capitalised(ws: &[&str], pool: &Pool<Postgres>) -> Result<(), AppError> {
+pub async fn replace_list_items_with_capitalised(ws: &[&str], ex: &SqlExecutor) -> Result<(), AppError> {
 
     // Here the assumption is that the input words are all upper case, and we need to leave only the first character in this state.
     // Below is from https://stackoverflow.com/questions/38406793/why-is-capitalizing-the-first-letter-of-a-string-so-convoluted-in-rust
@@ -125,23 +116,23 @@ pub async fn replace_list_items_with_capitalised(ws: &[&str], pool: &Pool<Postgr
             None => String::new(),
             Some(f) => f.to_uppercase().collect::<String>() + c.as_str(),
         };
-        replace_in_fac_proc( w, &wcap, "r", true, "", pool).await?; 
+        replace_in_fac_proc( w, &wcap, "r", true, "", ex).await?; 
     }
 
     Ok({})
 }
 
-pub async fn replace_list_items_with_lower_case(ws: &[&str], pool: &Pool<Postgres>) -> Result<(), AppError> {
+pub async fn replace_list_items_with_lower_case(ws: &[&str], ex: &SqlExecutor) -> Result<(), AppError> {
 
     for w in ws {
         let wlower = w.to_lowercase();
-        replace_in_fac_proc( w, &wlower, "r", true, "", pool).await?; 
+        replace_in_fac_proc( w, &wlower, "r", true, "", ex).await?; 
     }
 
     Ok({})
 }
 
-pub async fn add_zzz_prefix_to_list_items(ws: &[&str], pool: &Pool<Postgres>) -> Result<(), AppError> {
+pub async fn add_zzz_prefix_to_list_items(ws: &[&str], ex: &SqlExecutor) -> Result<(), AppError> {
 
 
     let mut res = "".to_string();
@@ -153,8 +144,7 @@ pub async fn add_zzz_prefix_to_list_items(ws: &[&str], pool: &Pool<Postgres>) ->
         else {
             format!("update ad.locs set fac_proc = 'ZZZ'||fac_proc where fac_proc ~ '^{}'", w)
         };
-        let r = sqlx::raw_sql(&sql).execute(pool)
-        .await.map_err(|e| AppError::SqlxError(e, sql.to_string()))?.rows_affected();
+        let r = ex.execute(&sql).await?.rows_affected();
         res = res + &format!("{} ({}), ", w, r);
     }
 
@@ -164,14 +154,13 @@ pub async fn add_zzz_prefix_to_list_items(ws: &[&str], pool: &Pool<Postgres>) ->
 }
 
 
-pub async fn remove_regexp_from_fac_proc(ss: &str, sql_where: &str, fb: &str, pool: &Pool<Postgres>) -> Result<(), AppError> {
+pub async fn remove_regexp_from_fac_proc(ss: &str, sql_where: &str, fb: &str, ex: &SqlExecutor) -> Result<(), AppError> {
     
     let sql = format!(r#"update ad.locs c
 	set fac_proc = trim(replace(fac_proc, substring (fac_proc from '{}'), ''))
 	where fac_proc ~ '{}'"#, ss, sql_where);
     
-    let r = sqlx::raw_sql(&sql).execute(pool)
-        .await.map_err(|e| AppError::SqlxError(e, sql.to_string()))?.rows_affected();
+    let r = ex.execute(&sql).await?.rows_affected();
     
     // Feedback line provided (unless fb = "")
 
@@ -182,30 +171,3 @@ pub async fn remove_regexp_from_fac_proc(ss: &str, sql_where: &str, fb: &str, po
               
     Ok(())
 }
-
-pub async fn execute_temp_phased_transfer(sql: &str, max_id: u64, chunk_size: u64, sql_linker: &str, rec_type: &str, pool: &Pool<Postgres>) -> Result<u64, AppError> {
-    
-    let mut rec_num: u64 = 0;
-    let mut start_num: u64 = 0;
-
-    while start_num <= max_id {
-
-        let end_num = start_num + chunk_size;
-        let chunk_sql = format!("c.sd_sid >= 'NCT{:0>8}' and c.sd_sid < 'NCT{:0>8}';", start_num, end_num);
-        let chsql = sql.to_string() + sql_linker + &chunk_sql;
-        
-        let res = sqlx::raw_sql(&chsql).execute(pool)
-            .await.map_err(|e| AppError::SqlxError(e, chsql.to_string()))?;
-        let recs = res.rows_affected();
-        rec_num += recs;
-        info!("{} {} copied, {}", recs, rec_type, chunk_sql);
-
-        start_num = end_num;
-    }
-
-    info!("{} {} transferred in total", rec_num, rec_type);
-    info!("");
-
-    Ok(rec_num)
-}
-
@@ -1,15 +1,15 @@
 mod locs_utils;
 mod locs_proc;
 
-use super::utils::{execute_sql, execute_phased_transfer, vacuum_table};
-use locs_utils::{execute_temp_phased_transfer};
+use super::utils::{save_checkpoint, restore_checkpoint};
+use super::stages::{self, StagePlan};
 
-use sqlx::{Pool, Postgres};
+use super::executor::SqlExecutor;
 use crate::AppError;
 use log::info;
 
 
-pub async fn build_locations_table (pool: &Pool<Postgres>) -> Result<(), AppError> {  
+pub async fn build_locations_table (ex: &SqlExecutor) -> Result<(), AppError> {  
 
     let sql = r#"SET client_min_messages TO WARNING; 
     DROP TABLE IF EXISTS ad.study_locations;
@@ -31,7 +31,7 @@ pub async fn build_locations_table (pool: &Pool<Postgres>) -> Result<(), AppErro
     );
     CREATE INDEX study_locations_sid ON ad.study_locations(sd_sid);"#;
 
-	execute_sql(sql, pool).await?;
+	ex.execute(sql).await?;
     info!("study locations table (re)created");
     
     Ok(())
@@ -39,7 +39,7 @@ pub async fn build_locations_table (pool: &Pool<Postgres>) -> Result<(), AppErro
 }
 
 
-pub async fn build_countries_table (pool: &Pool<Postgres>) -> Result<(), AppError> {  
+pub async fn build_countries_table (ex: &SqlExecutor) -> Result<(), AppError> {  
 
     let sql = r#"SET client_min_messages TO WARNING; 
     DROP TABLE IF EXISTS ad.study_countries;
@@ -54,14 +54,14 @@ pub async fn build_countries_table (pool: &Pool<Postgres>) -> Result<(), AppErro
     );
     CREATE INDEX study_countries_sid ON ad.study_countries(sd_sid);"#;
 
-	execute_sql(sql, pool).await?;
+	ex.execute(sql).await?;
     info!("study countries table (re)created");
     
     Ok(())
 
 }
 
-pub async fn build_locs_table (pool: &Pool<Postgres>) -> Result<(), AppError> {  
+pub async fn build_locs_table (ex: &SqlExecutor) -> Result<(), AppError> {  
 
     /*  
     fac_orig - the original facility name in the source data
@@ -92,20 +92,25 @@ pub async fn build_locs_table (pool: &Pool<Postgres>) -> Result<(), AppError> {
     );
     CREATE INDEX locs_fac_proc ON ad.locs(fac_proc);"#;
 
-	execute_sql(sql, pool).await?;
+	ex.execute(sql).await?;
     info!("study locs table (re)created");
     
     Ok(())
 
 }
 
-pub async fn load_facs_data (processing: &str, max_id: u64, pool: &Pool<Postgres>) -> Result<(), AppError> {  
+pub async fn load_facs_data (plan: &StagePlan, data_date: &str, max_id: u64, ex: &SqlExecutor) -> Result<(), AppError> {  
 
     let chunk_size = 1000000;
 
-    if processing == "full" {
+    // The initial processing can be replaced by restoring the table saved at the
+    // 'locs_base' checkpoint, when developing the later facility name steps.
 
-        build_locs_table(pool).await?;
+    let base_tables = stages::checkpoint_tables("locs_base");
+
+    if !plan.restores("locs_base") {
+
+        build_locs_table(ex).await?;
 
         // Insert the loc data from the ctgov.facilties table.
         
@@ -115,134 +120,60 @@ pub async fn load_facs_data (processing: &str, max_id: u64, pool: &Pool<Postgres
                                 from ctgov.facilities c
                                 where (status <> 'WITHDRAWN' or status is null) "#;
 
-        execute_phased_transfer(sql, max_id, chunk_size, " and ", "nct ids", "ad.locs", pool).await?;
+        ex.phased_transfer(sql, max_id, chunk_size, " and ", "nct ids", "ad.locs").await?;
 
         locs_proc::do_section_header().await?;
-        locs_proc::regularise_brackets(pool).await?;
-        locs_proc::remove_enclosing_brackets(pool).await?;
-        locs_proc::repair_non_ascii_1(pool).await?;
-        locs_proc::repair_non_ascii_2(pool).await?;
-        locs_proc::repair_non_ascii_3(pool).await?;
-
-        locs_proc::remove_double_quotes(pool).await?;
-        locs_proc::remove_single_quotes(pool).await?;
-        locs_proc::remove_double_commas(pool).await?;
-        locs_proc::process_apostrophes(pool).await?;
-        locs_proc::process_upper_ticks(pool).await?;
+        locs_proc::regularise_brackets(ex).await?;
+        locs_proc::remove_enclosing_brackets(ex).await?;
+        locs_proc::repair_non_ascii_1(ex).await?;
+        locs_proc::repair_non_ascii_2(ex).await?;
+        locs_proc::repair_non_ascii_3(ex).await?;
+
+        locs_proc::remove_double_quotes(ex).await?;
+        locs_proc::remove_single_quotes(ex).await?;
+        locs_proc::remove_double_commas(ex).await?;
+        locs_proc::process_apostrophes(ex).await?;
+        locs_proc::process_upper_ticks(ex).await?;
         
-        locs_proc::remove_leading_trailing_odd_chars(pool).await?;
-        locs_proc::remove_underscores(pool).await?;
-        locs_proc::improve_comma_spacing(pool).await?;
-        locs_proc::improve_bracket_spacing(pool).await?;
-        locs_proc::remove_initial_numbering(pool).await?;
+        locs_proc::remove_leading_trailing_odd_chars(ex).await?;
+        locs_proc::remove_underscores(ex).await?;
+        locs_proc::improve_comma_spacing(ex).await?;
+        locs_proc::improve_bracket_spacing(ex).await?;
+        locs_proc::remove_initial_numbering(ex).await?;
 
-        locs_proc::regularise_word_site(pool).await?;
-        locs_proc::correct_place_names(pool).await?;
-        locs_proc::correct_lower_case_beginnings(pool).await?;
+        locs_proc::regularise_word_site(ex).await?;
+        locs_proc::correct_place_names(ex).await?;
+        locs_proc::correct_lower_case_beginnings(ex).await?;
 
-        vacuum_table("locs", pool).await?;
+        ex.vacuum_table("locs").await?;
 
-        park_spare_locs_data(max_id, chunk_size, pool).await?;
+        if plan.saves("locs_base") {
+            save_checkpoint("locs_base", &base_tables, data_date, ex).await?;
+        }
     }
     else {
-        reuse_spare_locs_data(max_id, chunk_size, pool).await?;
+        restore_checkpoint("locs_base", &base_tables, ex).await?;
+    }
                
-        locs_proc::regularise_word_research(pool).await?;
-        locs_proc::regularise_word_investigation(pool).await?;
-        locs_proc::regularise_word_university(pool).await?;
-        locs_proc::regularise_word_others(pool).await?;
+    locs_proc::regularise_word_research(ex).await?;
+    locs_proc::regularise_word_investigation(ex).await?;
+    locs_proc::regularise_word_university(ex).await?;
+    locs_proc::regularise_word_others(ex).await?;
 
-        locs_proc::remove_upper_case_institut(pool).await?;
+    locs_proc::remove_upper_case_institut(ex).await?;
 
-        vacuum_table("locs", pool).await?;
-    }
+    ex.vacuum_table("locs").await?;
             
     //let _sql = r#"drop table if exists ad.temp_idents;"#;
-    // execute_sql(sql, pool).await?;
+    // ex.execute(sql).await?;
 
-    //vacuum_table("study_locations", pool).await?;
+    //ex.vacuum_table("study_locations").await?;
 
     Ok(())
 
 }
 
 
-async fn park_spare_locs_data(max_id: u64, chunk_size: u64, pool: &Pool<Postgres>) -> Result<(), AppError> { 
- 
-    // copy ad.locs to ad.spare_locs
-
-    let sql = r#"SET client_min_messages TO WARNING; 
-    drop table if exists ad.spare_locs;
-    CREATE TABLE ad.spare_locs(
-      id                     INT             PRIMARY KEY GENERATED BY DEFAULT AS IDENTITY (start with 10000001 increment by 1)
-    , sd_sid                 VARCHAR         NOT NULL
-    , fac_orig               VARCHAR         NULL
-    , fac_proc               VARCHAR         NULL
-    , fac_code               VARCHAR         NULL
-    , fac_name               VARCHAR         NULL
-    , fac_org                VARCHAR         NULL
-    , fac_spec               VARCHAR         NULL
-    , fac_echo               VARCHAR         NULL
-    , for_match              BOOL            NULL
-    , city                   VARCHAR         NULL
-    , state                  VARCHAR         NULL
-    , country                VARCHAR         NULL
-    , status                 VARCHAR         NULL
-    );
-    CREATE INDEX spare_locs_fac_proc ON ad.spare_locs(fac_proc);"#;
-    execute_sql(sql, pool).await?;
-
-    let sql = r#"insert into ad.spare_locs (id, sd_sid,fac_orig, fac_proc, fac_code, 
-    fac_name, fac_org, fac_spec, fac_echo, for_match, city, state, country, status)
-    select id, sd_sid,fac_orig, fac_proc, fac_code, 
-    fac_name, fac_org, fac_spec, fac_echo, for_match, city, state, country, status 
-    from ad.locs c "#;
-    execute_temp_phased_transfer(sql, max_id, chunk_size, " where ", "ad.locs", pool).await?;
-    
-    let sql = r#"SET client_min_messages TO NOTICE;"#;
-    execute_sql(sql, pool).await?;
-    
-    Ok(())
-
-}
-
-
-async fn reuse_spare_locs_data(max_id: u64, chunk_size: u64, pool: &Pool<Postgres>) -> Result<(), AppError> { 
-
-    // Load the data from ad.spare_locs to ad.locs
-
-    let sql = r#"SET client_min_messages TO WARNING; 
-	drop table if exists ad.locs;
-	CREATE TABLE ad.locs(
-      id                     INT             PRIMARY KEY GENERATED BY DEFAULT AS IDENTITY (start with 10000001 increment by 1)
-    , sd_sid                 VARCHAR         NOT NULL
-    , fac_orig               VARCHAR         NULL
-    , fac_proc               VARCHAR         NULL
-    , fac_code               VARCHAR         NULL
-    , fac_name               VARCHAR         NULL
-    , fac_org                VARCHAR         NULL
-    , fac_spec               VARCHAR         NULL
-    , fac_echo               VARCHAR         NULL
-    , for_match              BOOL            NULL
-    , city                   VARCHAR         NULL
-    , state                  VARCHAR         NULL
-    , country                VARCHAR         NULL
-    , status                 VARCHAR         NULL
-    );
-    CREATE INDEX locs_fac_proc ON ad.locs(fac_proc);"#;
-    execute_sql(sql, pool).await?;
-
-    let sql = r#"insert into ad.locs (id, sd_sid,fac_orig, fac_proc, fac_code, 
-    fac_name, fac_org, fac_spec, fac_echo, for_match, city, state, country, status)
-    select id, sd_sid,fac_orig, fac_proc, fac_code, 
-    fac_name, fac_org, fac_spec, fac_echo, for_match, city, state, country, status
-    from ad.spare_locs c "#;
-    execute_temp_phased_transfer(sql, max_id, chunk_size, " where ", "ad.spare_locs", pool).await?;
-
-    Ok(())
-}
-
-
 /*
 
 insert into ad.study_locations(sd_sid, facility, city_name, 
@@ -0,0 +1,186 @@
+/***************************************************************************
+ * Pre-flight check of the restored ctgov schema. The mdr stages assume that
+ * specific ctgov tables and columns exist, but AACT can change its schema
+ * without notice. The columns read by each stage are listed, with a broad
+ * type category, in the manifest (ctgov_manifest.csv, compiled into the
+ * program). Before any stage is run, the columns required by the planned
+ * stages are compared with those in information_schema, and all missing
+ * columns or changed types are reported together.
+ ***************************************************************************/
+
+use super::stages::StagePlan;
+use crate::AppError;
+use serde::Deserialize;
+use sqlx::{Pool, Postgres};
+use std::collections::HashMap;
+use log::info;
+
+static MANIFEST: &str = include_str!("ctgov_manifest.csv");
+
+#[derive(Debug, Deserialize)]
+pub struct ManifestEntry {
+    pub stage: String,
+    pub table: String,
+    pub column: String,
+    #[serde(rename = "type")]
+    pub type_category: String,
+}
+
+
+pub fn read_manifest() -> Result<Vec<ManifestEntry>, AppError> {
+
+    let mut rdr = csv::Reader::from_reader(MANIFEST.as_bytes());
+    let mut entries = Vec::new();
+    for result in rdr.deserialize() {
+        entries.push(result?);
+    }
+    Ok(entries)
+}
+
+
+pub async fn check_ctgov_schema(plan: &StagePlan, pool: &Pool<Postgres>) -> Result<(), AppError> {
+
+    let stage_names = plan.stage_names();
+    let required: Vec<ManifestEntry> = read_manifest()?.into_iter()
+        .filter(|e| stage_names.contains(&e.stage.as_str()))
+        .collect();
+    if required.is_empty() {
+        return Ok(());
+    }
+
+    let sql = r#"select table_name::varchar, column_name::varchar, data_type::varchar
+                 from information_schema.columns where table_schema = 'ctgov'"#;
+    let rows: Vec<(String, String, String)> = sqlx::query_as(sql).fetch_all(pool)
+        .await.map_err(|e| AppError::SqlxError(e, sql.to_string()))?;
+    let actual: HashMap<(String, String), String> = rows.into_iter()
+        .map(|(t, c, d)| ((t, c), d))
+        .collect();
+
+    let problems = compare_with_manifest(&required, &actual);
+    if !problems.is_empty() {
+        return Result::Err(AppError::CtgovSchemaError(problems));
+    }
+
+    info!("ctgov schema checked against manifest: {} required columns present", required.len());
+    Ok(())
+}
+
+
+pub fn compare_with_manifest(required: &[ManifestEntry], actual: &HashMap<(String, String), String>) -> Vec<String> {
+
+    // Returns one line per problem, each table or column reported only once,
+    // with the stages that need it.
+
+    let mut problems: Vec<String> = Vec::new();
+    let mut reported: Vec<(String, String)> = Vec::new();
+
+    for entry in required.iter() {
+        let key = (entry.table.clone(), entry.column.clone());
+        if reported.contains(&key) {
+            continue;
+        }
+        let stages = stage_list(required.iter()
+                    .filter(|e| e.table == entry.table && e.column == entry.column));
+
+        if !actual.keys().any(|(t, _)| *t == entry.table) {
+            let table_key = (entry.table.clone(), "".to_string());
+            if !reported.contains(&table_key) {
+                problems.push(format!("table ctgov.{} is missing (needed by {})", entry.table,
+                            stage_list(required.iter().filter(|e| e.table == entry.table))));
+                reported.push(table_key);
+            }
+        }
+        else {
+            match actual.get(&key) {
+                None => problems.push(format!("column ctgov.{}.{} is missing (needed by {})",
+                            entry.table, entry.column, stages)),
+                Some(data_type) if type_category(data_type) != entry.type_category =>
+                        problems.push(format!("column ctgov.{}.{} is {}, expected {} (needed by {})",
+                            entry.table, entry.column, data_type, entry.type_category, stages)),
+                _ => {},
+            }
+        }
+        reported.push(key);
+    }
+    problems
+}
+
+
+fn stage_list<'a>(entries: impl Iterator<Item = &'a ManifestEntry>) -> String {
+    let mut stages: Vec<&str> = Vec::new();
+    for e in entries {
+        if !stages.contains(&e.stage.as_str()) {
+            stages.push(&e.stage);
+        }
+    }
+    stages.join(", ")
+}
+
+
+pub fn type_category(data_type: &str) -> &str {
+
+    // Broad categories, so that e.g. a change between varchar and text is not reported.
+
+    match data_type {
+        "character varying" | "text" | "character" => "text",
+        "smallint" | "integer" | "bigint" | "numeric" | "real" | "double precision" => "number",
+        "date" | "timestamp without time zone" | "timestamp with time zone" => "date",
+        "boolean" => "boolean",
+        _ => data_type,
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mdr::stages;
+
+    fn entry(stage: &str, table: &str, column: &str, type_category: &str) -> ManifestEntry {
+        ManifestEntry { stage: stage.to_string(), table: table.to_string(),
+                        column: column.to_string(), type_category: type_category.to_string() }
+    }
+
+    #[test]
+    fn check_manifest_is_consistent_with_registry() {
+        let entries = read_manifest().unwrap();
+        assert!(!entries.is_empty());
+        for e in entries.iter() {
+            let stage = stages::get_stage(&e.stage);
+            assert!(stage.is_some(), "unknown stage {} in manifest", e.stage);
+            let table = format!("ctgov.{}", e.table);
+            assert!(stage.unwrap().reads.contains(&table.as_str()), "{} not listed as read by {}", table, e.stage);
+            assert!(["text", "number", "date", "boolean"].contains(&e.type_category.as_str()));
+        }
+    }
+
+    #[test]
+    fn check_comparison_lists_each_problem() {
+        let required = vec![
+            entry("studies", "studies", "nct_id", "text"),
+            entry("titles", "studies", "nct_id", "text"),
+            entry("studies", "studies", "enrollment", "number"),
+            entry("studies", "studies", "start_month_year", "text"),
+            entry("locations", "facilities", "name", "text"),
+            entry("locations", "facilities", "status", "text"),
+        ];
+        let mut actual = HashMap::new();
+        actual.insert(("studies".to_string(), "nct_id".to_string()), "character varying".to_string());
+        actual.insert(("studies".to_string(), "enrollment".to_string()), "character varying".to_string());
+
+        let problems = compare_with_manifest(&required, &actual);
+        assert_eq!(problems, vec![
+            "column ctgov.studies.enrollment is character varying, expected number (needed by studies)",
+            "column ctgov.studies.start_month_year is missing (needed by studies)",
+            "table ctgov.facilities is missing (needed by locations)",
+        ]);
+    }
+
+    #[test]
+    fn check_type_categories() {
+        assert_eq!(type_category("text"), "text");
+        assert_eq!(type_category("integer"), "number");
+        assert_eq!(type_category("timestamp without time zone"), "date");
+        assert_eq!(type_category("jsonb"), "jsonb");
+    }
+}
@@ -6,188 +6,313 @@ mod keywords;
 mod links;
 mod dataobjs;
 mod utils;
-
+pub mod executor;
+mod parallel;
+mod run_log;
+pub mod dry_run;
+pub mod stages;
+pub mod restore;
+mod manifest;
+
+use stages::{Stage, StagePlan};
+use executor::{SqlExecutor, TransactionMode};
+use parallel::{JobLimit, TaskSet};
 use sqlx::{Pool, Postgres};
 use crate::AppError;
 use log::info;
 
-pub async fn do_mdr_import(data_date: &str, pool: &Pool<Postgres>) -> Result<(), AppError> {  
+pub async fn do_mdr_import(data_date: &str, plan: &StagePlan, resume: bool, transaction_mode: TransactionMode, 
+                           jobs: usize, pool: &Pool<Postgres>) -> Result<(), AppError> {  
 
     // Different portions of the import process can be turned on and off.
-    // In normal use all data would be imported, but during development it is 
-    // very useful to 'turn off' functionality that would simply repeat processing 
-    // already successfully completed and import of data already present in the DB.
-
-    // The parts of the mdr import process to be implemented are stored iu 
-    // the 'mdr_params' string. If that string is empty - the normal default - all 
-    // parts of the import process are implemented. 
-    // If it contains any value then the letters are used to set specific boolean variables 
-    // that are used to control the execution of specific parts...
-  
-    let mut simplify_tables = true;
-    let mut import_studies = true;
-
-    let mut import_titles = true;
-    let mut import_idents = true;
-
-    let mut import_locations = true;
-    let mut import_countries = true;
-
-    let mut import_orgs = true;
-    let mut import_people = true;
-
-    let mut import_features = true;
-    let mut import_topics = true;
-    let mut import_conditions = true;
-
-    let mut import_rels = true;
-    let mut import_refs = true;
-    let mut import_links = true;
-    let mut import_ipd_avail = true;    
-
-    let mut import_data_objects = true;
-    let mut import_datasets = true;
-    let mut import_obj_instances = true;
-    let mut import_obj_titles = true;
-    let mut import_obj_dates = true;
-
-
-    // To be added to program parameters...
-    // Current value...
-    //******************************************
-    let mdr_params = "l";
-    //******************************************
-
-    if mdr_params != "" {
-        simplify_tables = (mdr_params).contains("m");
-        import_studies = (mdr_params).contains("d");
-        import_titles = (mdr_params).contains("t");
-        import_idents = (mdr_params).contains("n");
-
-        import_locations = (mdr_params).contains("l");
-        import_countries = (mdr_params).contains("c");
-
-        import_orgs = (mdr_params).contains("g");
-        import_people = (mdr_params).contains("p");
-
-        import_rels = (mdr_params).contains("q");
-        import_refs = (mdr_params).contains("r");
-        import_links = (mdr_params).contains("s");
-        import_ipd_avail = (mdr_params).contains("t");
-
-        import_features = (mdr_params).contains("x");
-        import_topics = (mdr_params).contains("y");
-        import_conditions = (mdr_params).contains("z");
-
-        import_data_objects = (mdr_params).contains("a");
-        import_datasets = (mdr_params).contains("e");
-        import_obj_instances = (mdr_params).contains("i");
-        import_obj_titles = (mdr_params).contains("o");
-        import_obj_dates = (mdr_params).contains("u");
-    }
-        
-    let max_id = get_max_nct_id(pool).await?;
+    // In normal use all data would be imported, but during development, or when 
+    // a single stage needs to be re-run, it is very useful to 'turn off' functionality 
+    // that would simply repeat processing already successfully completed.
 
-    if simplify_tables {
+    // The stages to be run have been resolved against the stage registry during set up.
+    // Any prerequisite stages not being run must have left their tables in place.
 
-        // Simplify the aact tables after initial restore of postgres.dmp file
-        // Remove tables not required (mostly from results details section).
-        // Then clarify the very big studies table by dropping unused fields
-    
-        utils::execute_sql(drop_tables_a_sql(), pool).await?;
-        utils::execute_sql(drop_tables_b_sql(), pool).await?;
+    // All sql is sent to the database through an executor (see executor.rs), one
+    // for each stage, which also runs the stage in a transaction, if so requested.
+    // Up to the number of jobs statements can be run at the same time, though in 
+    // a dry run everything is run in order, so that the file is always the same.
 
-        utils::execute_sql(drop_columns_a_sql(), pool).await?;
-        utils::execute_sql(drop_columns_b_sql(), pool).await?;
-    }
+    let limit = JobLimit::new(if dry_run::is_active() { 1 } else { jobs });
+    let ex = SqlExecutor::new(pool);
 
-    if import_studies {
-        studies::build_studies_table(pool).await?;
-        studies::load_studies_data(data_date, max_id, pool).await?;
-    }
+    // In a dry run the sql is only written to a file (see dry_run.rs), so the 
+    // checks below, and the run log, which all need the database, are not used.
 
-    if import_titles {
-        idents::build_titles_table(pool).await?;
-        idents::load_titles_data (max_id, pool).await?;
+    let max_id = if dry_run::is_active() {
+        dry_run::DRY_RUN_MAX_ID
     }
-
-    if import_idents {
-        idents::build_idents_table(pool).await?;
-        let idents_processing = "full";
-        idents::load_idents_data (idents_processing, max_id, pool).await?;
+    else {
+
+        // The ctgov tables and columns read by the stages must be as expected.
+
+        manifest::check_ctgov_schema(plan, pool).await?;
+        stages::check_assumed_stages(plan, pool).await?;
+
+        // Each stage, and each sql step within it, is recorded in the run log. 
+        // If resuming, stages and steps already completed for this data are skipped.
+
+        run_log::start_run(data_date, resume, pool).await?;
+        if !plan.save_points.is_empty() {
+            utils::create_checkpoints_table(&ex).await?;
+        }
+        get_max_nct_id(pool).await?
+    };
+
+    // Stages are started in run order, as soon as there is a job free and no
+    // unfinished earlier stage that they must follow (see stages::must_follow).
+    // With a single job this is simply one stage after another. If a stage fails 
+    // no further stages are started, but those already running are allowed to finish.
+
+    let to_run: Vec<&Stage> = plan.stages.iter().copied()
+                    .filter(|s| s.name != "restore")   // already run, before the pool was created (see lib::run)
+                    .collect();
+    let mut started = vec![false; to_run.len()];
+    let mut finished = vec![false; to_run.len()];
+    let mut running = TaskSet::new();
+    let mut failure = None;
+
+    loop {
+        for i in 0..to_run.len() {
+            if failure.is_some() || running.len() >= limit.jobs() {
+                break;
+            }
+            if !started[i] && !(0..i).any(|j| !finished[j] && stages::must_follow(to_run[i], to_run[j])) {
+                started[i] = true;
+                let stage = to_run[i];
+                let ex = SqlExecutor::new(pool).with_transaction_mode(transaction_mode).with_job_limit(&limit);
+                running.push(i, async move { import_stage(stage, data_date, plan, max_id, ex).await });
+            }
+        }
+        let Some((i, res)) = running.next().await else {
+            break;
+        };
+        finished[i] = true;
+        if let Err(e) = res {
+            if !running.is_empty() {
+                info!("mdr stage '{}' failed, waiting for the stages still running to finish", to_run[i].name);
+            }
+            failure.get_or_insert(e);
+        }
     }
-
-    if import_locations {
-        locs::build_locations_table(pool).await?;
-        let locs_processing = "reuse";
-        locs::load_facs_data (locs_processing, max_id, pool).await?;
+    if let Some(e) = failure {
+        return Err(e);
     }
 
-    if import_countries {
-        locs::build_countries_table(pool).await?;
-    }
+    run_log::end_run();
 
-    if import_orgs {
-        peoporgs::build_orgs_table(pool).await?;
-    }
+    ex.execute(set_messages_to_notice()).await?;
+    info!("");
+   
+    Ok(())
 
-    if import_people {
-        peoporgs::build_people_table(pool).await?;
-    }
+}
 
-    if import_features {
-        keywords::build_features_table(pool).await?;
-    }
 
-    if import_topics {
-        keywords::build_topics_table(pool).await?;
-    }
-        
-    if import_conditions {
-        keywords::build_conditions_table(pool).await?;
+async fn import_stage(stage: &Stage, data_date: &str, plan: &StagePlan, max_id: u64, ex: SqlExecutor) -> Result<(), AppError> {
+
+    // A stage can also be replaced by restoring the tables saved at its end, and  
+    // those tables saved, using the checkpoint with the stage's name.
+
+    if ex.start_stage(stage.name).await? {
+        let tables = stages::checkpoint_tables(stage.name);
+        let outcome = if plan.restores(stage.name) {
+            info!("restoring mdr stage '{}' from its checkpoint", stage.name);
+            utils::restore_checkpoint(stage.name, &tables, &ex).await
+        }
+        else {
+            info!("running mdr stage '{}'", stage.name);
+            match run_stage(stage.name, data_date, plan, max_id, &ex).await {
+                Ok(()) if plan.saves(stage.name) => utils::save_checkpoint(stage.name, &tables, data_date, &ex).await,
+                res => res,
+            }
+        };
+        ex.end_stage(outcome).await?;
     }
+    Ok(())
+}
 
-    if import_rels {
-        links::build_rels_table(pool).await?;
-    }
 
-    if import_refs {
-        links::build_refs_table(pool).await?;
-    }
-    
-    if import_links {
-        links::build_links_table(pool).await?;
-    }
+async fn run_stage(stage: &str, data_date: &str, plan: &StagePlan, max_id: u64, ex: &SqlExecutor) -> Result<(), AppError> {  
+
+    match stage {
+
+        "restore" => {},    // run before the pool is created, in lib::run
+
+        "simplify" => {
+
+            // Simplify the aact tables after initial restore of postgres.dmp file
+            // Remove tables not required (mostly from results details section).
+            // Then clarify the very big studies table by dropping unused fields
+            // The MeSH terms that AACT lists for each study's conditions and interventions
+            // are kept first (in ctgov.mesh_conditions and ctgov.mesh_interventions), as
+            // the browse_conditions and browse_interventions tables are among those dropped.
         
-    if import_ipd_avail {
-        links::build_ipd_available_table(pool).await?;
+            ex.execute(keep_mesh_terms_sql()).await?;
+            ex.execute(drop_tables_a_sql()).await?;
+            ex.execute(drop_tables_b_sql()).await?;
+
+            ex.execute(drop_columns_a_sql()).await?;
+            ex.execute(drop_columns_b_sql()).await?;
+        },
+
+        "studies" => {
+            studies::build_studies_table(ex).await?;
+            studies::load_studies_data(data_date, max_id, ex).await?;
+        },
+
+        "titles" => {
+            idents::build_titles_table(ex).await?;
+            idents::load_titles_data (max_id, ex).await?;
+        },
+
+        "idents" => {
+            idents::build_idents_table(ex).await?;
+            idents::load_idents_data (plan, data_date, max_id, ex).await?;
+        },
+
+        "locations" => {
+            locs::build_locations_table(ex).await?;
+            locs::load_facs_data (plan, data_date, max_id, ex).await?;
+        },
+
+        "countries" => locs::build_countries_table(ex).await?,
+        "orgs" => {
+            peoporgs::build_orgs_table(ex).await?;
+            peoporgs::load_orgs_data(ex).await?;
+        },
+        "people" => {
+            peoporgs::build_people_table(ex).await?;
+            peoporgs::load_people_data(ex).await?;
+        },
+
+        "features" => {
+            keywords::build_features_table(ex).await?;
+            keywords::load_features_data(ex).await?;
+        },
+        "topics" => {
+            keywords::build_topics_table(ex).await?;
+            keywords::load_topics_data(ex).await?;
+        },
+        "conditions" => {
+            keywords::build_conditions_table(ex).await?;
+            keywords::load_conditions_data(ex).await?;
+        },
+
+        "relationships" => {
+            links::build_rels_table(ex).await?;
+            links::load_rels_data(ex).await?;
+        },
+        "references" => {
+            links::build_refs_table(ex).await?;
+            links::load_refs_data(ex).await?;
+        },
+        "links" => {
+            links::build_links_table(ex).await?;
+            links::load_links_data(ex).await?;
+        },
+        "ipd_available" => {
+            links::build_ipd_available_table(ex).await?;
+            links::load_ipd_available_data(ex).await?;
+        },
+
+        "data_objects" => {
+            dataobjs::build_data_objects_table(ex).await?;
+            dataobjs::load_data_objects_data(ex).await?;
+        },
+        "datasets" => {
+            dataobjs::build_datasets_table(ex).await?;
+            dataobjs::load_datasets_data(ex).await?;
+        },
+        "obj_instances" => {
+            dataobjs::build_obj_instances_table(ex).await?;
+            dataobjs::load_obj_instances_data(ex).await?;
+        },
+        "obj_titles" => {
+            dataobjs::build_obj_titles_table(ex).await?;
+            dataobjs::load_obj_titles_data(ex).await?;
+        },
+        "obj_dates" => {
+            dataobjs::build_obj_dates_table(ex).await?;
+            dataobjs::load_obj_dates_data(ex).await?;
+        },
+
+        _ => return Result::Err(AppError::StageSelectionError(
+                format!("No implementation found for mdr stage '{}'", stage),
+                "The stage is in the stage registry but not in mdr::run_stage".to_string())),
     }
 
-    if import_data_objects {
-        dataobjs::build_data_objects_table(pool).await?;
-    }
+    Ok(())
+}
 
-    if import_datasets {
-        dataobjs::build_datasets_table(pool).await?;
-    }
 
-    if import_obj_instances {
-        dataobjs::build_obj_instances_table(pool).await?;
-    }
+pub async fn list_checkpoints(pool: &Pool<Postgres>) -> Result<Vec<String>, AppError> {  
 
-    if import_obj_titles {
-        dataobjs::build_obj_titles_table(pool).await?;
-    }
+    // Returns the lines of a readable list of the saved checkpoints.
 
-    if import_obj_dates {
-        dataobjs::build_obj_dates_table(pool).await?;
+    let checkpoints = utils::get_checkpoints(&SqlExecutor::new(pool)).await?;
+    let mut lines = vec![format!("{} saved mdr checkpoint(s)", checkpoints.len())];
+    for c in checkpoints.iter() {
+        lines.push(format!("{:<20} saved {} (data date {}): {}", c.name, c.saved_on, c.data_date, c.tables));
     }
+    Ok(lines)
+}
 
-    utils::execute_sql(set_messages_to_notice(), pool).await?;
-    info!("");
-   
+
+pub async fn prune_checkpoints(names: &str, pool: &Pool<Postgres>) -> Result<(), AppError> {  
+
+    // Drops the listed checkpoints, or all of them if names is 'all'.
+
+    let ex = SqlExecutor::new(pool);
+    let saved: Vec<String> = utils::get_checkpoints(&ex).await?.into_iter().map(|c| c.name).collect();
+    let to_drop: Vec<String> = if names.trim().eq_ignore_ascii_case("all") {
+        saved.clone()
+    }
+    else {
+        names.split(',').map(|s| s.trim().to_lowercase()).filter(|s| !s.is_empty()).collect()
+    };
+
+    for name in to_drop.iter() {
+        if !saved.contains(name) {
+            return Result::Err(AppError::CheckpointError(
+                format!("Checkpoint '{}' has not been saved", name),
+                "Use --list-checkpoints to see the saved checkpoints".to_string()));
+        }
+    }
+    for name in to_drop.iter() {
+        utils::drop_checkpoint(name, &ex).await?;
+    }
     Ok(())
+}
+
+
+fn keep_mesh_terms_sql <'a>() -> &'a str {
 
+    // Each table is only (re)created if its browse table is still present, i.e. if
+    // simplify has not already been run on this snapshot, so that a re-run keeps it.
+
+    r#"SET client_min_messages TO WARNING; 
+    DO $$
+    BEGIN
+    IF to_regclass('ctgov.browse_conditions') is not null THEN
+        DROP TABLE IF EXISTS ctgov.mesh_conditions;
+        CREATE TABLE ctgov.mesh_conditions as
+        select distinct nct_id, mesh_term from ctgov.browse_conditions
+        where mesh_type = 'mesh-list';
+        CREATE INDEX mesh_conditions_nct_id ON ctgov.mesh_conditions(nct_id);
+    END IF;
+    IF to_regclass('ctgov.browse_interventions') is not null THEN
+        DROP TABLE IF EXISTS ctgov.mesh_interventions;
+        CREATE TABLE ctgov.mesh_interventions as
+        select distinct nct_id, mesh_term from ctgov.browse_interventions
+        where mesh_type = 'mesh-list';
+        CREATE INDEX mesh_interventions_nct_id ON ctgov.mesh_interventions(nct_id);
+    END IF;
+    END $$;"#
 }
 
 
@@ -0,0 +1,127 @@
+/***************************************************************************
+ * Support for running mdr work at the same time. The program runs on a
+ * single threaded runtime, but almost all the time is spent waiting on
+ * the database, so running several statements at once, each on its own
+ * pooled connection, still reduces the elapsed time.
+ *
+ * The job limit is shared by everything run for the import, and is the
+ * maximum number of statements that are being executed at any one time,
+ * whether from different stages or different chunks of the same stage.
+ * A task set polls a group of futures together, returning each result as
+ * it becomes available. As the futures only borrow, rather than own, the
+ * data they use (the plan, the pool etc.), nothing needs to be spawned.
+ ***************************************************************************/
+
+use std::future::{poll_fn, Future};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::Poll;
+use tokio::sync::{Semaphore, SemaphorePermit};
+
+#[derive(Clone)]
+pub struct JobLimit {
+    jobs: usize,
+    permits: Arc<Semaphore>,
+}
+
+impl JobLimit {
+
+    pub fn new(jobs: usize) -> JobLimit {
+        let jobs = jobs.max(1);
+        JobLimit { jobs, permits: Arc::new(Semaphore::new(jobs)) }
+    }
+
+    pub fn jobs(&self) -> usize {
+        self.jobs
+    }
+
+    pub async fn acquire(&self) -> SemaphorePermit<'_> {
+
+        // The semaphore is never closed, so acquiring can only fail if it were.
+
+        self.permits.acquire().await.expect("job limit semaphore closed")
+    }
+}
+
+
+type Task<'a, T> = Pin<Box<dyn Future<Output = T> + 'a>>;
+
+pub struct TaskSet<'a, K, T> {
+    tasks: Vec<(K, Task<'a, T>)>,
+}
+
+impl<'a, K, T> TaskSet<'a, K, T> {
+
+    pub fn new() -> TaskSet<'a, K, T> {
+        TaskSet { tasks: Vec::new() }
+    }
+
+    pub fn push(&mut self, key: K, task: impl Future<Output = T> + 'a) {
+        self.tasks.push((key, Box::pin(task)));
+    }
+
+    pub fn len(&self) -> usize {
+        self.tasks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tasks.is_empty()
+    }
+
+    pub async fn next(&mut self) -> Option<(K, T)> {
+
+        // Returns the key and result of the next task to complete,
+        // or None if there are no tasks left.
+
+        if self.tasks.is_empty() {
+            return None;
+        }
+        poll_fn(|cx| {
+            for i in 0..self.tasks.len() {
+                if let Poll::Ready(res) = self.tasks[i].1.as_mut().poll(cx) {
+                    let (key, _) = self.tasks.remove(i);
+                    return Poll::Ready(Some((key, res)));
+                }
+            }
+            Poll::Pending
+        }).await
+    }
+}
+
+impl<K, T> Default for TaskSet<'_, K, T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn check_tasks_complete_in_order_of_completion() {
+        let mut set = TaskSet::new();
+        for (key, waits) in [("slow", 5), ("fast", 0), ("medium", 2)] {
+            set.push(key, async move {
+                for _ in 0..waits {
+                    tokio::task::yield_now().await;
+                }
+                waits
+            });
+        }
+        assert_eq!(set.len(), 3);
+        assert_eq!(set.next().await, Some(("fast", 0)));
+        assert_eq!(set.next().await, Some(("medium", 2)));
+        assert_eq!(set.next().await, Some(("slow", 5)));
+        assert_eq!(set.next().await, None);
+    }
+
+    #[tokio::test]
+    async fn check_job_limit_is_at_least_one() {
+        let limit = JobLimit::new(0);
+        assert_eq!(limit.jobs(), 1);
+        let _permit = limit.acquire().await;
+        assert_eq!(limit.permits.available_permits(), 0);
+    }
+}
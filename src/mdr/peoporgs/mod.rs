@@ -0,0 +1,458 @@
+mod people_names;
+
+use people_names::parse_person_name;
+
+use super::dry_run;
+use super::executor::SqlExecutor;
+use crate::setup::{config_reader, get_named_db_pool};
+use crate::AppError;
+use log::{info, warn};
+
+// Contribution types used for organisations (from the mdr lookup tables).
+
+const CONTRIB_SPONSOR: i32 = 54;
+const CONTRIB_COLLABORATOR: i32 = 69;
+
+// Contribution types used for people.
+
+const CONTRIB_PRINCIPAL_INVESTIGATOR: i32 = 51;
+const CONTRIB_STUDY_CHAIR: i32 = 52;
+const CONTRIB_STUDY_DIRECTOR: i32 = 53;
+const CONTRIB_STUDY_CONTACT: i32 = 56;
+const CONTRIB_SPONSOR_INVESTIGATOR: i32 = 70;
+
+// The number of context organisation names written to the lookup table at once.
+
+const LOOKUP_BATCH: usize = 50000;
+
+// The number of ad.study_people records read, parsed and written back as a batch.
+
+const NAME_BATCH: i32 = 10000;
+
+#[derive(sqlx::FromRow)]
+struct CxtOrgName {
+    name: String,
+    org_id: i32,
+    default_name: String,
+    ror_id: Option<String>,
+}
+
+
+pub async fn build_orgs_table (ex: &SqlExecutor) -> Result<(), AppError> {  
+
+    let sql = r#"SET client_min_messages TO WARNING; 
+    DROP TABLE IF EXISTS ad.study_organisations;
+    CREATE TABLE ad.study_organisations(
+      id                     INT             PRIMARY KEY GENERATED ALWAYS AS IDENTITY (start with 10000001 increment by 1)
+    , sd_sid                 VARCHAR         NOT NULL
+    , contrib_type_id        INT             NULL
+    , organisation_id        INT             NULL
+    , organisation_name      VARCHAR         NULL
+    , organisation_ror_id    VARCHAR         NULL
+    , added_on               TIMESTAMPTZ     NOT NULL default now()
+    , coded_on               TIMESTAMPTZ     NULL
+    );
+    CREATE INDEX study_organisations_sid ON ad.study_organisations(sd_sid);"#;
+
+	ex.execute(sql).await?;
+    info!("study orgs table (re)created");
+    
+    Ok(())
+
+}
+
+
+pub async fn load_orgs_data (ex: &SqlExecutor) -> Result<(), AppError> {
+
+    // The lead sponsor and any collaborators are listed in ctgov.sponsors.
+
+    let sql = format!(r#"insert into ad.study_organisations(sd_sid, contrib_type_id, organisation_name)
+        select nct_id, {}, name from ctgov.sponsors
+        where lead_or_collaborator = 'lead'
+        order by nct_id, id;"#, CONTRIB_SPONSOR);
+    execute_orgs_fb(&sql, "lead sponsors added", ex).await?;
+
+    let sql = format!(r#"insert into ad.study_organisations(sd_sid, contrib_type_id, organisation_name)
+        select nct_id, {}, name from ctgov.sponsors
+        where lead_or_collaborator = 'collaborator'
+        order by nct_id, id;"#, CONTRIB_COLLABORATOR);
+    execute_orgs_fb(&sql, "collaborators added", ex).await?;
+    info!("");
+
+    clean_org_names(ex).await?;
+    code_orgs(ex).await?;
+    ex.vacuum_table("study_organisations").await?;
+
+    Ok(())
+}
+
+
+async fn clean_org_names (ex: &SqlExecutor) -> Result<(), AppError> {
+
+    // Spacing, quotes and apostrophes are regularised as for facility names, and the
+    // commonest abbreviations expanded. Names that are only placeholders are removed,
+    // as are duplicates, including collaborators that are also the study's sponsor.
+
+    execute_orgs_fb(r#"update ad.study_organisations set organisation_name = regexp_replace(trim(organisation_name), '\s+', ' ', 'g')
+        where organisation_name ~ '^\s|\s$|\s{2,}';"#, "names had spacing regularised", ex).await?;
+    execute_orgs_fb(r#"update ad.study_organisations set organisation_name = trim(replace(organisation_name, '"', ''))
+        where organisation_name like '%"%';"#, "names had double quotes removed", ex).await?;
+    execute_orgs_fb(r#"update ad.study_organisations set organisation_name = replace(organisation_name, '''', '’')
+        where organisation_name like '%''%';"#, "names had apostrophes replaced by right single quotes", ex).await?;
+    execute_orgs_fb(r#"update ad.study_organisations set organisation_name = rtrim(organisation_name, ',;: ')
+        where organisation_name ~ '[,;:]$';"#, "names had trailing punctuation removed", ex).await?;
+
+    for (abbrev, word) in [("Univ", "University"), ("Hosp", "Hospital"), ("Natl", "National"),
+                           ("Ctr", "Center"), ("Inst", "Institute"), ("Dept", "Department")] {
+        let sql = format!(r#"update ad.study_organisations set organisation_name = regexp_replace(organisation_name, '\m{}\M\.?', '{}', 'g')
+            where organisation_name ~ '\m{}\M';"#, abbrev, word, abbrev);
+        execute_orgs_fb(&sql, &format!("names had '{}' expanded to '{}'", abbrev, word), ex).await?;
+    }
+
+    execute_orgs_fb(placeholder_orgs_sql(), "placeholder names removed", ex).await?;
+    execute_orgs_fb(duplicate_orgs_sql(), "duplicate organisations removed", ex).await?;
+    info!("");
+
+    Ok(())
+}
+
+
+async fn code_orgs (ex: &SqlExecutor) -> Result<(), AppError> {
+
+    // Organisations whose (lower cased) name matches a name in the context database
+    // are given its organisation id, default name and ROR id. Names that match more
+    // than one organisation are not used. If the context database cannot be read
+    // the organisations are left uncoded.
+
+    let sql = r#"SET client_min_messages TO WARNING;
+    DROP TABLE IF EXISTS ad.temp_org_lookup;
+    CREATE TABLE ad.temp_org_lookup(
+      comp_name              VARCHAR         NOT NULL
+    , org_id                 INT             NOT NULL
+    , org_name               VARCHAR         NOT NULL
+    , ror_id                 VARCHAR         NULL
+    );"#;
+    ex.execute(sql).await?;
+
+    if !load_org_lookup(ex).await? {
+        ex.execute("DROP TABLE IF EXISTS ad.temp_org_lookup;").await?;
+        return Ok(());
+    }
+
+    ex.execute("CREATE INDEX temp_org_lookup_name ON ad.temp_org_lookup(comp_name);").await?;
+    ex.execute(ambiguous_org_names_sql()).await?;
+    execute_orgs_fb(code_orgs_sql(), "organisations coded using the context database", ex).await?;
+
+    ex.execute("DROP TABLE IF EXISTS ad.temp_org_lookup;").await?;
+    info!("");
+
+    Ok(())
+}
+
+
+async fn load_org_lookup (ex: &SqlExecutor) -> Result<bool, AppError> {
+
+    // The organisation names are read from the context database (cxt_db_name in the
+    // configuration file), which is on the same server, and written to the lookup table.
+
+    if dry_run::is_active() {
+        info!("dry run: organisation names not read from the context database");
+        return Ok(true);
+    }
+
+    let cxt_db_name = config_reader::fetch_db_pars()?.cxt_db_name;
+    let rows: Vec<CxtOrgName> = match get_named_db_pool(&cxt_db_name).await {
+        Ok(pool) => match sqlx::query_as(cxt_org_names_sql()).fetch_all(&pool).await {
+            Ok(rows) => rows,
+            Err(e) => {
+                warn!("organisations not coded, as names could not be read from context database {}: {}", cxt_db_name, e);
+                return Ok(false);
+            },
+        },
+        Err(e) => {
+            warn!("organisations not coded, as context database {} could not be connected to: {}", cxt_db_name, e);
+            return Ok(false);
+        },
+    };
+
+    for batch in rows.chunks(LOOKUP_BATCH) {
+        let columns = vec![
+            batch.iter().map(|r| r.name.clone()).collect(),
+            batch.iter().map(|r| r.org_id.to_string()).collect(),
+            batch.iter().map(|r| r.default_name.clone()).collect(),
+            batch.iter().map(|r| r.ror_id.clone().unwrap_or_default()).collect(),
+        ];
+        ex.execute_with_arrays(insert_org_lookup_sql(), &columns).await?;
+    }
+    info!("{} organisation names read from context database {}", rows.len(), cxt_db_name);
+
+    Ok(true)
+}
+
+
+async fn execute_orgs_fb (sql: &str, fb: &str, ex: &SqlExecutor) -> Result<(), AppError> {
+
+    let r = ex.execute(sql).await?.rows_affected();
+    info!("{} study organisation records: {}", r, fb);
+    Ok(())
+}
+
+
+fn placeholder_orgs_sql <'a>() -> &'a str {
+    r#"delete from ad.study_organisations
+    where organisation_name is null
+    or organisation_name = ''
+    or lower(organisation_name) in ('[redacted]', 'n/a', 'na', 'none', 'not applicable',
+    'unknown', 'sponsor', 'investigator', 'principal investigator', 'private', 'individual');"#
+}
+
+
+fn duplicate_orgs_sql <'a>() -> &'a str {
+    r#"delete from ad.study_organisations a
+    using ad.study_organisations b
+    where a.sd_sid = b.sd_sid
+    and lower(a.organisation_name) = lower(b.organisation_name)
+    and (a.contrib_type_id > b.contrib_type_id
+    or (a.contrib_type_id = b.contrib_type_id and a.id > b.id));"#
+}
+
+
+fn cxt_org_names_sql <'a>() -> &'a str {
+    r#"select n.name, o.id as org_id, o.default_name, o.ror_id
+    from ctx.org_names n
+    inner join ctx.organisations o on n.org_id = o.id
+    union
+    select o.default_name, o.id, o.default_name, o.ror_id
+    from ctx.organisations o"#
+}
+
+
+fn insert_org_lookup_sql <'a>() -> &'a str {
+    r#"insert into ad.temp_org_lookup(comp_name, org_id, org_name, ror_id)
+    select lower(replace(name, '''', '’')), org_id::int, org_name, nullif(ror_id, '')
+    from unnest($1::varchar[], $2::varchar[], $3::varchar[], $4::varchar[])
+    as t(name, org_id, org_name, ror_id);"#
+}
+
+
+fn ambiguous_org_names_sql <'a>() -> &'a str {
+    r#"delete from ad.temp_org_lookup k
+    using (select comp_name from ad.temp_org_lookup
+           group by comp_name having count(distinct org_id) > 1) a
+    where k.comp_name = a.comp_name;"#
+}
+
+
+fn code_orgs_sql <'a>() -> &'a str {
+    r#"update ad.study_organisations s
+    set organisation_id = k.org_id,
+    organisation_name = k.org_name,
+    organisation_ror_id = k.ror_id,
+    coded_on = now()
+    from (select distinct comp_name, org_id, org_name, ror_id from ad.temp_org_lookup) k
+    where lower(s.organisation_name) = k.comp_name
+    and s.organisation_id is null;"#
+}
+
+pub async fn build_people_table (ex: &SqlExecutor) -> Result<(), AppError> {  
+
+    let sql = r#"SET client_min_messages TO WARNING; 
+    DROP TABLE IF EXISTS ad.study_people;
+    CREATE TABLE ad.study_people(
+      id                     INT             PRIMARY KEY GENERATED ALWAYS AS IDENTITY (start with 10000001 increment by 1)
+    , sd_sid                 VARCHAR         NOT NULL
+    , contrib_type_id        INT             NULL
+    , person_given_name      VARCHAR         NULL
+    , person_family_name     VARCHAR         NULL
+    , person_full_name       VARCHAR         NULL
+    , orcid_id               VARCHAR         NULL
+    , person_affiliation     VARCHAR         NULL
+    , organisation_id        INT             NULL
+    , organisation_name      VARCHAR         NULL
+    , organisation_ror_id    VARCHAR         NULL
+    , added_on               TIMESTAMPTZ     NOT NULL default now()
+    , coded_on               TIMESTAMPTZ     NULL
+    );
+    CREATE INDEX study_people_sid ON ad.study_people(sd_sid);"#;
+
+	ex.execute(sql).await?;
+    info!("study people table (re)created");
+    
+    Ok(())
+
+}
+
+
+pub async fn load_people_data (ex: &SqlExecutor) -> Result<(), AppError> {
+
+    // People are listed in ctgov as the study's overall officials, as its responsible
+    // party (when that is an investigator rather than the sponsor) and as its central
+    // contacts. The names are loaded as given, and then parsed (see people_names.rs).
+
+    let sql = format!(r#"insert into ad.study_people(sd_sid, contrib_type_id, person_full_name, person_affiliation)
+        select nct_id,
+        case lower(replace(role, '_', ' '))
+            when 'principal investigator' then {}
+            when 'study chair' then {}
+            when 'study director' then {}
+        end, name, affiliation
+        from ctgov.overall_officials
+        where name is not null
+        order by nct_id, id;"#, CONTRIB_PRINCIPAL_INVESTIGATOR, CONTRIB_STUDY_CHAIR, CONTRIB_STUDY_DIRECTOR);
+    execute_people_fb(&sql, "overall officials added", ex).await?;
+
+    let sql = format!(r#"insert into ad.study_people(sd_sid, contrib_type_id, person_full_name, person_affiliation)
+        select nct_id,
+        case lower(replace(responsible_party_type, '_', ' '))
+            when 'principal investigator' then {}
+            when 'sponsor-investigator' then {}
+            when 'sponsor investigator' then {}
+        end, name, coalesce(affiliation, organization)
+        from ctgov.responsible_parties
+        where name is not null
+        and lower(responsible_party_type) <> 'sponsor'
+        order by nct_id, id;"#, CONTRIB_PRINCIPAL_INVESTIGATOR, CONTRIB_SPONSOR_INVESTIGATOR, CONTRIB_SPONSOR_INVESTIGATOR);
+    execute_people_fb(&sql, "responsible party investigators added", ex).await?;
+
+    let sql = format!(r#"insert into ad.study_people(sd_sid, contrib_type_id, person_full_name)
+        select nct_id, {}, name
+        from ctgov.central_contacts
+        where name is not null
+        order by nct_id, id;"#, CONTRIB_STUDY_CONTACT);
+    execute_people_fb(&sql, "central contacts added", ex).await?;
+    info!("");
+
+    parse_people_names(ex).await?;
+
+    execute_people_fb(r#"update ad.study_people set person_affiliation = nullif(regexp_replace(trim(person_affiliation), '\s+', ' ', 'g'), '')
+        where person_affiliation ~ '^\s|\s$|\s{2,}' or person_affiliation = '';"#, "affiliations had spacing regularised", ex).await?;
+    execute_people_fb(r#"update ad.study_people set person_affiliation = replace(person_affiliation, '''', '’')
+        where person_affiliation like '%''%';"#, "affiliations had apostrophes replaced by right single quotes", ex).await?;
+    execute_people_fb("delete from ad.study_people where person_full_name is null;", "removed as not being names of people", ex).await?;
+    execute_people_fb(duplicate_people_sql(), "duplicate people removed", ex).await?;
+    info!("");
+
+    ex.vacuum_table("study_people").await?;
+
+    Ok(())
+}
+
+
+async fn parse_people_names (ex: &SqlExecutor) -> Result<(), AppError> {
+
+    // The names are read in batches (by id) and the parsed names written back with one
+    // update per batch. Names that are not those of a person are set to null, for removal.
+    // In a dry run ad.study_people cannot be read, so the names are not parsed.
+
+    if dry_run::is_active() {
+        info!("dry run: people's names not parsed, as ad.study_people cannot be read");
+        return Ok(());
+    }
+
+    let (min_id, max_id): (Option<i32>, Option<i32>) = ex.fetch_rows("select min(id), max(id) from ad.study_people").await?
+        .into_iter().next().unwrap_or((None, None));
+    let (Some(min_id), Some(max_id)) = (min_id, max_id) else {
+        info!("no names to parse, as ad.study_people is empty");
+        return Ok(());
+    };
+
+    let (mut parsed, mut orcids) = (0, 0);
+    let mut start = min_id;
+    while start <= max_id {
+        let sql = format!(r#"select id, person_full_name from ad.study_people
+                          where id >= {} and id < {} order by id"#, start, start + NAME_BATCH);
+        let rows: Vec<(i32, String)> = ex.fetch_rows(&sql).await?;
+
+        let mut columns: Vec<Vec<String>> = vec![Vec::new(); 5];
+        for (id, name) in rows {
+            let p = parse_person_name(&name);
+            if p.orcid.is_some() {
+                orcids += 1;
+            }
+            columns[0].push(id.to_string());
+            columns[1].push(p.given.unwrap_or_default());
+            columns[2].push(p.family.unwrap_or_default());
+            columns[3].push(p.full.unwrap_or_default());
+            columns[4].push(p.orcid.unwrap_or_default());
+        }
+
+        if !columns[0].is_empty() {
+            parsed += ex.execute_with_arrays(update_people_names_sql(), &columns).await?.rows_affected();
+        }
+        start += NAME_BATCH;
+    }
+
+    info!("{} study people records: names parsed, with {} ORCID ids found", parsed, orcids);
+    Ok(())
+}
+
+
+async fn execute_people_fb (sql: &str, fb: &str, ex: &SqlExecutor) -> Result<(), AppError> {
+
+    let r = ex.execute(sql).await?.rows_affected();
+    info!("{} study people records: {}", r, fb);
+    Ok(())
+}
+
+
+fn update_people_names_sql <'a>() -> &'a str {
+    r#"update ad.study_people p
+    set person_given_name = nullif(t.given, ''),
+    person_family_name = nullif(t.family, ''),
+    person_full_name = nullif(t.full_name, ''),
+    orcid_id = nullif(t.orcid, '')
+    from unnest($1::varchar[], $2::varchar[], $3::varchar[], $4::varchar[], $5::varchar[])
+    as t(id, given, family, full_name, orcid)
+    where p.id = t.id::int;"#
+}
+
+
+fn duplicate_people_sql <'a>() -> &'a str {
+    r#"delete from ad.study_people a
+    using ad.study_people b
+    where a.sd_sid = b.sd_sid
+    and a.contrib_type_id is not distinct from b.contrib_type_id
+    and lower(a.person_full_name) = lower(b.person_full_name)
+    and a.id > b.id;"#
+}
+
+/*
+
+
+DROP TABLE IF EXISTS ad.study_people;
+CREATE TABLE ad.study_people(
+	id                     INT             PRIMARY KEY GENERATED ALWAYS AS IDENTITY (start with 10000001 increment by 1)
+  , sd_sid                 VARCHAR         NOT NULL
+  , contrib_type_id        INT             NULL
+  , person_given_name      VARCHAR         NULL
+  , person_family_name     VARCHAR         NULL
+  , person_full_name       VARCHAR         NULL
+  , orcid_id               VARCHAR         NULL
+  , person_affiliation     VARCHAR         NULL
+  , organisation_id        INT             NULL
+  , organisation_name      VARCHAR         NULL
+  , organisation_ror_id    VARCHAR         NULL
+  , added_on               TIMESTAMPTZ     NOT NULL default now()
+  , coded_on               TIMESTAMPTZ     NULL
+);
+CREATE INDEX study_people_sid ON ad.study_people(sd_sid);
+
+
+DROP TABLE IF EXISTS ad.study_organisations;
+CREATE TABLE ad.study_organisations(
+	id                     INT             PRIMARY KEY GENERATED ALWAYS AS IDENTITY (start with 10000001 increment by 1)
+  , sd_sid                 VARCHAR         NOT NULL
+  , contrib_type_id        INT             NULL
+  , organisation_id        INT             NULL
+  , organisation_name      VARCHAR         NULL
+  , organisation_ror_id    VARCHAR         NULL
+  , added_on               TIMESTAMPTZ     NOT NULL default now()
+  , coded_on               TIMESTAMPTZ     NULL
+);
+CREATE INDEX study_organisations_sid ON ad.study_organisations(sd_sid);
+
+
+
+
+
+*/
\ No newline at end of file
@@ -0,0 +1,232 @@
+// Parses the name of a person as given in ctgov (for an official, responsible party
+// or contact). The names often carry titles ("Dr.", "Prof") and degrees ("MD, PhD"),
+// sometimes an ORCID id, and occasionally are in 'family, given' order. Titles and
+// degrees are removed, any ORCID id extracted, and the remaining name split into
+// given and family names, the family name being the last word together with any
+// preceding particles ("van", "de la"). Names that are clearly not those of a person
+// ("Clinical Trials", "Medical Director") return no name at all.
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct PersonName {
+    pub given: Option<String>,
+    pub family: Option<String>,
+    pub full: Option<String>,
+    pub orcid: Option<String>,
+}
+
+const TITLES: [&str; 15] = ["dr", "doctor", "prof", "professor", "pr", "mr", "mrs", "ms", "miss", "mme",
+    "sir", "assoc", "associate", "assistant", "asst"];
+
+const DEGREES: [&str; 76] = ["md", "phd", "mph", "msc", "ms", "ma", "ba", "bs", "bsc", "mba", "rn", "bsn",
+    "msn", "np", "pa", "pa-c", "do", "dds", "dmd", "pharmd", "mbbs", "mbchb", "mbbch", "frcp", "frcpc",
+    "frcs", "frcsc", "facs", "facp", "facc", "fccp", "fracp", "franzcp", "frcog", "frcpath", "mrcp",
+    "mrcpch", "drph", "dphil", "crnp", "aprn", "cnm", "ccrp", "ccrc", "lcsw", "msw", "otr", "pt", "dpt",
+    "od", "dvm", "mhs", "mscr", "msci", "jd", "edd", "psyd", "lpc", "slp", "ccc-slp", "mmed", "faan",
+    "faap", "fache", "med", "bpharm", "mpharm", "rd", "ld", "cpnp", "fnp", "fnp-bc", "ocn", "mpa",
+    "mhsc", "dsc"];
+
+const SUFFIXES: [&str; 5] = ["jr", "sr", "ii", "iii", "iv"];
+
+const PARTICLES: [&str; 17] = ["van", "von", "der", "den", "de", "da", "del", "della", "di", "du",
+    "le", "la", "ter", "ten", "dos", "das", "st"];
+
+const NON_PERSON: [&str; 31] = ["clinical", "trial", "trials", "call", "center", "centre", "director",
+    "pharmaceutical", "pharmaceuticals", "inc", "ltd", "gmbh", "company", "corporation", "department",
+    "hospital", "university", "study", "studies", "contact", "information", "medical", "research",
+    "sponsor", "team", "office", "desk", "registry", "disclosure", "transparency", "group"];
+
+
+pub fn parse_person_name(raw: &str) -> PersonName {
+
+    let orcid = find_orcid(raw);
+    let text: Vec<&str> = raw.split_whitespace()
+        .filter(|t| !is_orcid_token(t, orcid.as_deref()))
+        .collect();
+    let text = text.join(" ");
+
+    // The first comma separated segment is the name. Later segments are usually degrees
+    // (dropped) or an affiliation (ignored), but a single later segment after a one word
+    // name is taken as the given name, the name having been entered 'family, given'.
+
+    let mut segments = text.split(',').map(str::trim).filter(|s| !s.is_empty());
+    let Some(first) = segments.next() else {
+        return PersonName { orcid, ..PersonName::default() };
+    };
+    let others: Vec<&str> = segments.filter(|s| !s.split_whitespace().all(is_title_or_degree)).collect();
+
+    let tokens = name_tokens(first);
+    if tokens.iter().any(|t| NON_PERSON.contains(&norm(t).as_str())) {
+        return PersonName { orcid, ..PersonName::default() };
+    }
+
+    let (given, family) = if tokens.len() == 1 && others.len() == 1 {
+        let given = name_tokens(others[0]);
+        (Some(given.join(" ")).filter(|g| !g.is_empty()), Some(tokens[0].clone()))
+    } else {
+        split_name(&tokens)
+    };
+
+    let full = match (&given, &family) {
+        (Some(g), Some(f)) => Some(format!("{} {}", g, f)),
+        (None, Some(f)) => Some(f.clone()),
+        _ => None,
+    };
+
+    PersonName { given, family, full, orcid }
+}
+
+
+fn name_tokens(segment: &str) -> Vec<String> {
+
+    // Leading titles and trailing degrees are removed, as are tokens without letters.
+    // A trailing degree without a preceding comma is only removed if it cannot be
+    // part of a name, i.e. it is dotted, capitalised or at least three letters long
+    // (so that "MA" is removed but the family name "Ma" retained).
+
+    let mut tokens: Vec<String> = segment.split_whitespace()
+        .map(|t| t.trim_matches(|c: char| !c.is_alphanumeric() && c != '.' && c != '\'' && c != '’').to_string())
+        .map(|t| t.trim_start_matches('.').to_string())
+        .filter(|t| t.chars().any(char::is_alphabetic))
+        .collect();
+
+    while tokens.len() > 1 && TITLES.contains(&norm(&tokens[0]).as_str()) {
+        tokens.remove(0);
+    }
+    while tokens.len() > 1 && is_trailing_degree(&tokens[tokens.len() - 1]) {
+        tokens.pop();
+    }
+    tokens
+}
+
+
+fn split_name(tokens: &[String]) -> (Option<String>, Option<String>) {
+
+    let mut tokens = tokens.to_vec();
+    let suffix = if tokens.len() > 2 && SUFFIXES.contains(&norm(&tokens[tokens.len() - 1]).as_str()) {
+        tokens.pop()
+    } else {
+        None
+    };
+
+    match tokens.len() {
+        0 => (None, None),
+        1 => (None, Some(tokens[0].clone())),
+        n => {
+            let mut start = n - 1;
+            while start > 1 && PARTICLES.contains(&tokens[start - 1].to_lowercase().as_str()) {
+                start -= 1;
+            }
+            let mut family = tokens[start..].join(" ");
+            if let Some(s) = suffix {
+                family = format!("{} {}", family, s);
+            }
+            (Some(tokens[..start].join(" ")), Some(family))
+        },
+    }
+}
+
+
+fn is_title_or_degree(t: &str) -> bool {
+    let n = norm(t);
+    n.is_empty() || DEGREES.contains(&n.as_str()) || TITLES.contains(&n.as_str())
+}
+
+
+fn is_trailing_degree(t: &str) -> bool {
+    let n = norm(t);
+    DEGREES.contains(&n.as_str())
+        && (n.len() >= 3 || t.contains('.') || t.chars().skip(1).any(char::is_uppercase))
+}
+
+
+fn norm(t: &str) -> String {
+    t.to_lowercase()
+        .replace('.', "")
+        .trim_matches(|c: char| !c.is_alphanumeric())
+        .to_string()
+}
+
+
+fn find_orcid(text: &str) -> Option<String> {
+
+    // An ORCID id is four groups of four digits separated by hyphens, the last
+    // character possibly being a check character 'X'.
+
+    let chars: Vec<char> = text.chars().collect();
+    let pattern_len = 19;
+    if chars.len() < pattern_len {
+        return None;
+    }
+    for start in 0..=(chars.len() - pattern_len) {
+        if start > 0 && chars[start - 1].is_ascii_digit() {
+            continue;
+        }
+        let candidate = &chars[start..start + pattern_len];
+        let matches = candidate.iter().enumerate().all(|(i, c)| match i {
+            4 | 9 | 14 => *c == '-',
+            18 => c.is_ascii_digit() || *c == 'X' || *c == 'x',
+            _ => c.is_ascii_digit(),
+        });
+        let bounded = chars.get(start + pattern_len).is_none_or(|c| !c.is_ascii_alphanumeric());
+        if matches && bounded {
+            return Some(candidate.iter().collect::<String>().to_uppercase());
+        }
+    }
+    None
+}
+
+
+fn is_orcid_token(t: &str, orcid: Option<&str>) -> bool {
+    let Some(orcid) = orcid else { return false };
+    let lower = t.to_lowercase();
+    lower.contains("orcid") || lower.contains(&orcid.to_lowercase()) || norm(t) == "id"
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(raw: &str) -> (Option<String>, Option<String>, Option<String>) {
+        let p = parse_person_name(raw);
+        (p.given, p.family, p.full)
+    }
+
+    fn some(given: &str, family: &str, full: &str) -> (Option<String>, Option<String>, Option<String>) {
+        (Some(given.to_string()), Some(family.to_string()), Some(full.to_string()))
+    }
+
+    #[test]
+    fn check_titles_and_degrees_removed() {
+        assert_eq!(names("Dr. John Smith, MD, PhD"), some("John", "Smith", "John Smith"));
+        assert_eq!(names("Prof  Anna K. Jones MD"), some("Anna K.", "Jones", "Anna K. Jones"));
+        assert_eq!(names("Associate Professor Li Wei, M.D., Ph.D."), some("Li", "Wei", "Li Wei"));
+        assert_eq!(names("Wei Ma"), some("Wei", "Ma", "Wei Ma"));
+        assert_eq!(names("Jane Doe, MD, Harvard University"), some("Jane", "Doe", "Jane Doe"));
+    }
+
+    #[test]
+    fn check_family_name_split() {
+        assert_eq!(names("Maria de la Cruz"), some("Maria", "de la Cruz", "Maria de la Cruz"));
+        assert_eq!(names("Smith, John"), some("John", "Smith", "John Smith"));
+        assert_eq!(names("John Smith Jr."), some("John", "Smith Jr.", "John Smith Jr."));
+        assert_eq!(names("Madonna"), (None, Some("Madonna".to_string()), Some("Madonna".to_string())));
+    }
+
+    #[test]
+    fn check_orcid_extracted() {
+        let p = parse_person_name("Jane Doe, PhD (ORCID: 0000-0002-1825-009x)");
+        assert_eq!(p.orcid.as_deref(), Some("0000-0002-1825-009X"));
+        assert_eq!(p.full.as_deref(), Some("Jane Doe"));
+        let p = parse_person_name("Jane Doe https://orcid.org/0000-0002-1825-0097");
+        assert_eq!(p.orcid.as_deref(), Some("0000-0002-1825-0097"));
+        assert_eq!(p.family.as_deref(), Some("Doe"));
+    }
+
+    #[test]
+    fn check_non_person_names() {
+        assert_eq!(parse_person_name("Clinical Trials Information Desk"), PersonName::default());
+        assert_eq!(parse_person_name("Medical Director"), PersonName::default());
+        assert_eq!(parse_person_name(" , "), PersonName::default());
+    }
+}
@@ -0,0 +1,184 @@
+/***************************************************************************
+ * The restore stage, which replaces the ctgov schema in the aact database
+ * with the contents of an AACT snapshot (postgres.dmp), following the steps
+ * in 'docs/Downloading AACT db from ctgov.txt'. It is run before the pool to
+ * the aact database is created, as that database may not yet exist.
+ * If given the downloaded zip file, its contents are first extracted to a
+ * folder alongside it, named after the zip file.
+ ***************************************************************************/
+
+use crate::AppError;
+use crate::setup::{self, config_reader::{self, RestorePars}};
+use log::info;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+pub async fn restore_aact(restore_pars: &RestorePars) -> Result<(), AppError> {
+
+    let mut dump_path = PathBuf::from(&restore_pars.dump_path);
+    if !dump_path.is_file() {
+        return Result::Err(AppError::RestoreError(
+            format!("AACT snapshot file not found: {}", dump_path.display()),
+            "Check the dump_path value in the [restore] section of the config file, or the --dump argument".to_string()));
+    }
+
+    if dump_path.extension().is_some_and(|e| e.eq_ignore_ascii_case("zip")) {
+        dump_path = unzip_snapshot(&dump_path)?;
+    }
+
+    let db_pars = config_reader::fetch_db_pars()?;
+    create_db_if_missing(&db_pars.db_name).await?;
+    clear_ctgov_schema(&db_pars.db_name, &restore_pars.previous_ctgov).await?;
+
+    // The password is passed via the environment rather than a pgpass file.
+
+    info!("restoring {} to database {}", dump_path.display(), db_pars.db_name);
+    let mut cmd = Command::new("pg_restore");
+    cmd.env("PGPASSWORD", &db_pars.db_password)
+        .args(["-h", &db_pars.db_host, "-p", &db_pars.db_port.to_string(), "-U", &db_pars.db_user])
+        .args(["-e", "-v", "-O", "-x", "-d", &db_pars.db_name, "--no-owner"])
+        .arg(&dump_path);
+    run_and_log(cmd, "pg_restore")?;
+
+    info!("AACT snapshot restored as the ctgov schema");
+    info!("");
+
+    Ok(())
+}
+
+
+fn unzip_snapshot(zip_path: &Path) -> Result<PathBuf, AppError> {
+
+    let parent = zip_path.parent().unwrap_or(Path::new("."));
+    let stem = zip_path.file_stem().unwrap_or_default();
+    let folder = parent.join(stem);
+    std::fs::create_dir_all(&folder)?;
+
+    info!("extracting {} to {}", zip_path.display(), folder.display());
+    let mut cmd = if cfg!(windows) {
+        let mut c = Command::new("tar");
+        c.arg("-xvf").arg(zip_path).arg("-C").arg(&folder);
+        c
+    }
+    else {
+        let mut c = Command::new("unzip");
+        c.arg("-o").arg(zip_path).arg("-d").arg(&folder);
+        c
+    };
+    cmd.stdin(Stdio::null());
+    run_and_log(cmd, "unzip")?;
+
+    let dump_path = folder.join("postgres.dmp");
+    if !dump_path.is_file() {
+        return Result::Err(AppError::RestoreError(
+            format!("No postgres.dmp file found after extracting {}", zip_path.display()),
+            format!("Check the contents of {}", folder.display())));
+    }
+    Ok(dump_path)
+}
+
+
+async fn create_db_if_missing(db_name: &str) -> Result<(), AppError> {
+
+    let pool = setup::get_named_db_pool("postgres").await?;
+
+    let sql = "select exists(select 1 from pg_database where datname = $1)";
+    let exists: bool = sqlx::query_scalar(sql).bind(db_name).fetch_one(&pool)
+        .await.map_err(|e| AppError::SqlxError(e, sql.to_string()))?;
+
+    if !exists {
+        let sql = format!(r#"create database "{}""#, db_name);
+        sqlx::raw_sql(&sql).execute(&pool)
+            .await.map_err(|e| AppError::SqlxError(e, sql.clone()))?;
+        info!("database {} created", db_name);
+    }
+
+    pool.close().await;
+    Ok(())
+}
+
+
+async fn clear_ctgov_schema(db_name: &str, previous_ctgov: &str) -> Result<(), AppError> {
+
+    // The existing ctgov schema is either dropped, or renamed to ctgov_prev
+    // (replacing any earlier ctgov_prev schema).
+
+    let pool = setup::get_named_db_pool(db_name).await?;
+
+    let sql = "select exists(select 1 from pg_namespace where nspname = 'ctgov')";
+    let exists: bool = sqlx::query_scalar(sql).fetch_one(&pool)
+        .await.map_err(|e| AppError::SqlxError(e, sql.to_string()))?;
+
+    if exists {
+        let sql = if previous_ctgov == "rename" {
+            r#"SET client_min_messages TO WARNING;
+            drop schema if exists ctgov_prev cascade;
+            alter schema ctgov rename to ctgov_prev;"#
+        }
+        else {
+            r#"SET client_min_messages TO WARNING;
+            drop schema ctgov cascade;"#
+        };
+        sqlx::raw_sql(sql).execute(&pool)
+            .await.map_err(|e| AppError::SqlxError(e, sql.to_string()))?;
+        if previous_ctgov == "rename" {
+            info!("existing ctgov schema renamed to ctgov_prev");
+        }
+        else {
+            info!("existing ctgov schema dropped");
+        }
+    }
+
+    pool.close().await;
+    Ok(())
+}
+
+
+fn run_and_log(mut cmd: Command, program: &str) -> Result<(), AppError> {
+
+    // Progress messages are written to stderr by pg_restore (in verbose mode), and
+    // to stdout by unzip. Both are sent to the log, line by line, as they arrive.
+
+    let mut child = cmd.stdout(Stdio::piped()).stderr(Stdio::piped()).spawn()
+        .map_err(|e| AppError::RestoreError(format!("Unable to start {}: {}", program, e),
+                      format!("Check that {} is installed and in the PATH", program)))?;
+
+    let stdout = child.stdout.take();
+    let stdout_program = program.to_string();
+    let stdout_reader = std::thread::spawn(move || {
+        if let Some(out) = stdout {
+            for line in BufReader::new(out).lines().map_while(Result::ok) {
+                log_line(&stdout_program, &line);
+            }
+        }
+    });
+
+    if let Some(err) = child.stderr.take() {
+        for line in BufReader::new(err).lines().map_while(Result::ok) {
+            log_line(program, &line);
+        }
+    }
+    let _ = stdout_reader.join();
+
+    let status = child.wait()?;
+    if !status.success() {
+        return Result::Err(AppError::RestoreError(
+            format!("{} failed ({})", program, status),
+            "See the log for the messages it produced".to_string()));
+    }
+    Ok(())
+}
+
+
+fn log_line(program: &str, line: &str) {
+
+    // pg_restore already prefixes its messages with its name.
+
+    if line.starts_with(program) {
+        info!("{}", line);
+    }
+    else {
+        info!("{}: {}", program, line);
+    }
+}
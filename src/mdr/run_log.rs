@@ -0,0 +1,322 @@
+/***************************************************************************
+ * Persisted run state for the mdr import. Each stage, and each sql step run
+ * through the mdr executor within it, is recorded in ad.run_log, with its start
+ * and end times, the rows affected and its status. Runs are keyed on the
+ * data date and the snapshot of the AACT data (the maximum NCT id and the
+ * number of studies). Steps are numbered in the order they are run within
+ * their stage, which is deterministic for a given version of the program.
+ *
+ * On a resumed run, a stage completed in the last attempt for the same key
+ * is skipped as a whole. Otherwise the leading steps of the stage that were
+ * completed in the last attempt (with unchanged sql) are skipped, and the
+ * stage continues from the first step that had not been completed. Skipped
+ * steps are recorded with status 'skipped' and the row count originally
+ * recorded, which is also returned to the calling code. Steps whose changes
+ * were rolled back (see executor.rs) are given the status 'rolled back', so
+ * that they are run again. When the chunks of a phased update are run at the
+ * same time, each is still a separate step, and as the chunks are independent
+ * any completed chunk can be skipped, not only those leading the stage.
+ ***************************************************************************/
+
+use super::executor::StepResult;
+use sqlx::{Pool, Postgres, Row};
+use crate::AppError;
+use log::info;
+use std::sync::Mutex;
+
+struct RunState {
+    data_date: String,
+    snapshot: String,
+    resume: bool,
+}
+
+static RUN_STATE: Mutex<Option<RunState>> = Mutex::new(None);
+
+
+// The state of a stage while it is being run. It is held by the stage's executor
+// rather than here, as stages may be run at the same time (see executor.rs).
+
+pub struct StageLog {
+    stage: String,
+    stage_row: Option<i32>,     // id of the run_log row for the stage, if a run is being recorded
+    resume_after: Option<i32>,  // id of the previous attempt's stage row, while steps can still be skipped
+    step: i32,
+    stage_rows: u64,
+}
+
+impl StageLog {
+
+    pub fn new(stage: &str) -> StageLog {
+        StageLog { stage: stage.to_string(), stage_row: None, resume_after: None, step: 0, stage_rows: 0 }
+    }
+
+    pub fn step(&self) -> i32 {
+        self.step
+    }
+}
+
+
+pub async fn start_run(data_date: &str, resume: bool, pool: &Pool<Postgres>) -> Result<(), AppError> {
+
+    execute_log_sql(create_run_log_sql(), pool).await?;
+
+    let sql = "select max(nct_id)||'/'||count(*)::varchar from ctgov.studies";
+    let snapshot: String = sqlx::query_scalar(sql).fetch_one(pool)
+        .await.map_err(|e| AppError::SqlxError(e, sql.to_string()))?;
+
+    info!("run state being recorded in ad.run_log, for data date {} and snapshot {}", data_date, snapshot);
+    if resume {
+        info!("resuming any earlier incomplete run for the same data date and snapshot");
+    }
+
+    *RUN_STATE.lock().unwrap() = Some(RunState {
+        data_date: data_date.to_string(),
+        snapshot,
+        resume,
+    });
+
+    Ok(())
+}
+
+
+pub fn end_run() {
+    *RUN_STATE.lock().unwrap() = None;
+}
+
+
+pub async fn start_stage(stage: &str, pool: &Pool<Postgres>) -> Result<Option<StageLog>, AppError> {
+
+    // Returns the stage's log if the stage should be run, None if it can be skipped.
+    // Step 0 is used for the stage as a whole.
+
+    let mut log = StageLog::new(stage);
+    let Some((data_date, snapshot, resume)) = current_key() else {
+        return Ok(Some(log));
+    };
+
+    let sql = r#"select id, status, rows_affected from ad.run_log
+                 where data_date = $1 and snapshot = $2 and stage = $3 and step = 0
+                 order by id desc limit 1"#;
+    let previous = sqlx::query(sql).bind(&data_date).bind(&snapshot).bind(stage)
+        .fetch_optional(pool).await.map_err(|e| AppError::SqlxError(e, sql.to_string()))?;
+
+    if resume && let Some(row) = previous {
+        let id: i32 = row.get("id");
+        let status: String = row.get("status");
+        if is_done(&status) {
+            let rows: Option<i64> = row.get("rows_affected");
+            insert_skipped_row(&data_date, &snapshot, stage, 0, "", rows.unwrap_or(0), pool).await?;
+            info!("mdr stage '{}' skipped, as already completed for this data date and snapshot", stage);
+            return Ok(None);
+        }
+        log.resume_after = Some(id);
+    }
+
+    log.stage_row = Some(insert_running_row(&data_date, &snapshot, stage, 0, "", pool).await?);
+    Ok(Some(log))
+}
+
+
+pub async fn end_stage<T>(log: &Mutex<StageLog>, outcome: &Result<T, AppError>, pool: &Pool<Postgres>) -> Result<(), AppError> {
+
+    let (stage_row, stage_rows) = {
+        let log = log.lock().unwrap();
+        (log.stage_row, log.stage_rows)
+    };
+    let Some(stage_row) = stage_row else {
+        return Ok(());
+    };
+
+    match outcome {
+        Ok(_) => complete_row(stage_row, stage_rows, pool).await,
+        Err(e) => fail_row(stage_row, &e.to_string(), pool).await,
+    }
+}
+
+
+pub enum StepStart {
+    Skipped(u64),       // rows affected, as originally recorded
+    Run(Option<i32>),   // id of the run_log row for the step, if a run is being recorded
+}
+
+
+pub async fn start_step(log: &Mutex<StageLog>, sql: &str, pool: &Pool<Postgres>) -> Result<StepStart, AppError> {
+
+    // Sql run outside of a recorded run (e.g. by other modules) is simply executed.
+    // The step number is taken before anything is awaited, so that steps started
+    // together (the chunks of a phased update) are numbered in the order started.
+
+    let Some((data_date, snapshot, _)) = current_key() else {
+        return Ok(StepStart::Run(None));
+    };
+
+    let (stage, step, resume_after) = {
+        let mut log = log.lock().unwrap();
+        if log.stage_row.is_none() {
+            return Ok(StepStart::Run(None));
+        }
+        log.step += 1;
+        (log.stage.clone(), log.step, log.resume_after)
+    };
+
+    if let Some(after_id) = resume_after {
+        if let Some(rows) = get_completed_step(&data_date, &snapshot, &stage, step, sql, after_id, pool).await? {
+            insert_skipped_row(&data_date, &snapshot, &stage, step, sql, rows, pool).await?;
+            log.lock().unwrap().stage_rows += rows as u64;
+            info!("step {} of stage '{}' skipped, as already completed ({} rows)", step, stage, rows);
+            return Ok(StepStart::Skipped(rows as u64));
+        }
+
+        // From here on all steps in this stage are run again.
+
+        log.lock().unwrap().resume_after = None;
+    }
+
+    let step_row = insert_running_row(&data_date, &snapshot, &stage, step, sql, pool).await?;
+    Ok(StepStart::Run(Some(step_row)))
+}
+
+
+pub async fn end_step(log: &Mutex<StageLog>, step_row: Option<i32>, outcome: &Result<StepResult, AppError>, pool: &Pool<Postgres>) -> Result<(), AppError> {
+
+    let Some(step_row) = step_row else {
+        return Ok(());
+    };
+
+    match outcome {
+        Ok(res) => {
+            complete_row(step_row, res.rows_affected(), pool).await?;
+            log.lock().unwrap().stage_rows += res.rows_affected();
+            Ok(())
+        },
+        Err(e) => fail_row(step_row, &e.to_string(), pool).await,
+    }
+}
+
+
+pub async fn mark_rolled_back(log: &Mutex<StageLog>, after_step: i32, pool: &Pool<Postgres>) -> Result<(), AppError> {
+
+    // Marks the steps of the stage completed after the given step.
+
+    let (stage, stage_row, step) = {
+        let log = log.lock().unwrap();
+        (log.stage.clone(), log.stage_row, log.step)
+    };
+    let Some(stage_row) = stage_row else {
+        return Ok(());
+    };
+    if step <= after_step {
+        return Ok(());
+    }
+
+    let upd_sql = r#"update ad.run_log r set status = 'rolled back'
+                 from ad.run_log s
+                 where s.id = $1 and r.data_date = s.data_date and r.snapshot = s.snapshot
+                 and r.stage = s.stage and r.id > s.id and r.step > $2 and r.status = 'completed'"#;
+    let res = sqlx::query(upd_sql).bind(stage_row).bind(after_step)
+        .execute(pool).await.map_err(|e| AppError::SqlxError(e, upd_sql.to_string()))?;
+    info!("{} completed step(s) of stage '{}' marked as rolled back in the run log", res.rows_affected(), stage);
+    Ok(())
+}
+
+
+fn current_key() -> Option<(String, String, bool)> {
+    RUN_STATE.lock().unwrap().as_ref()
+        .map(|s| (s.data_date.clone(), s.snapshot.clone(), s.resume))
+}
+
+
+fn is_done(status: &str) -> bool {
+    status == "completed" || status == "skipped"
+}
+
+
+async fn execute_log_sql(sql: &str, pool: &Pool<Postgres>) -> Result<(), AppError> {
+
+    sqlx::raw_sql(sql).execute(pool)
+        .await.map_err(|e| AppError::SqlxError(e, sql.to_string()))?;
+    Ok(())
+}
+
+
+async fn get_completed_step(data_date: &str, snapshot: &str, stage: &str, step: i32, sql: &str,
+                            after_id: i32, pool: &Pool<Postgres>) -> Result<Option<i64>, AppError> {
+
+    // The most recent record of the step since the start of the previous attempt at the stage.
+
+    let get_sql = r#"select status, step_sql, rows_affected from ad.run_log
+                 where data_date = $1 and snapshot = $2 and stage = $3 and step = $4 and id > $5
+                 order by id desc limit 1"#;
+    let row = sqlx::query(get_sql).bind(data_date).bind(snapshot).bind(stage).bind(step).bind(after_id)
+        .fetch_optional(pool).await.map_err(|e| AppError::SqlxError(e, get_sql.to_string()))?;
+
+    Ok(row.and_then(|r| {
+        let status: String = r.get("status");
+        let step_sql: Option<String> = r.get("step_sql");
+        let rows: Option<i64> = r.get("rows_affected");
+        if is_done(&status) && step_sql.as_deref() == Some(sql) { Some(rows.unwrap_or(0)) } else { None }
+    }))
+}
+
+
+async fn insert_running_row(data_date: &str, snapshot: &str, stage: &str, step: i32, sql: &str,
+                            pool: &Pool<Postgres>) -> Result<i32, AppError> {
+
+    let ins_sql = r#"insert into ad.run_log (data_date, snapshot, stage, step, step_sql, started, status)
+                 values ($1, $2, $3, $4, nullif($5, ''), now(), 'running')
+                 returning id"#;
+    sqlx::query_scalar(ins_sql).bind(data_date).bind(snapshot).bind(stage).bind(step).bind(sql)
+        .fetch_one(pool).await.map_err(|e| AppError::SqlxError(e, ins_sql.to_string()))
+}
+
+
+async fn insert_skipped_row(data_date: &str, snapshot: &str, stage: &str, step: i32, sql: &str,
+                            rows: i64, pool: &Pool<Postgres>) -> Result<(), AppError> {
+
+    let ins_sql = r#"insert into ad.run_log (data_date, snapshot, stage, step, step_sql, started, ended, rows_affected, status)
+                 values ($1, $2, $3, $4, nullif($5, ''), now(), now(), $6, 'skipped')"#;
+    sqlx::query(ins_sql).bind(data_date).bind(snapshot).bind(stage).bind(step).bind(sql).bind(rows)
+        .execute(pool).await.map_err(|e| AppError::SqlxError(e, ins_sql.to_string()))?;
+    Ok(())
+}
+
+
+async fn complete_row(id: i32, rows: u64, pool: &Pool<Postgres>) -> Result<(), AppError> {
+
+    let upd_sql = r#"update ad.run_log set ended = now(), rows_affected = $2, status = 'completed'
+                 where id = $1"#;
+    sqlx::query(upd_sql).bind(id).bind(rows as i64)
+        .execute(pool).await.map_err(|e| AppError::SqlxError(e, upd_sql.to_string()))?;
+    Ok(())
+}
+
+
+async fn fail_row(id: i32, error_msg: &str, pool: &Pool<Postgres>) -> Result<(), AppError> {
+
+    let upd_sql = r#"update ad.run_log set ended = now(), status = 'failed', error_msg = $2
+                 where id = $1"#;
+    sqlx::query(upd_sql).bind(id).bind(error_msg)
+        .execute(pool).await.map_err(|e| AppError::SqlxError(e, upd_sql.to_string()))?;
+    Ok(())
+}
+
+
+fn create_run_log_sql <'a>() -> &'a str {
+    r#"SET client_min_messages TO WARNING;
+    create schema if not exists ad;
+    create table if not exists ad.run_log(
+        id                     INT             PRIMARY KEY GENERATED ALWAYS AS IDENTITY (start with 1 increment by 1)
+      , data_date              VARCHAR         NOT NULL
+      , snapshot               VARCHAR         NOT NULL
+      , stage                  VARCHAR         NOT NULL
+      , step                   INT             NOT NULL
+      , step_sql               VARCHAR         NULL
+      , started                TIMESTAMPTZ     NOT NULL
+      , ended                  TIMESTAMPTZ     NULL
+      , rows_affected          BIGINT          NULL
+      , status                 VARCHAR         NOT NULL
+      , error_msg              VARCHAR         NULL
+    );
+    create index if not exists run_log_key on ad.run_log(data_date, snapshot, stage, step);
+    SET client_min_messages TO NOTICE;"#
+}
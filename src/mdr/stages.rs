@@ -0,0 +1,590 @@
+/***************************************************************************
+ * The registry of mdr import stages. Each stage declares the tables it reads
+ * and writes, any temporary tables it creates and drops before it ends, and
+ * the other stages that must have been run before it.
+ * The registry order is also the run order, and is a valid ordering for
+ * the dependencies. A selection of stages is resolved into a plan, that
+ * either pulls in any missing prerequisites or assumes that they have been
+ * run already, in which case their tables are checked before anything runs.
+ * Stages that neither need each other nor share any tables are independent,
+ * and can be run at the same time.
+ *
+ * Checkpoints are named points at which a set of ad tables can be saved, and
+ * later restored instead of re-running the processing that produced them.
+ * Every stage that writes ad tables has a checkpoint at its end, with the
+ * stage's name, and some stages have additional checkpoints within them.
+ ***************************************************************************/
+
+use sqlx::{Pool, Postgres};
+use crate::AppError;
+
+pub struct Stage {
+    pub name: &'static str,
+    pub reads: &'static [&'static str],
+    pub writes: &'static [&'static str],
+    pub temps: &'static [&'static str],
+    pub needs: &'static [&'static str],
+}
+
+pub static STAGES: [Stage; 21] = [
+
+    // restore replaces the ctgov schema with the contents of an AACT snapshot.
+    // It is opt-in, i.e. not included when all stages are selected by default.
+
+    Stage { name: "restore",
+            reads: &[],
+            writes: &["ctgov.studies"],
+            temps: &[],
+            needs: &[] },
+
+    // simplify drops many other (results related) ctgov tables,
+    // but only those read by the mdr stages are listed here. Before dropping the
+    // browse tables it keeps their MeSH terms, as ctgov.mesh_conditions / interventions.
+
+    Stage { name: "simplify",
+            reads: &["ctgov.studies"],
+            writes: &["ctgov.studies", "ctgov.browse_conditions", "ctgov.browse_interventions", "ctgov.detailed_descriptions",
+                      "ctgov.mesh_conditions", "ctgov.mesh_interventions"],
+            temps: &[],
+            needs: &["restore"] },
+    Stage { name: "studies",
+            reads: &["ctgov.studies", "ctgov.brief_summaries", "ctgov.calculated_values", "ctgov.eligibilities", "ctgov.ipd_information_types"],
+            writes: &["ad.studies", "ctgov.assoc_ipd_docs"],
+            temps: &[],
+            needs: &[] },
+    Stage { name: "titles",
+            reads: &["ctgov.studies"],
+            writes: &["ad.study_titles"],
+            temps: &[],
+            needs: &[] },
+    Stage { name: "idents",
+            reads: &["ctgov.studies", "ctgov.id_information", "ctgov.sponsors", "ad.study_titles"],
+            writes: &["ad.study_identifiers", "ad.temp_idents"],
+            temps: &["ad.temp_adds", "ad.temp_split_numbers", "ad.temp_id_dups", "ad.temp_id_dup_mins"],
+            needs: &["titles"] },
+    Stage { name: "locations",
+            reads: &["ctgov.facilities"],
+            writes: &["ad.study_locations", "ad.locs"],
+            temps: &[],
+            needs: &[] },
+    Stage { name: "countries",
+            reads: &[],
+            writes: &["ad.study_countries"],
+            temps: &[],
+            needs: &[] },
+    Stage { name: "orgs",
+            reads: &["ctgov.sponsors"],
+            writes: &["ad.study_organisations"],
+            temps: &["ad.temp_org_lookup"],
+            needs: &[] },
+    Stage { name: "people",
+            reads: &["ctgov.overall_officials", "ctgov.responsible_parties", "ctgov.central_contacts"],
+            writes: &["ad.study_people"],
+            temps: &[],
+            needs: &[] },
+    Stage { name: "features",
+            reads: &["ctgov.studies", "ctgov.designs"],
+            writes: &["ad.study_features"],
+            temps: &["ad.temp_feature_map"],
+            needs: &[] },
+    Stage { name: "topics",
+            reads: &["ctgov.interventions", "ctgov.keywords", "ctgov.mesh_interventions"],
+            writes: &["ad.study_topics"],
+            temps: &[],
+            needs: &["simplify"] },
+    Stage { name: "conditions",
+            reads: &["ctgov.conditions", "ctgov.mesh_conditions"],
+            writes: &["ad.study_conditions"],
+            temps: &[],
+            needs: &["simplify"] },
+    Stage { name: "relationships",
+            reads: &["ctgov.studies", "ctgov.brief_summaries", "ad.study_identifiers", "ad.temp_idents"],
+            writes: &["ad.study_relationships"],
+            temps: &["ad.temp_rels"],
+            needs: &["idents"] },
+    Stage { name: "references",
+            reads: &["ctgov.study_references"],
+            writes: &["ad.study_references"],
+            temps: &[],
+            needs: &[] },
+    Stage { name: "links",
+            reads: &["ctgov.links", "ctgov.documents"],
+            writes: &["ad.study_links"],
+            temps: &[],
+            needs: &[] },
+    Stage { name: "ipd_available",
+            reads: &["ctgov.provided_documents", "ctgov.ipd_information_types", "ctgov.studies"],
+            writes: &["ad.study_ipd_available"],
+            temps: &[],
+            needs: &[] },
+
+    // The registry entry and results summary objects of each study are generated from
+    // ad.studies, and its document and IPD objects from the ctgov tables, via ad.temp_doc_objects.
+    // Their instances, titles and dates, and the datasets of the IPD, come from the objects.
+
+    Stage { name: "data_objects",
+            reads: &["ad.studies", "ctgov.provided_documents", "ctgov.ipd_information_types", "ctgov.studies"],
+            writes: &["ad.data_objects", "ad.temp_doc_objects"],
+            temps: &[],
+            needs: &["studies"] },
+    Stage { name: "datasets",
+            reads: &["ad.temp_doc_objects"],
+            writes: &["ad.object_datasets"],
+            temps: &[],
+            needs: &["data_objects"] },
+    Stage { name: "obj_instances",
+            reads: &["ad.data_objects", "ad.temp_doc_objects"],
+            writes: &["ad.object_instances"],
+            temps: &[],
+            needs: &["data_objects"] },
+    Stage { name: "obj_titles",
+            reads: &["ad.data_objects"],
+            writes: &["ad.object_titles"],
+            temps: &[],
+            needs: &["data_objects"] },
+    Stage { name: "obj_dates",
+            reads: &["ad.data_objects", "ad.studies"],
+            writes: &["ad.object_dates"],
+            temps: &[],
+            needs: &["data_objects"] },
+];
+
+
+// Stages only run if explicitly selected.
+
+pub static OPT_IN_STAGES: [&str; 1] = ["restore"];
+
+
+pub struct Checkpoint {
+    pub name: &'static str,
+    pub stage: &'static str,
+    pub tables: &'static [&'static str],
+}
+
+// Checkpoints within stages, in addition to those at the end of each stage.
+
+pub static CHECKPOINTS: [Checkpoint; 2] = [
+
+    // After the nct ids and the bulk of the identifier cleaning and coding,
+    // before the identification of company identifiers.
+
+    Checkpoint { name: "idents_base",
+                 stage: "idents",
+                 tables: &["ad.temp_idents", "ad.study_identifiers"] },
+
+    // After the initial linguistic processing of facility names.
+
+    Checkpoint { name: "locs_base",
+                 stage: "locations",
+                 tables: &["ad.locs"] },
+];
+
+
+pub struct StagePlan {
+    pub stages: Vec<&'static Stage>,    // the stages to run, in run order
+    pub assumed: Vec<&'static Stage>,   // prerequisites not being run, assumed to have been run already
+    pub save_points: Vec<&'static str>,     // checkpoints at which tables are to be saved
+    pub restore_points: Vec<&'static str>,  // checkpoints from which tables are restored, instead of being processed
+}
+
+impl StagePlan {
+
+    pub fn stage_names(&self) -> Vec<&'static str> {
+        self.stages.iter().map(|s| s.name).collect()
+    }
+
+    pub fn assumed_names(&self) -> Vec<&'static str> {
+        self.assumed.iter().map(|s| s.name).collect()
+    }
+
+    pub fn saves(&self, point: &str) -> bool {
+        self.save_points.contains(&point)
+    }
+
+    pub fn restores(&self, point: &str) -> bool {
+        self.restore_points.contains(&point)
+    }
+}
+
+
+pub fn get_stage(name: &str) -> Option<&'static Stage> {
+    STAGES.iter().find(|s| s.name == name)
+}
+
+
+pub fn stage_names() -> Vec<&'static str> {
+    STAGES.iter().map(|s| s.name).collect()
+}
+
+
+pub fn must_follow(stage: &Stage, earlier: &Stage) -> bool {
+
+    // True if the stage cannot start until the earlier stage (in run order) has
+    // finished, because it needs it, or because one writes a table the other uses.
+    // A stage that need not follow any unfinished earlier stage can be started.
+
+    stage.needs.contains(&earlier.name)
+        || earlier.writes.iter().any(|t| stage.reads.contains(t) || stage.writes.contains(t))
+        || stage.writes.iter().any(|t| earlier.reads.contains(t))
+        || stage.temps.iter().any(|t| earlier.temps.contains(t))
+}
+
+
+pub fn select_stages(stages: &str, exclude_stages: &str, with_deps: bool) -> Result<StagePlan, AppError> {
+
+    // An empty include list means all stages are included, other than
+    // those that are opt-in. Excluded stages
+    // are then removed. If with_deps is true the prerequisites of the selected
+    // stages are added (unless explicitly excluded), otherwise they are listed
+    // as being assumed. In either case stages are returned in run order,
+    // whatever the order in which they were listed.
+
+    let included = split_stage_list(stages)?;
+    let excluded = split_stage_list(exclude_stages)?;
+
+    let mut selected: Vec<&str> = STAGES.iter()
+        .filter(|s| included.contains(&s.name.to_string())
+                    || (included.is_empty() && !OPT_IN_STAGES.contains(&s.name)))
+        .filter(|s| !excluded.contains(&s.name.to_string()))
+        .map(|s| s.name)
+        .collect();
+
+    // Work back through the registry, so that prerequisites of
+    // prerequisites are also found (needs always precede a stage).
+
+    let mut assumed: Vec<&str> = Vec::new();
+    for stage in STAGES.iter().rev() {
+        if selected.contains(&stage.name) || assumed.contains(&stage.name) {
+            for need in stage.needs {
+                if selected.contains(need) || assumed.contains(need) {
+                    continue;
+                }
+                if with_deps && !excluded.contains(&need.to_string()) {
+                    selected.push(need);
+                }
+                else {
+                    assumed.push(need);
+                }
+            }
+        }
+    }
+
+    Ok(StagePlan {
+        stages: STAGES.iter().filter(|s| selected.contains(&s.name)).collect(),
+        assumed: STAGES.iter().filter(|s| assumed.contains(&s.name)).collect(),
+        save_points: Vec::new(),
+        restore_points: Vec::new(),
+    })
+}
+
+
+pub fn add_stage(plan: &mut StagePlan, name: &str) {
+
+    // Adds a stage (normally an opt-in stage) to a resolved plan, in run order.
+
+    if let Some(stage) = get_stage(name) && !plan.stages.iter().any(|s| s.name == name) {
+        plan.assumed.retain(|s| s.name != name);
+        plan.stages.push(stage);
+        plan.stages.sort_by_key(|s| STAGES.iter().position(|r| r.name == s.name));
+    }
+}
+
+
+pub fn checkpoint_names() -> Vec<&'static str> {
+
+    // In run order, with any checkpoints within a stage before that at its end.
+
+    let mut names = Vec::new();
+    for stage in STAGES.iter() {
+        names.extend(CHECKPOINTS.iter().filter(|c| c.stage == stage.name).map(|c| c.name));
+        if stage.writes.iter().any(|t| t.starts_with("ad.")) {
+            names.push(stage.name);
+        }
+    }
+    names
+}
+
+
+pub fn checkpoint_tables(point: &str) -> Vec<&'static str> {
+
+    // The ad tables saved and restored at a checkpoint.
+
+    match CHECKPOINTS.iter().find(|c| c.name == point) {
+        Some(c) => c.tables.to_vec(),
+        None => match get_stage(point) {
+            Some(s) => s.writes.iter().filter(|t| t.starts_with("ad.")).copied().collect(),
+            None => Vec::new(),
+        },
+    }
+}
+
+
+fn checkpoint_stage(point: &str) -> &str {
+    CHECKPOINTS.iter().find(|c| c.name == point).map(|c| c.stage).unwrap_or(point)
+}
+
+
+pub fn select_checkpoints(plan: &mut StagePlan, save_points: &str, restore_points: &str) -> Result<(), AppError> {
+
+    // 'all' can be used to save at every checkpoint in the stages being run.
+    // Checkpoints are only meaningful if their stage is being run.
+
+    let planned: Vec<&str> = checkpoint_names().into_iter()
+        .filter(|c| plan.stage_names().contains(&checkpoint_stage(c)))
+        .collect();
+
+    plan.save_points = if save_points.trim().eq_ignore_ascii_case("all") {
+        planned.clone()
+    }
+    else {
+        split_checkpoint_list(save_points, &planned)?
+    };
+    plan.restore_points = split_checkpoint_list(restore_points, &planned)?;
+
+    Ok(())
+}
+
+
+fn split_checkpoint_list(point_list: &str, planned: &[&'static str]) -> Result<Vec<&'static str>, AppError> {
+
+    let names: Vec<String> = point_list.split(',')
+        .map(|s| s.trim().to_lowercase())
+        .filter(|s| !s.is_empty())
+        .collect();
+
+    let mut points = Vec::new();
+    for name in names.iter() {
+        match checkpoint_names().into_iter().find(|c| c == name) {
+            Some(c) if planned.contains(&c) => points.push(c),
+            Some(c) => return Result::Err(AppError::CheckpointError(
+                    format!("Checkpoint '{}' is in stage '{}', which is not being run", c, checkpoint_stage(c)),
+                    "Include the stage in the selected stages".to_string())),
+            None => return Result::Err(AppError::CheckpointError(
+                    format!("Unrecognised checkpoint name: {}", name),
+                    format!("Valid checkpoint names are: {}", checkpoint_names().join(", ")))),
+        }
+    }
+    Ok(points)
+}
+
+
+fn split_stage_list(stage_list: &str) -> Result<Vec<String>, AppError> {
+
+    let names: Vec<String> = stage_list.split(',')
+        .map(|s| s.trim().to_lowercase())
+        .filter(|s| !s.is_empty())
+        .collect();
+
+    let unknown: Vec<&str> = names.iter()
+        .filter(|n| get_stage(n).is_none())
+        .map(|n| n.as_str())
+        .collect();
+
+    if !unknown.is_empty() {
+        return Result::Err(AppError::StageSelectionError(
+            format!("Unrecognised mdr stage name(s): {}", unknown.join(", ")),
+            format!("Valid stage names are: {}", stage_names().join(", "))));
+    }
+
+    Ok(names)
+}
+
+
+pub fn describe_plan(plan: &StagePlan) -> Vec<String> {
+
+    // Returns the lines of a readable description of the plan.
+
+    let mut lines = vec![format!("mdr import plan: {} stage(s) to run", plan.stages.len())];
+    for (i, stage) in plan.stages.iter().enumerate() {
+        lines.push(format!("{:>3}. {}", i + 1, stage.name));
+        if !stage.needs.is_empty() {
+            lines.push(format!("       needs:  {}", stage.needs.join(", ")));
+        }
+        if !stage.reads.is_empty() {
+            lines.push(format!("       reads:  {}", stage.reads.join(", ")));
+        }
+        lines.push(format!("       writes: {}", stage.writes.join(", ")));
+        if !stage.temps.is_empty() {
+            lines.push(format!("       temps:  {}", stage.temps.join(", ")));
+        }
+    }
+    if !plan.assumed.is_empty() {
+        lines.push(format!("assumed to have been run already (their tables must exist): {}",
+                            plan.assumed_names().join(", ")));
+    }
+    if !plan.restore_points.is_empty() {
+        lines.push(format!("restored from checkpoint(s), rather than processed: {}", plan.restore_points.join(", ")));
+    }
+    if !plan.save_points.is_empty() {
+        lines.push(format!("tables saved at checkpoint(s): {}", plan.save_points.join(", ")));
+    }
+    lines
+}
+
+
+pub async fn check_assumed_stages(plan: &StagePlan, pool: &Pool<Postgres>) -> Result<(), AppError> {
+
+    // For each assumed prerequisite, the tables it writes that are read by a
+    // stage being run must already be present (if none of its tables are read
+    // directly, all of the tables it writes are checked).
+
+    let mut missing: Vec<String> = Vec::new();
+
+    for prereq in plan.assumed.iter() {
+        let mut tables: Vec<&str> = plan.stages.iter()
+            .filter(|s| s.needs.contains(&prereq.name))
+            .flat_map(|s| s.reads.iter())
+            .filter(|t| prereq.writes.contains(t))
+            .copied()
+            .collect();
+        if tables.is_empty() {
+            tables = prereq.writes.to_vec();
+        }
+
+        for table in tables {
+            let sql = format!("select to_regclass('{}') is not null", table);
+            let exists: bool = sqlx::query_scalar(&sql).fetch_one(pool)
+                .await.map_err(|e| AppError::SqlxError(e, sql.clone()))?;
+            if !exists {
+                missing.push(format!("{} (from stage '{}')", table, prereq.name));
+            }
+        }
+    }
+
+    if !missing.is_empty() {
+        let dependents: Vec<&str> = plan.stages.iter()
+                .filter(|s| s.needs.iter().any(|n| plan.assumed_names().contains(n)))
+                .map(|s| s.name).collect();
+        return Result::Err(AppError::StageSelectionError(
+            format!("Prerequisites of stage(s) {} have not been run - missing table(s): {}",
+                     dependents.join(", "), missing.join(", ")),
+            "Include the prerequisite stages, or use --with-deps to add them automatically".to_string()));
+    }
+
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_registry_order_respects_needs() {
+        for (i, stage) in STAGES.iter().enumerate() {
+            for need in stage.needs {
+                let pos = STAGES.iter().position(|s| s.name == *need);
+                assert!(pos.is_some(), "unknown prerequisite {} for {}", need, stage.name);
+                assert!(pos.unwrap() < i, "prerequisite {} must precede {}", need, stage.name);
+            }
+        }
+    }
+
+    #[test]
+    fn check_empty_selection_gives_all_stages() {
+        let plan = select_stages("", "", false).unwrap();
+        assert_eq!(plan.stages.len(), STAGES.len() - OPT_IN_STAGES.len());
+        assert_eq!(plan.assumed_names(), vec!["restore"]);
+    }
+
+    #[test]
+    fn check_opt_in_stage_can_be_added() {
+        let mut plan = select_stages("", "", false).unwrap();
+        add_stage(&mut plan, "restore");
+        assert_eq!(plan.stages.len(), STAGES.len());
+        assert_eq!(plan.stages[0].name, "restore");
+        assert!(plan.assumed.is_empty());
+
+        let plan = select_stages("restore,simplify", "", false).unwrap();
+        assert_eq!(plan.stage_names(), vec!["restore", "simplify"]);
+    }
+
+    #[test]
+    fn check_missing_prerequisite_is_assumed() {
+        let plan = select_stages("idents", "", false).unwrap();
+        assert_eq!(plan.stage_names(), vec!["idents"]);
+        assert_eq!(plan.assumed_names(), vec!["titles"]);
+    }
+
+    #[test]
+    fn check_with_deps_pulls_in_prerequisite() {
+        let plan = select_stages("idents,locations", "", true).unwrap();
+        assert_eq!(plan.stage_names(), vec!["titles", "idents", "locations"]);
+        assert!(plan.assumed.is_empty());
+    }
+
+    #[test]
+    fn check_excluded_prerequisite_is_not_pulled_in() {
+        let plan = select_stages("idents", "titles", true).unwrap();
+        assert_eq!(plan.stage_names(), vec!["idents"]);
+        assert_eq!(plan.assumed_names(), vec!["titles"]);
+    }
+
+    #[test]
+    fn check_checkpoint_names_and_tables() {
+        let names = checkpoint_names();
+        assert!(!names.contains(&"simplify"));
+        let base = names.iter().position(|c| *c == "idents_base").unwrap();
+        let idents = names.iter().position(|c| *c == "idents").unwrap();
+        assert!(base < idents);
+        assert_eq!(checkpoint_tables("locs_base"), vec!["ad.locs"]);
+        assert_eq!(checkpoint_tables("titles"), vec!["ad.study_titles"]);
+    }
+
+    #[test]
+    fn check_checkpoint_selection() {
+        let mut plan = select_stages("titles,idents", "", false).unwrap();
+        select_checkpoints(&mut plan, "all", "idents_base").unwrap();
+        assert_eq!(plan.save_points, vec!["titles", "idents_base", "idents"]);
+        assert!(plan.restores("idents_base"));
+        assert!(!plan.restores("idents"));
+
+        let mut plan = select_stages("titles", "", false).unwrap();
+        assert!(select_checkpoints(&mut plan, "", "locs_base").is_err());
+        assert!(select_checkpoints(&mut plan, "titels", "").is_err());
+    }
+
+    #[test]
+    fn check_checkpoint_tables_outlive_their_stage() {
+
+        // A temporary table is dropped before its stage ends, so cannot be
+        // saved at a checkpoint, or be used by any other stage.
+
+        for stage in STAGES.iter() {
+            for table in stage.temps {
+                assert!(table.starts_with("ad."), "temporary table {} of {} is not an ad table", table, stage.name);
+                assert!(STAGES.iter().all(|s| !s.reads.contains(table) && !s.writes.contains(table)),
+                        "temporary table {} of {} is read or written by a stage", table, stage.name);
+            }
+        }
+        for point in checkpoint_names() {
+            for table in checkpoint_tables(point) {
+                assert!(STAGES.iter().all(|s| !s.temps.contains(&table)),
+                        "{} is a temporary table, but is saved at checkpoint {}", table, point);
+            }
+        }
+        assert!(get_stage("orgs").unwrap().temps.contains(&"ad.temp_org_lookup"));
+        assert!(!checkpoint_tables("orgs").contains(&"ad.temp_org_lookup"));
+    }
+
+    #[test]
+    fn check_unknown_stage_name_is_an_error() {
+        let res = select_stages("titles,locs", "", false);
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn check_stages_that_must_follow_others() {
+        let stage = |name| get_stage(name).unwrap();
+        assert!(must_follow(stage("idents"), stage("titles")));
+        assert!(must_follow(stage("studies"), stage("simplify")));
+        assert!(must_follow(stage("titles"), stage("simplify")));
+        assert!(must_follow(stage("conditions"), stage("simplify")));
+        assert!(must_follow(stage("topics"), stage("simplify")));
+        assert!(!must_follow(stage("titles"), stage("studies")));
+        assert!(!must_follow(stage("locations"), stage("titles")));
+        assert!(!must_follow(stage("orgs"), stage("locations")));
+        assert!(must_follow(stage("obj_dates"), stage("data_objects")));
+        assert!(!must_follow(stage("obj_titles"), stage("obj_instances")));
+    }
+}
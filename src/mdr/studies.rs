@@ -1,12 +1,11 @@
-use super::utils;
 
-use sqlx::{Pool, Postgres};
+use super::executor::SqlExecutor;
 use crate::AppError;
 use log::info;
 use chrono::Datelike;
 
  
-pub async fn build_studies_table (pool: &Pool<Postgres>) -> Result<(), AppError> {  
+pub async fn build_studies_table (ex: &SqlExecutor) -> Result<(), AppError> {  
 
     let sql = r#"SET client_min_messages TO WARNING; 
     create schema if not exists ad;
@@ -46,7 +45,7 @@ pub async fn build_studies_table (pool: &Pool<Postgres>) -> Result<(), AppError>
 	);
 	CREATE INDEX studies_sid ON ad.studies(sd_sid);"#;
 
-	utils::execute_sql(sql, pool).await?;
+	ex.execute(sql).await?;
     info!("studies table (re)created");
     
     Ok(())
@@ -54,39 +53,39 @@ pub async fn build_studies_table (pool: &Pool<Postgres>) -> Result<(), AppError>
 }
 
 
-pub async fn load_studies_data (data_date: &str, max_id: u64, pool: &Pool<Postgres>) -> Result<(), AppError> {  
+pub async fn load_studies_data (data_date: &str, max_id: u64, ex: &SqlExecutor) -> Result<(), AppError> {  
 
     let chunk_size = 2000000;
-    let rec_num = utils::execute_phased_transfer(core_study_data_sql(), max_id, chunk_size, " where ", "core study records", "ad.studies", pool).await?;
+    let rec_num = ex.phased_transfer(core_study_data_sql(), max_id, chunk_size, " where ", "core study records", "ad.studies").await?;
 
 	let chunk_size = 150000;
-    utils::execute_phased_update(registries_update_sql(), rec_num, chunk_size, "registries identified", pool).await?;
-    utils::execute_phased_update(status_sql(), rec_num, chunk_size, "statuses inserted", pool).await?;
-    utils::execute_phased_update(last_known_status_sql(), rec_num, chunk_size, "statuses added using last known statuses", pool).await?;
-    utils::execute_phased_update(descriptions_sql(), rec_num, chunk_size, "study descriptions added", pool).await?;
-
-    utils::execute_phased_update(min_and_max_age_sql(), rec_num, chunk_size, "min / max ages inserted", pool).await?;
-    utils::execute_phased_update(age_group_child_sql(), rec_num, chunk_size, "child studies identified", pool).await?;
-    utils::execute_phased_update(age_group_adult_sql(), rec_num, chunk_size, "adult studies identified", pool).await?;
-    utils::execute_phased_update(age_group_elderly_sql(), rec_num, chunk_size, "elderly studies identified", pool).await?;
-    utils::execute_phased_update(gender_flag_sql(), rec_num, chunk_size, "gender eligibilities inserted", pool).await?;
-
-	utils::execute_phased_update(update_start_date_types_sql(), rec_num, chunk_size, "start date types updated", pool).await?;
-	utils::execute_phased_update(update_comp_date_types_sql(), rec_num, chunk_size, "comp date types updated", pool).await?;
-	utils::execute_phased_update(completed_status_1_sql(), rec_num, chunk_size, "comp statuses updated using result dates", pool).await?;
-	utils::execute_phased_update(completed_status_2_sql(), rec_num, chunk_size, "comp statuses updated using comp dates", pool).await?;
+    ex.phased_update(registries_update_sql(), rec_num, chunk_size, "registries identified").await?;
+    ex.phased_update(status_sql(), rec_num, chunk_size, "statuses inserted").await?;
+    ex.phased_update(last_known_status_sql(), rec_num, chunk_size, "statuses added using last known statuses").await?;
+    ex.phased_update(descriptions_sql(), rec_num, chunk_size, "study descriptions added").await?;
+
+    ex.phased_update(min_and_max_age_sql(), rec_num, chunk_size, "min / max ages inserted").await?;
+    ex.phased_update(age_group_child_sql(), rec_num, chunk_size, "child studies identified").await?;
+    ex.phased_update(age_group_adult_sql(), rec_num, chunk_size, "adult studies identified").await?;
+    ex.phased_update(age_group_elderly_sql(), rec_num, chunk_size, "elderly studies identified").await?;
+    ex.phased_update(gender_flag_sql(), rec_num, chunk_size, "gender eligibilities inserted").await?;
+
+	ex.phased_update(update_start_date_types_sql(), rec_num, chunk_size, "start date types updated").await?;
+	ex.phased_update(update_comp_date_types_sql(), rec_num, chunk_size, "comp date types updated").await?;
+	ex.phased_update(completed_status_1_sql(), rec_num, chunk_size, "comp statuses updated using result dates").await?;
+	ex.phased_update(completed_status_2_sql(), rec_num, chunk_size, "comp statuses updated using comp dates").await?;
 	let sql_string = completed_status_3_sql();  // Function returns a string rather than &str
-	utils::execute_phased_update(&sql_string, rec_num, chunk_size, "comp statuses updated using est. comp dates on older studies", pool).await?;
+	ex.phased_update(&sql_string, rec_num, chunk_size, "comp statuses updated using est. comp dates on older studies").await?;
 
-	utils::execute_phased_update(ipd_1_sql(), rec_num, chunk_size, "ipd basics added", pool).await?;
-	utils::execute_sql(ipd_2_sql(), pool).await?;
-	utils::execute_phased_update(ipd_3_sql(), rec_num, chunk_size, "ipd details added", pool).await?;
-	utils::execute_sql(ipd_4_sql(), pool).await?;
+	ex.phased_update(ipd_1_sql(), rec_num, chunk_size, "ipd basics added").await?;
+	ex.execute(ipd_2_sql()).await?;
+	ex.phased_update(ipd_3_sql(), rec_num, chunk_size, "ipd details added").await?;
+	ex.execute(ipd_4_sql()).await?;
 
 	let sql_string = max_date_sql(data_date);  // Function returns a string rather than &str
-	utils::execute_phased_update(&sql_string, rec_num, chunk_size, "dates of data set inserted", pool).await?;
+	ex.phased_update(&sql_string, rec_num, chunk_size, "dates of data set inserted").await?;
 
-	utils::vacuum_table("studies", pool).await?;
+	ex.vacuum_table("studies").await?;
     
     Ok(())
 
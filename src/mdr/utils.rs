@@ -1,85 +1,209 @@
-use sqlx::{postgres::PgQueryResult, Pool, Postgres};
+use super::executor::SqlExecutor;
+use super::dry_run;
 use crate::AppError;
 use log::info;
 
-pub async fn execute_sql(sql: &str, pool: &Pool<Postgres>) -> Result<PgQueryResult, AppError> {
-    
-    sqlx::raw_sql(&sql).execute(pool)
-        .await.map_err(|e| AppError::SqlxError(e, sql.to_string()))
+/***************************************************************************
+ * Checkpoints. The tables saved at a checkpoint are copied to a schema of 
+ * their own (ckpt_<checkpoint name>), and the checkpoint is recorded in
+ * ad.checkpoints. Restoring a checkpoint recreates the ad tables from those
+ * copies, including their indexes, and resets any identity columns so that 
+ * further inserts continue from the restored ids. 
+ ***************************************************************************/
+
+pub struct CheckpointInfo {
+    pub name: String,
+    pub tables: String,
+    pub data_date: String,
+    pub saved_on: String,
 }
 
 
-pub async fn execute_phased_transfer(sql: &str, max_id: u64, chunk_size: u64, sql_linker: &str, rec_type: &str, rec_dest: &str, pool: &Pool<Postgres>) -> Result<u64, AppError> {
-    
-    let mut rec_num: u64 = 0;
-    let mut start_num: u64 = 0;
+pub async fn create_checkpoints_table(ex: &SqlExecutor) -> Result<(), AppError> {
 
-    while start_num <= max_id {
+    // Also done when each checkpoint is saved, but done first when stages may be run 
+    // at the same time, as concurrent 'create table if not exists' statements can fail.
 
-        let end_num = start_num + chunk_size;
-        let chunk_sql = format!("c.nct_id >= 'NCT{:0>8}' and c.nct_id < 'NCT{:0>8}';", start_num, end_num);
-        let chsql = sql.to_string() + sql_linker + &chunk_sql;
-        
-        let res = sqlx::raw_sql(&chsql).execute(pool)
-            .await.map_err(|e| AppError::SqlxError(e, chsql.to_string()))?;
-        let recs = res.rows_affected();
-        rec_num += recs;
-        info!("{} {} transferred to {}, {}", recs, rec_type, rec_dest, chunk_sql);
+    ex.execute(create_checkpoints_table_sql()).await?;
+    Ok(())
+}
+
+
+pub async fn save_checkpoint(name: &str, tables: &[&str], data_date: &str, ex: &SqlExecutor) -> Result<(), AppError> {
+
+    let schema = checkpoint_schema(name)?;
+    for table in tables {
+        check_ad_table(table)?;
+    }
 
-        start_num = end_num;
+    ex.execute(create_checkpoints_table_sql()).await?;
+    let sql = format!(r#"SET client_min_messages TO WARNING;
+        drop schema if exists {} cascade;
+        create schema {};
+        delete from ad.checkpoints where name = '{}';"#, schema, schema, name);
+    ex.execute(&sql).await?;
+
+    for table in tables {
+        let copy = format!("{}.{}", schema, &table[3..]);
+        let sql = format!(r#"create table {} (like {} including all);
+            insert into {} overriding system value select * from {};"#, copy, table, copy, table);
+        let res = ex.execute(&sql).await?;
+        info!("{} records saved from {} at checkpoint '{}'", res.rows_affected(), table, name);
     }
 
-    info!("{} {} transferred in total", rec_num, rec_type);
+    let sql = format!(r#"insert into ad.checkpoints (name, tables, data_date, saved_on)
+        values ('{}', '{}', '{}', now());
+        SET client_min_messages TO NOTICE;"#, name, tables.join(","), data_date);
+    ex.execute(&sql).await?;
     info!("");
 
-    Ok(rec_num)
+    Ok(())
 }
 
 
-pub async fn execute_phased_update(sql: &str, rec_num: u64, chunk_size: u64, fback: &str, pool: &Pool<Postgres>) -> Result<(), AppError> {
-
-    let mut total_recs = 0;
-
-    for i in (0..rec_num).step_by(chunk_size.try_into().unwrap()) {
-        
-        let start_num = i + 1000001;
-        let mut end_num = start_num + chunk_size - 1;
-        if end_num > rec_num + 1000001 {
-            end_num = rec_num + 1000000;
+pub async fn restore_checkpoint(name: &str, tables: &[&str], ex: &SqlExecutor) -> Result<(), AppError> {
+
+    // The checkpoint must exist and include all the tables required
+    // (not checked in a dry run, as that needs the database).
+
+    let schema = checkpoint_schema(name)?;
+    let mut saved_on = "-".to_string();
+    if !dry_run::is_active() {
+        let saved = match get_checkpoints(ex).await?.into_iter().find(|c| c.name == name) {
+            Some(c) => c,
+            None => return Result::Err(AppError::CheckpointError(
+                        format!("Checkpoint '{}' has not been saved", name),
+                        format!("Run the stage with --checkpoint {} first", name))),
+        };
+        let saved_tables: Vec<&str> = saved.tables.split(',').collect();
+        for table in tables {
+            if !saved_tables.contains(table) {
+                return Result::Err(AppError::CheckpointError(
+                        format!("Checkpoint '{}' does not include table {}", name, table),
+                        format!("Save the checkpoint again, with --checkpoint {}", name)));
+            }
         }
+        saved_on = saved.saved_on;
+    }
+
+    // The tables are restored together, in a single transaction, so that
+    // a failure cannot leave only some of them replaced.
 
-        let chunk_sql = format!("s.id >= {} and s.id <= {};", start_num, end_num);
-        let chsql = sql.to_string() + " and " + &chunk_sql;
-        let res = sqlx::raw_sql(&chsql).execute(pool)
-            .await.map_err(|e| AppError::SqlxError(e, chsql.to_string()))?;
-        info!("{} {}, {}", res.rows_affected(), fback, chunk_sql);
-        total_recs += res.rows_affected();
+    ex.begin_transaction().await?;
+    match restore_tables(&schema, name, tables, &saved_on, ex).await {
+        Ok(()) => ex.commit().await?,
+        Err(e) => {
+            ex.rollback().await?;
+            return Err(e);
+        },
     }
 
-    info!("{} records affected in total", total_recs);
+    ex.execute("SET client_min_messages TO NOTICE;").await?;
     info!("");
 
     Ok(())
 }
 
 
+async fn restore_tables(schema: &str, name: &str, tables: &[&str], saved_on: &str, ex: &SqlExecutor) -> Result<(), AppError> {
+
+    for table in tables {
+        let copy = format!("{}.{}", schema, &table[3..]);
+        let sql = format!(r#"SET client_min_messages TO WARNING;
+            drop table if exists {};
+            create table {} (like {} including all);
+            insert into {} overriding system value select * from {};"#, table, table, copy, table, copy);
+        let res = ex.execute(&sql).await?;
+        reset_identity_columns(table, ex).await?;
+        info!("{} records restored to {} from checkpoint '{}' (saved {})", res.rows_affected(), table, name, saved_on);
+    }
+
+    Ok(())
+}
+
+
+pub async fn get_checkpoints(ex: &SqlExecutor) -> Result<Vec<CheckpointInfo>, AppError> {
 
-pub async fn vacuum_table (table: &str, pool: &Pool<Postgres>) -> Result<(), AppError> {  
-    
-    // For studies table, normally leave until final updates...after iec_flag calculated
+    let sql = "select to_regclass('ad.checkpoints') is not null";
+    let exists: bool = sqlx::query_scalar(sql).fetch_one(ex.pool())
+        .await.map_err(|e| AppError::SqlxError(e, sql.to_string()))?;
+    if !exists {
+        return Ok(Vec::new());
+    }
+
+    let sql = r#"select name, tables, data_date, to_char(saved_on, 'YYYY-MM-DD HH24:MI:SS')
+                 from ad.checkpoints order by saved_on"#;
+    let rows: Vec<(String, String, String, String)> = sqlx::query_as(sql).fetch_all(ex.pool())
+        .await.map_err(|e| AppError::SqlxError(e, sql.to_string()))?;
+
+    Ok(rows.into_iter()
+        .map(|(name, tables, data_date, saved_on)| CheckpointInfo { name, tables, data_date, saved_on })
+        .collect())
+}
 
-    let size_sql = format!("SELECT pg_size_pretty(pg_total_relation_size('ad.{}'));", table);
-    let before: String = sqlx::query_scalar(&size_sql).fetch_one(pool)
-        .await.map_err(|e| AppError::SqlxError(e, size_sql.clone()))?;
-    
-    let vac_sql =format!("VACUUM (FULL, ANALYZE) ad.{};", table);
-    sqlx::raw_sql(&vac_sql).execute(pool)
-        .await.map_err(|e| AppError::SqlxError(e, vac_sql))?;
 
-    let after: String = sqlx::query_scalar(&size_sql).fetch_one(pool)
-        .await.map_err(|e| AppError::SqlxError(e, size_sql))?;
+pub async fn drop_checkpoint(name: &str, ex: &SqlExecutor) -> Result<(), AppError> {
 
-    info!("vacuum carried out on {} table, (size changing from {} to {})", table, before, after);
+    let schema = checkpoint_schema(name)?;
+    let sql = format!(r#"SET client_min_messages TO WARNING;
+        drop schema if exists {} cascade;
+        delete from ad.checkpoints where name = '{}';
+        SET client_min_messages TO NOTICE;"#, schema, name);
+    ex.execute(&sql).await?;
+    info!("checkpoint '{}' dropped", name);
 
     Ok(())
-}
\ No newline at end of file
+}
+
+
+async fn reset_identity_columns(table: &str, ex: &SqlExecutor) -> Result<(), AppError> {
+
+    if dry_run::is_active() {
+        return dry_run::note(&format!("identity columns of {} reset to the maximum values restored", table));
+    }
+
+    let sql = format!(r#"select column_name::varchar from information_schema.columns
+                 where table_schema = 'ad' and table_name = '{}' and is_identity = 'YES'"#, &table[3..]);
+    let columns = ex.fetch_column(&sql).await?;   // the table may have been recreated in the current transaction
+
+    for column in columns {
+        let sql = format!("select setval(pg_get_serial_sequence('{}', '{}'), max({})) from {};", table, column, column, table);
+        ex.execute(&sql).await?;
+    }
+
+    Ok(())
+}
+
+
+fn checkpoint_schema(name: &str) -> Result<String, AppError> {
+
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_') {
+        return Result::Err(AppError::CheckpointError(
+                    format!("Invalid checkpoint name: '{}'", name),
+                    "Checkpoint names can only include lower case letters, digits and underscores".to_string()));
+    }
+    Ok(format!("ckpt_{}", name))
+}
+
+
+fn check_ad_table(table: &str) -> Result<(), AppError> {
+
+    if !table.starts_with("ad.") {
+        return Result::Err(AppError::CheckpointError(
+                    format!("Table {} cannot be saved at a checkpoint", table),
+                    "Only tables in the ad schema can be saved at checkpoints".to_string()));
+    }
+    Ok(())
+}
+
+
+fn create_checkpoints_table_sql <'a>() -> &'a str {
+    r#"SET client_min_messages TO WARNING;
+    create table if not exists ad.checkpoints(
+        name                   VARCHAR         PRIMARY KEY
+      , tables                 VARCHAR         NOT NULL
+      , data_date              VARCHAR         NOT NULL
+      , saved_on               TIMESTAMPTZ     NOT NULL
+    );
+    SET client_min_messages TO NOTICE;"#
+}
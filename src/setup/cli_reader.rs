@@ -4,6 +4,14 @@
  
  pub struct CliPars {
     pub data_date: String,
+    pub stages: String,
+    pub exclude_stages: String,
+    pub checkpoints: String,
+    pub from_checkpoints: String,
+    pub prune_checkpoints: String,
+    pub dump_path: String,
+    pub transactions: String,
+    pub jobs: usize,
     pub flags: Flags, 
  }
  
@@ -15,6 +23,12 @@
     pub code_data: bool,
     pub transfer_to_who: bool,
     pub overwrite_ctg: bool,
+    pub with_deps: bool,
+    pub show_plan: bool,
+    pub resume: bool,
+    pub list_checkpoints: bool,
+    pub restore_aact: bool,
+    pub dry_run: bool,
     pub test_run: bool,
  }
  
@@ -26,6 +40,24 @@
   
     let data_date = parse_result.get_one::<String>("data_date").unwrap();
 
+    // Stage lists also default to "", i.e. no selection made on the command line.
+    // Their validation is done later, once any config file values are known.
+
+    let stages = parse_result.get_one::<String>("stages").unwrap();
+    let exclude_stages = parse_result.get_one::<String>("exclude_stages").unwrap();
+
+    // Checkpoint lists also default to "", and are validated once the stage plan is known.
+
+    let checkpoints = parse_result.get_one::<String>("checkpoints").unwrap();
+    let from_checkpoints = parse_result.get_one::<String>("from_checkpoints").unwrap();
+    let prune_checkpoints = parse_result.get_one::<String>("prune_checkpoints").unwrap();
+    let dump_path = parse_result.get_one::<String>("dump_path").unwrap();
+    let transactions = parse_result.get_one::<String>("transactions").unwrap();
+
+    // Jobs defaults to 0, i.e. not set on the command line.
+
+    let jobs = parse_result.get_one::<usize>("jobs").unwrap();
+
     // Flag values are false if not present, true if present.
  
     let a_flag = parse_result.get_flag("a_flag");
@@ -34,6 +66,12 @@
     let c_flag = parse_result.get_flag("c_flag");
     let t_flag = parse_result.get_flag("t_flag");
     let v_flag = parse_result.get_flag("v_flag");
+    let w_flag = parse_result.get_flag("w_flag");
+    let p_flag = parse_result.get_flag("p_flag");
+    let r_flag = parse_result.get_flag("r_flag");
+    let l_flag = parse_result.get_flag("l_flag");
+    let g_flag = parse_result.get_flag("g_flag");
+    let n_flag = parse_result.get_flag("n_flag");
     let z_flag = parse_result.get_flag("z_flag");
 
     if a_flag == true {
@@ -54,11 +92,25 @@
         code_data: c_flag,
         transfer_to_who: t_flag,
         overwrite_ctg: v_flag,
+        with_deps: w_flag,
+        show_plan: p_flag,
+        resume: r_flag,
+        list_checkpoints: l_flag,
+        restore_aact: g_flag,
+        dry_run: n_flag,
         test_run: z_flag,
     };
  
     Ok(CliPars {
         data_date: data_date.clone(),
+        stages: stages.clone(),
+        exclude_stages: exclude_stages.clone(),
+        checkpoints: checkpoints.clone(),
+        from_checkpoints: from_checkpoints.clone(),
+        prune_checkpoints: prune_checkpoints.clone(),
+        dump_path: dump_path.clone(),
+        transactions: transactions.clone(),
+        jobs: *jobs,
         flags: flags,
     })
  
@@ -77,6 +129,68 @@
            .help("A string with a date in ISO format that gives the date of the data")
            .default_value("")
          )
+         .arg(
+            Arg::new("stages")
+           .short('s')
+           .long("stages")
+           .required(false)
+           .help("A comma separated list of the mdr stages to run, e.g. 'titles,idents' - runs all stages if absent")
+           .default_value("")
+         )
+         .arg(
+            Arg::new("exclude_stages")
+           .short('x')
+           .long("exclude-stages")
+           .required(false)
+           .help("A comma separated list of mdr stages that should not be run")
+           .default_value("")
+         )
+         .arg(
+            Arg::new("checkpoints")
+           .short('k')
+           .long("checkpoint")
+           .required(false)
+           .help("A comma separated list of the checkpoints at which ad tables should be saved, or 'all'")
+           .default_value("")
+         )
+         .arg(
+            Arg::new("from_checkpoints")
+           .short('f')
+           .long("from-checkpoint")
+           .required(false)
+           .help("A comma separated list of checkpoints whose saved tables should be restored, instead of being processed")
+           .default_value("")
+         )
+         .arg(
+            Arg::new("prune_checkpoints")
+           .long("prune-checkpoints")
+           .required(false)
+           .help("A comma separated list of saved checkpoints to drop, or 'all' - nothing else is run")
+           .default_value("")
+         )
+         .arg(
+            Arg::new("dump_path")
+           .long("dump")
+           .required(false)
+           .help("The path to the AACT postgres.dmp file, or to the zip file containing it, used by the restore stage")
+           .default_value("")
+         )
+         .arg(
+            Arg::new("transactions")
+           .long("transactions")
+           .required(false)
+           .help("Whether each mdr stage is run in a transaction: none, stage (rolled back as a whole on failure), or step (with a savepoint per step)")
+           .default_value("")
+         )
+         .arg(
+            Arg::new("jobs")
+           .short('j')
+           .long("jobs")
+           .required(false)
+           .help("The number of independent mdr stages, and of chunks within a stage, that can be run at the same time")
+           .value_parser(clap::value_parser!(usize))
+           .default_value("0")
+         )
          .arg(
              Arg::new("a_flag")
             .short('a')
@@ -125,6 +239,54 @@
             .help("A flag indicating the data should overwrite the data in the CTG database")
             .action(clap::ArgAction::SetTrue)
          )
+         .arg(
+             Arg::new("w_flag")
+            .short('w')
+            .long("with-deps")
+            .required(false)
+            .help("A flag indicating that the prerequisites of the selected mdr stages should be run as well")
+            .action(clap::ArgAction::SetTrue)
+         )
+         .arg(
+             Arg::new("p_flag")
+            .short('p')
+            .long("plan")
+            .required(false)
+            .help("A flag indicating that the resolved mdr stage plan should be printed, without running it")
+            .action(clap::ArgAction::SetTrue)
+         )
+         .arg(
+             Arg::new("r_flag")
+            .short('r')
+            .long("resume")
+            .required(false)
+            .help("A flag indicating that mdr stages and steps already completed for the same data should be skipped")
+            .action(clap::ArgAction::SetTrue)
+         )
+         .arg(
+             Arg::new("l_flag")
+            .short('l')
+            .long("list-checkpoints")
+            .required(false)
+            .help("A flag indicating that the saved checkpoints should be listed, without running anything")
+            .action(clap::ArgAction::SetTrue)
+         )
+         .arg(
+             Arg::new("g_flag")
+            .short('g')
+            .long("restore")
+            .required(false)
+            .help("A flag indicating that the AACT snapshot should be restored (as the ctgov schema) before the mdr stages are run")
+            .action(clap::ArgAction::SetTrue)
+         )
+         .arg(
+             Arg::new("n_flag")
+            .short('n')
+            .long("dry-run")
+            .required(false)
+            .help("A flag indicating that the mdr sql should be written to a file in the log folder, rather than executed")
+            .action(clap::ArgAction::SetTrue)
+         )
         .arg(
              Arg::new("z_flag")
              .short('z')
@@ -214,6 +376,98 @@
          assert_eq!(res.flags.overwrite_ctg, true);
          assert_eq!(res.flags.test_run, false);
      }
+
+     #[test]
+     fn check_cli_with_stage_lists() {
+         let target = "dummy target";
+         let args : Vec<&str> = vec![target, "--stages", "titles,idents", "-x", "idents"];
+         let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+ 
+         let res = fetch_valid_arguments(test_args).unwrap();
+         assert_eq!(res.stages, "titles,idents");
+         assert_eq!(res.exclude_stages, "idents");
+         assert_eq!(res.flags.process_mdr_data, true);
+         assert_eq!(res.flags.with_deps, false);
+         assert_eq!(res.flags.show_plan, false);
+     }
+
+     #[test]
+     fn check_cli_with_plan_flags() {
+         let target = "dummy target";
+         let args : Vec<&str> = vec![target, "-s", "idents", "--with-deps", "-p"];
+         let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+ 
+         let res = fetch_valid_arguments(test_args).unwrap();
+         assert_eq!(res.stages, "idents");
+         assert_eq!(res.flags.with_deps, true);
+         assert_eq!(res.flags.show_plan, true);
+         assert_eq!(res.flags.resume, false);
+         assert_eq!(res.flags.process_mdr_data, true);
+     }
+
+     #[test]
+     fn check_cli_with_checkpoint_lists() {
+         let target = "dummy target";
+         let args : Vec<&str> = vec![target, "-s", "idents", "--checkpoint", "all", "-f", "idents_base", "--prune-checkpoints", "locs_base"];
+         let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+ 
+         let res = fetch_valid_arguments(test_args).unwrap();
+         assert_eq!(res.checkpoints, "all");
+         assert_eq!(res.from_checkpoints, "idents_base");
+         assert_eq!(res.prune_checkpoints, "locs_base");
+         assert_eq!(res.flags.list_checkpoints, false);
+     }
+
+     #[test]
+     fn check_cli_with_resume_flag() {
+         let target = "dummy target";
+         let args : Vec<&str> = vec![target, "-d", "2025-01-15", "--resume"];
+         let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+ 
+         let res = fetch_valid_arguments(test_args).unwrap();
+         assert_eq!(res.data_date, "2025-01-15");
+         assert_eq!(res.flags.resume, true);
+         assert_eq!(res.flags.process_mdr_data, true);
+         assert_eq!(res.flags.show_plan, false);
+     }
+
+     #[test]
+     fn check_cli_with_dry_run_flag() {
+         let target = "dummy target";
+         let args : Vec<&str> = vec![target, "-n", "-s", "studies"];
+         let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+
+         let res = fetch_valid_arguments(test_args).unwrap();
+         assert_eq!(res.stages, "studies");
+         assert_eq!(res.flags.dry_run, true);
+         assert_eq!(res.flags.process_mdr_data, true);
+         assert_eq!(res.flags.resume, false);
+     }
+
+     #[test]
+     fn check_cli_with_jobs() {
+         let target = "dummy target";
+         let args : Vec<&str> = vec![target, "-m", "--jobs", "4"];
+         let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+
+         let res = fetch_valid_arguments(test_args).unwrap();
+         assert_eq!(res.jobs, 4);
+
+         let args : Vec<&str> = vec![target, "-m", "-j", "four"];
+         let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+         assert!(fetch_valid_arguments(test_args).is_err());
+     }
+
+     #[test]
+     fn check_cli_stage_lists_default_to_empty() {
+         let target = "dummy target";
+         let args : Vec<&str> = vec![target, "-m"];
+         let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+ 
+         let res = fetch_valid_arguments(test_args).unwrap();
+         assert_eq!(res.stages, "");
+         assert_eq!(res.exclude_stages, "");
+     }
  
  }
  
@@ -13,6 +13,8 @@
     pub data: Option<TomlDataPars>, 
     pub folders: Option<TomlFolderPars>, 
     pub database: Option<TomlDBPars>,
+    pub mdr: Option<TomlMdrPars>,
+    pub restore: Option<TomlRestorePars>,
  }
 
  
@@ -20,6 +22,20 @@
 pub struct TomlDataPars {
    pub data_date: Option<String>,
 }
+
+#[derive(Debug, Deserialize)]
+pub struct TomlMdrPars {
+   pub stages: Option<String>,
+   pub exclude_stages: Option<String>,
+   pub transactions: Option<String>,
+   pub jobs: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TomlRestorePars {
+   pub dump_path: Option<String>,
+   pub previous_ctgov: Option<String>,
+}
  
  #[derive(Debug, Deserialize)]
  pub struct TomlFolderPars {
@@ -42,12 +58,27 @@ pub struct TomlDataPars {
     pub data_details: DataPars, 
     pub folders: FolderPars, 
     pub db_pars: DBPars,
+    pub mdr_pars: MdrPars,
+    pub restore_pars: RestorePars,
  }
  
  pub struct DataPars {
     pub data_date: String,
 }
 
+ pub struct MdrPars {
+    pub stages: String,
+    pub exclude_stages: String,
+    pub transactions: String,
+    pub jobs: usize,
+}
+
+ #[derive(Debug, Clone)]
+ pub struct RestorePars {
+    pub dump_path: String,
+    pub previous_ctgov: String,
+}
+
  pub struct FolderPars {
     pub log_folder_path: PathBuf,
  }
@@ -83,6 +114,29 @@ pub struct TomlDataPars {
         },
     };
            
+    // The mdr section is optional - without it all mdr stages are run.
+
+    let toml_mdr = match toml_config.mdr {
+        Some(m) => m,
+        None => TomlMdrPars {
+            stages: None,
+            exclude_stages: None,
+            transactions: None,
+            jobs: None,
+        },
+    };
+
+    // The restore section is also optional, and only needed if the AACT
+    // snapshot is to be restored by the program.
+
+    let toml_restore = match toml_config.restore {
+        Some(r) => r,
+        None => TomlRestorePars {
+            dump_path: None,
+            previous_ctgov: None,
+        },
+    };
+           
     let toml_database = match toml_config.database {
          Some(d) => d,
          None => {return Result::Err(AppError::ConfigurationError("Missing or misspelt configuration section.".to_string(),
@@ -98,6 +152,8 @@ pub struct TomlDataPars {
     let config_data_dets = verify_data_parameters(toml_data_details)?;
     let config_folders = verify_folder_parameters(toml_folders)?;
     let config_db_pars = verify_db_parameters(toml_database)?;
+    let config_mdr_pars = verify_mdr_parameters(toml_mdr)?;
+    let config_restore_pars = verify_restore_parameters(toml_restore)?;
  
     let _ = DB_PARS.set(config_db_pars.clone());
  
@@ -105,6 +161,8 @@ pub struct TomlDataPars {
          data_details: config_data_dets,
          folders: config_folders,
          db_pars: config_db_pars,
+         mdr_pars: config_mdr_pars,
+         restore_pars: config_restore_pars,
      })
  }
  
@@ -120,7 +178,66 @@ pub struct TomlDataPars {
         data_date,
     })
 }
+
+
+fn verify_mdr_parameters(toml_mdr_pars: TomlMdrPars) -> Result<MdrPars, AppError> {
+
+    // Stage names are checked against the mdr stage list only once 
+    // any command line values have also been taken into account.
+
+    let stages = match toml_mdr_pars.stages {
+        Some(s) => s.trim().to_string(),
+        None => "".to_string(),
+    };
+
+    let exclude_stages = match toml_mdr_pars.exclude_stages {
+        Some(s) => s.trim().to_string(),
+        None => "".to_string(),
+    };
+
+    let transactions = match toml_mdr_pars.transactions {
+        Some(s) => s.trim().to_string(),
+        None => "".to_string(),
+    };
+        
+    let jobs = toml_mdr_pars.jobs.unwrap_or(0);
+        
+    Ok(MdrPars {
+        stages,
+        exclude_stages,
+        transactions,
+        jobs,
+    })
+}
  
+
+fn verify_restore_parameters(toml_restore_pars: TomlRestorePars) -> Result<RestorePars, AppError> {
+
+    // The dump path is to the unzipped postgres.dmp file, or to the zip file itself.
+    // Whether it is needed is only known once the mdr stages have been selected.
+    // The existing ctgov schema is dropped before the restore, unless it is renamed.
+
+    let dump_path = match toml_restore_pars.dump_path {
+        Some(s) => s.trim().to_string(),
+        None => "".to_string(),
+    };
+
+    let previous_ctgov = match toml_restore_pars.previous_ctgov {
+        Some(s) => s.trim().to_lowercase(),
+        None => "drop".to_string(),
+    };
+
+    if previous_ctgov != "drop" && previous_ctgov != "rename" {
+        return Result::Err(AppError::ConfigurationError("Invalid restore configuration value.".to_string(),
+            format!("previous_ctgov must be 'drop' or 'rename', not '{}'.", previous_ctgov)));
+    }
+        
+    Ok(RestorePars {
+        dump_path,
+        previous_ctgov,
+    })
+}
+
  fn verify_folder_parameters(toml_folders: TomlFolderPars) -> Result<FolderPars, AppError> {
  
      let log_folder_string = check_essential_string (toml_folders.log_folder_path, "log folder", "log_folder_path")?;
@@ -213,7 +330,15 @@ pub struct TomlDataPars {
  }
  
  
- pub fn fetch_db_conn_string(db_name: &String) -> Result<String, AppError> {
+ pub fn fetch_db_pars() -> Result<DBPars, AppError> {
+     match DB_PARS.get() {
+          Some(dbp) => Ok(dbp.clone()),
+          None => Result::Err(AppError::MissingDBParameters()),
+     }
+ }
+ 
+ 
+ pub fn fetch_db_conn_string(db_name: &str) -> Result<String, AppError> {
      let db_pars = match DB_PARS.get() {
           Some(dbp) => dbp,
           None => {
@@ -266,6 +391,63 @@ pub struct TomlDataPars {
          assert_eq!(res.db_pars.who_db_name, "who");
          assert_eq!(res.db_pars.cxt_db_name, "cxt");
          assert_eq!(res.db_pars.cgt_db_name, "cgt");
+         assert_eq!(res.mdr_pars.stages, "");
+         assert_eq!(res.mdr_pars.exclude_stages, "");
+         assert_eq!(res.restore_pars.dump_path, "");
+         assert_eq!(res.restore_pars.previous_ctgov, "drop");
+    }
+
+
+     #[test]
+     fn check_config_with_mdr_section() {
+ 
+         let config = r#"
+ [data]
+ data_date="2025-06-25"
+
+ [folders]
+ log_folder_path="/home/steve/Data/MDR logs/aact/"
+ 
+ [database]
+ db_user="user_name"
+ db_password="password"
+
+ [mdr]
+ stages=" titles, idents "
+ exclude_stages="idents"
+ "#;
+         let config_string = config.to_string();
+         let res = populate_config_vars(&config_string).unwrap();
+         assert_eq!(res.mdr_pars.stages, "titles, idents");
+         assert_eq!(res.mdr_pars.exclude_stages, "idents");
+    }
+
+
+     #[test]
+     fn check_config_with_restore_section() {
+ 
+         let config = r#"
+ [data]
+ data_date="2025-06-25"
+
+ [folders]
+ log_folder_path="/home/steve/Data/MDR logs/aact/"
+ 
+ [database]
+ db_user="user_name"
+ db_password="password"
+
+ [restore]
+ dump_path="/home/steve/Data/MDR source data/CTGDumps/20250318/postgres.dmp"
+ previous_ctgov="Rename"
+ "#;
+         let config_string = config.to_string();
+         let res = populate_config_vars(&config_string).unwrap();
+         assert_eq!(res.restore_pars.dump_path, "/home/steve/Data/MDR source data/CTGDumps/20250318/postgres.dmp");
+         assert_eq!(res.restore_pars.previous_ctgov, "rename");
+
+         let bad_config_string = config_string.replace("Rename", "archive");
+         assert!(populate_config_vars(&bad_config_string).is_err());
     }
  
      
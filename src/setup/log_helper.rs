@@ -73,6 +73,25 @@
      info!("");
      info!("log_folder: {}", ip.log_folder.display());
      info!("import mdr data: {}", ip.flags.process_mdr_data);
+     if ip.flags.process_mdr_data {
+         info!("mdr stages: {}", ip.mdr_plan.stage_names().join(", "));
+         if !ip.mdr_plan.assumed.is_empty() {
+             info!("mdr stages assumed already run: {}", ip.mdr_plan.assumed_names().join(", "));
+         }
+         if ip.mdr_plan.stage_names().contains(&"restore") {
+             info!("AACT snapshot to restore: {}", ip.restore_pars.dump_path);
+         }
+         if !ip.mdr_plan.save_points.is_empty() {
+             info!("checkpoints to save: {}", ip.mdr_plan.save_points.join(", "));
+         }
+         if !ip.mdr_plan.restore_points.is_empty() {
+             info!("checkpoints to restore: {}", ip.mdr_plan.restore_points.join(", "));
+         }
+     }
+     info!("resume earlier run: {}", ip.flags.resume);
+     info!("transactions: {}", ip.transaction_mode.name());
+     info!("jobs: {}", ip.jobs);
+     info!("dry run (sql written, not executed): {}", ip.flags.dry_run);
      info!("import iec data: {}", ip.flags.process_iec_data);
      info!("encode data: {}", ip.flags.code_data);
      info!("transfer to who DB: {}", ip.flags.transfer_to_who);
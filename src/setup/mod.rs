@@ -3,19 +3,26 @@ pub mod log_helper;
 pub mod cli_reader;
 
 use crate::err::AppError;
+use crate::mdr::stages::{self, StagePlan};
+use crate::mdr::executor::TransactionMode;
 use sqlx::postgres::{PgPoolOptions, PgConnectOptions, PgPool};
 use std::path::PathBuf;
 use cli_reader::{CliPars, Flags};
 use std::fs;
 use std::time::Duration;
 use sqlx::ConnectOptions;
-use config_reader::Config;
+use config_reader::{Config, RestorePars};
 use std::sync::OnceLock;
 use chrono::NaiveDate;
 
 pub struct InitParams {
     pub data_date: String,
     pub log_folder: PathBuf,
+    pub mdr_plan: StagePlan,
+    pub prune_checkpoints: String,
+    pub restore_pars: RestorePars,
+    pub transaction_mode: TransactionMode,
+    pub jobs: usize,
     pub flags: Flags,
 }
 
@@ -50,6 +57,75 @@ pub fn get_params(cli_pars: CliPars, config_string: &String) -> Result<InitParam
     }
 
 
+    // The mdr stages to run, again with any CL values taking precedence over
+    // those in the config file. Names are checked against the stage registry,
+    // and the selection resolved into a plan that takes stage prerequisites 
+    // into account. Stages named in the CL are not excluded by the config file,
+    // and it is an error if the named stages are all excluded in the CL.
+
+    let cli_stages_given = !cli_pars.stages.is_empty();
+    let mut stages = cli_pars.stages;
+    if stages.is_empty() {
+        stages = config_file.mdr_pars.stages;
+    }
+
+    let mut exclude_stages = cli_pars.exclude_stages;
+    if exclude_stages.is_empty() && !cli_stages_given {
+        exclude_stages = config_file.mdr_pars.exclude_stages;
+    }
+
+    let mut mdr_plan = stages::select_stages(&stages, &exclude_stages, cli_pars.flags.with_deps)?;
+    if !stages.trim().is_empty() && mdr_plan.stages.is_empty() {
+        return Result::Err(AppError::StageSelectionError(
+            format!("All of the selected mdr stage(s) are excluded: {}", stages),
+            "Remove the stage(s) from the exclude list".to_string()));
+    }
+
+    // The restore stage is opt-in, and can also be added by a CL flag. It needs 
+    // the path to the AACT snapshot, with any CL value again taking precedence.
+
+    if cli_pars.flags.restore_aact {
+        stages::add_stage(&mut mdr_plan, "restore");
+    }
+
+    let mut restore_pars = config_file.restore_pars;
+    if !cli_pars.dump_path.is_empty() {
+        restore_pars.dump_path = cli_pars.dump_path;
+    }
+
+    if mdr_plan.stage_names().contains(&"restore") && restore_pars.dump_path.is_empty() {
+        return Result::Err(AppError::MissingProgramParameter("dump_path".to_string()));
+    }
+
+    // Whether each stage is run in a transaction, again with any CL value taking precedence.
+
+    let mut transactions = cli_pars.transactions;
+    if transactions.is_empty() {
+        transactions = config_file.mdr_pars.transactions;
+    }
+    let transaction_mode = match TransactionMode::from_name(&transactions) {
+        Some(m) => m,
+        None => return Result::Err(AppError::ConfigurationError(
+                    format!("Invalid transactions value: '{}'", transactions),
+                    "The value must be none, stage or step".to_string())),
+    };
+
+    // The number of stages, and chunks within a stage, that can be run at the same time,
+    // again with any CL value taking precedence. If not given at all, everything is run
+    // one step after another, as before.
+
+    let mut jobs = cli_pars.jobs;
+    if jobs == 0 {
+        jobs = config_file.mdr_pars.jobs;
+    }
+    if jobs == 0 {
+        jobs = 1;
+    }
+
+    // Any checkpoints to save or restore must be within the stages being run.
+
+    stages::select_checkpoints(&mut mdr_plan, &cli_pars.checkpoints, &cli_pars.from_checkpoints)?;
+
     let log_folder = config_file.folders.log_folder_path;  
     if !folder_exists (&log_folder) { 
         fs::create_dir_all(&log_folder)?;
@@ -60,6 +136,11 @@ pub fn get_params(cli_pars: CliPars, config_string: &String) -> Result<InitParam
     Ok(InitParams {
         data_date,
         log_folder,
+        mdr_plan,
+        prune_checkpoints: cli_pars.prune_checkpoints,
+        restore_pars,
+        transaction_mode,
+        jobs,
         flags: cli_pars.flags,
     })
 
@@ -78,7 +159,7 @@ fn folder_exists(folder_name: &PathBuf) -> bool {
         
 
 
-pub async fn get_db_pool() -> Result<PgPool, AppError> {  
+pub async fn get_db_pool(jobs: usize) -> Result<PgPool, AppError> {  
 
     // Establish DB name and thence the connection string
     // (done as two separate steps to allow for future development).
@@ -86,16 +167,33 @@ pub async fn get_db_pool() -> Result<PgPool, AppError> {
     // the time threshold for warnings. Set up a DB pool option and 
     // connect using the connection options object.
 
+    // Each of the jobs may hold a connection for a stage's transaction, while 
+    // as many again run statements on the pool, with a couple more for the run log.
+
     let db_name = match config_reader::fetch_db_name() {
         Ok(n) => n,
         Err(e) => return Err(e),
     };
 
-    let db_conn_string = config_reader::fetch_db_conn_string(&db_name)?;  
-   
-    let mut opts: PgConnectOptions = db_conn_string.parse()
-                    .map_err(|e| AppError::DBPoolError("Problem with parsing conection string".to_string(), e))?;
-    opts = opts.log_slow_statements(log::LevelFilter::Warn, Duration::from_secs(5));
+    let opts = get_connect_options(&db_name)?;
+
+    PgPoolOptions::new()
+        .max_connections(pool_size(jobs)) 
+        .connect_with(opts).await
+        .map_err(|e| AppError::DBPoolError(format!("Problem with connecting to database {} and obtaining Pool", db_name), e))
+}
+
+
+fn pool_size(jobs: usize) -> u32 {
+    (2 * jobs as u32 + 2).max(5)
+}
+
+
+pub async fn get_named_db_pool(db_name: &str) -> Result<PgPool, AppError> {  
+
+    // Used directly for databases other than the main (aact) database.
+
+    let opts = get_connect_options(db_name)?;
 
     PgPoolOptions::new()
         .max_connections(5) 
@@ -104,6 +202,30 @@ pub async fn get_db_pool() -> Result<PgPool, AppError> {
 }
 
 
+pub fn get_lazy_db_pool() -> Result<PgPool, AppError> {  
+
+    // Used for dry runs, when no connection should be made to the database,
+    // unless and until a statement is actually executed.
+
+    let db_name = config_reader::fetch_db_name()?;
+    let opts = get_connect_options(&db_name)?;
+
+    Ok(PgPoolOptions::new()
+        .max_connections(5) 
+        .connect_lazy_with(opts))
+}
+
+
+fn get_connect_options(db_name: &str) -> Result<PgConnectOptions, AppError> {  
+
+    let db_conn_string = config_reader::fetch_db_conn_string(db_name)?;  
+   
+    let opts: PgConnectOptions = db_conn_string.parse()
+                    .map_err(|e| AppError::DBPoolError("Problem with parsing conection string".to_string(), e))?;
+    Ok(opts.log_slow_statements(log::LevelFilter::Warn, Duration::from_secs(5)))
+}
+
+
 pub fn establish_log(params: &InitParams) -> Result<(), AppError> {
 
     if !log_running() {  // can be called more than once in context of integration tests
@@ -330,6 +452,205 @@ mod tests {
         assert_eq!(res.flags.test_run, false);
         
     }
+
+
+    #[test]
+    fn check_all_stages_selected_by_default() {
+    let config = r#"
+ [data]
+ data_date="2025-06-25"
+
+ [folders]
+ log_folder_path="/home/steve/Data/MDR logs/aact/"
+ 
+ [database]
+ db_user="user_name"
+ db_password="password"
+
+ "#;
+
+        let config_string = config.to_string();
+        let args : Vec<&str> = vec!["dummy target"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let cli_pars = cli_reader::fetch_valid_arguments(test_args).unwrap();
+
+        let res = get_params(cli_pars, &config_string).unwrap();
+        assert_eq!(res.mdr_plan.stages.len(), stages::STAGES.len() - stages::OPT_IN_STAGES.len());
+        assert_eq!(res.mdr_plan.stages[0].name, "simplify");
+    }
+
+
+    #[test]
+    fn check_restore_flag_needs_dump_path() {
+    let config = r#"
+ [data]
+ data_date="2025-06-25"
+
+ [folders]
+ log_folder_path="/home/steve/Data/MDR logs/aact/"
+ 
+ [database]
+ db_user="user_name"
+ db_password="password"
+
+ "#;
+
+        let config_string = config.to_string();
+        let args : Vec<&str> = vec!["dummy target", "--restore"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let cli_pars = cli_reader::fetch_valid_arguments(test_args).unwrap();
+        assert!(get_params(cli_pars, &config_string).is_err());
+
+        let args : Vec<&str> = vec!["dummy target", "--restore", "--dump", "/home/steve/Data/20250318.zip"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let cli_pars = cli_reader::fetch_valid_arguments(test_args).unwrap();
+        let res = get_params(cli_pars, &config_string).unwrap();
+        assert_eq!(res.mdr_plan.stages[0].name, "restore");
+        assert_eq!(res.restore_pars.dump_path, "/home/steve/Data/20250318.zip");
+    }
+
+
+    #[test]
+    fn check_transaction_mode_from_config_and_cli() {
+    let config = r#"
+ [data]
+ data_date="2025-06-25"
+
+ [folders]
+ log_folder_path="/home/steve/Data/MDR logs/aact/"
+
+ [database]
+ db_user="user_name"
+ db_password="password"
+
+ [mdr]
+ transactions="stage"
+
+ "#;
+
+        let config_string = config.to_string();
+        let args : Vec<&str> = vec!["dummy target"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let cli_pars = cli_reader::fetch_valid_arguments(test_args).unwrap();
+        let res = get_params(cli_pars, &config_string).unwrap();
+        assert_eq!(res.transaction_mode, TransactionMode::Stage);
+
+        let args : Vec<&str> = vec!["dummy target", "--transactions", "step"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let cli_pars = cli_reader::fetch_valid_arguments(test_args).unwrap();
+        let res = get_params(cli_pars, &config_string).unwrap();
+        assert_eq!(res.transaction_mode, TransactionMode::Step);
+
+        let args : Vec<&str> = vec!["dummy target", "--transactions", "all"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let cli_pars = cli_reader::fetch_valid_arguments(test_args).unwrap();
+        assert!(get_params(cli_pars, &config_string).is_err());
+    }
+
+
+    #[test]
+    fn check_jobs_from_config_and_cli() {
+    let config = r#"
+ [data]
+ data_date="2025-06-25"
+
+ [folders]
+ log_folder_path="/home/steve/Data/MDR logs/aact/"
+
+ [database]
+ db_user="user_name"
+ db_password="password"
+
+ [mdr]
+ jobs=3
+
+ "#;
+
+        let config_string = config.to_string();
+        let args : Vec<&str> = vec!["dummy target"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let cli_pars = cli_reader::fetch_valid_arguments(test_args).unwrap();
+        let res = get_params(cli_pars, &config_string).unwrap();
+        assert_eq!(res.jobs, 3);
+
+        let args : Vec<&str> = vec!["dummy target", "--jobs", "6"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let cli_pars = cli_reader::fetch_valid_arguments(test_args).unwrap();
+        let res = get_params(cli_pars, &config_string).unwrap();
+        assert_eq!(res.jobs, 6);
+        assert_eq!(pool_size(res.jobs), 14);
+        assert_eq!(pool_size(1), 5);
+
+        let config_string = config.replace("jobs=3", "");
+        let args : Vec<&str> = vec!["dummy target"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let cli_pars = cli_reader::fetch_valid_arguments(test_args).unwrap();
+        let res = get_params(cli_pars, &config_string).unwrap();
+        assert_eq!(res.jobs, 1);
+    }
+
+
+    #[test]
+    fn check_cli_stages_override_config_stages() {
+    let config = r#"
+ [data]
+ data_date="2025-06-25"
+
+ [folders]
+ log_folder_path="/home/steve/Data/MDR logs/aact/"
+ 
+ [database]
+ db_user="user_name"
+ db_password="password"
+
+ [mdr]
+ stages="locations"
+ exclude_stages="titles"
+
+ "#;
+
+        let config_string = config.to_string();
+        let args : Vec<&str> = vec!["dummy target", "-s", "idents, Titles ,studies"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let cli_pars = cli_reader::fetch_valid_arguments(test_args).unwrap();
+
+        let res = get_params(cli_pars, &config_string).unwrap();
+        assert_eq!(res.mdr_plan.stage_names(), vec!["studies", "titles", "idents"]);
+        assert!(res.mdr_plan.assumed.is_empty());
+    }
+
+
+    #[test]
+    fn check_config_exclusions_ignored_for_cli_stages() {
+    let config = r#"
+ [data]
+ data_date="2025-06-25"
+
+ [folders]
+ log_folder_path="/home/steve/Data/MDR logs/aact/"
+ 
+ [database]
+ db_user="user_name"
+ db_password="password"
+
+ [mdr]
+ exclude_stages="titles"
+
+ "#;
+
+        let config_string = config.to_string();
+        let args : Vec<&str> = vec!["dummy target", "-s", "titles"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let cli_pars = cli_reader::fetch_valid_arguments(test_args).unwrap();
+
+        let res = get_params(cli_pars, &config_string).unwrap();
+        assert_eq!(res.mdr_plan.stage_names(), vec!["titles"]);
+
+        let args : Vec<&str> = vec!["dummy target", "-s", "titles", "-x", "titles"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let cli_pars = cli_reader::fetch_valid_arguments(test_args).unwrap();
+        assert!(get_params(cli_pars, &config_string).is_err());
+    }
     
 }
 
@@ -1,9 +1,158 @@
 use crate::err::AppError;
+use crate::setup::{config_reader, get_named_db_pool};
 use sqlx::postgres::PgPool;
+use log::info;
 
-pub async fn do_who_transfer(_pool: &PgPool) -> Result<(), AppError> {  
+// A summary of each CTG study is written to the study summaries table of the who database
+// (who_db_name in the configuration file), so that CTG can be summarised along with the
+// other registries. The previous CTG rows are replaced in a single transaction, so that the
+// table always holds a complete set of CTG summaries.
+
+// The source id of CTG, and the identifier types of the trial registries (from the mdr lookup
+// tables). The NCT id itself (120) and obsolete NCT aliases (180) are not secondary ids.
+
+const CTG_SOURCE_ID: i32 = 100120;
+const REGISTRY_ID_TYPES: &str = "(i.id_type_id between 115 and 160 and i.id_type_id <> 120) or i.id_type_id in (181, 182)";
+
+// The number of studies read from the aact database and written to the who database at a time.
+
+const SUMMARY_BATCH: i64 = 50000;
+
+
+#[derive(sqlx::FromRow)]
+struct StudySummary {
+    id: i32,
+    sd_sid: String,
+    title: Option<String>,
+    reg_year: Option<i32>,
+    reg_month: Option<i32>,
+    start_year: Option<i32>,
+    start_month: Option<i32>,
+    comp_year: Option<i32>,
+    comp_month: Option<i32>,
+    status_id: i32,
+    type_id: i32,
+    countries: Option<String>,
+    conditions: Option<String>,
+    secondary_ids: Option<String>,
+}
+
+
+pub async fn do_who_transfer(pool: &PgPool) -> Result<(), AppError> {
+
+    let who_db_name = config_reader::fetch_db_pars()?.who_db_name;
+    let who_pool = get_named_db_pool(&who_db_name).await?;
+    sqlx::raw_sql(summaries_table_sql()).execute(&who_pool).await
+        .map_err(|e| AppError::SqlxError(e, summaries_table_sql().to_string()))?;
+
+    let mut t = who_pool.begin().await
+                .map_err(|e| AppError::SqlxError(e, "begin".to_string()))?;
+
+    let sql = "delete from who.study_summaries where source_id = $1";
+    let r = sqlx::query(sql).bind(CTG_SOURCE_ID).execute(&mut *t).await
+                .map_err(|e| AppError::SqlxError(e, sql.to_string()))?.rows_affected();
+    info!("{} previous CTG study summaries removed from the who database {}", r, who_db_name);
+
+    // The studies are read in batches, in id order, each batch being added to the
+    // (uncommitted) transaction in the who database.
+
+    let fetch_sql = fetch_summaries_sql();
+    let mut last_id = 0;
+    let mut total = 0;
+    loop {
+        let batch: Vec<StudySummary> = sqlx::query_as(&fetch_sql).bind(last_id).bind(SUMMARY_BATCH)
+                .fetch_all(pool).await
+                .map_err(|e| AppError::SqlxError(e, fetch_sql.clone()))?;
+        let Some(last) = batch.last() else {
+            break;
+        };
+        last_id = last.id;
+
+        let sql = insert_summaries_sql();
+        let r = sqlx::query(sql)
+            .bind(CTG_SOURCE_ID)
+            .bind(batch.iter().map(|s| s.sd_sid.clone()).collect::<Vec<String>>())
+            .bind(batch.iter().map(|s| s.title.clone()).collect::<Vec<Option<String>>>())
+            .bind(batch.iter().map(|s| s.reg_year).collect::<Vec<Option<i32>>>())
+            .bind(batch.iter().map(|s| s.reg_month).collect::<Vec<Option<i32>>>())
+            .bind(batch.iter().map(|s| s.start_year).collect::<Vec<Option<i32>>>())
+            .bind(batch.iter().map(|s| s.start_month).collect::<Vec<Option<i32>>>())
+            .bind(batch.iter().map(|s| s.comp_year).collect::<Vec<Option<i32>>>())
+            .bind(batch.iter().map(|s| s.comp_month).collect::<Vec<Option<i32>>>())
+            .bind(batch.iter().map(|s| s.status_id).collect::<Vec<i32>>())
+            .bind(batch.iter().map(|s| s.type_id).collect::<Vec<i32>>())
+            .bind(batch.iter().map(|s| s.countries.clone()).collect::<Vec<Option<String>>>())
+            .bind(batch.iter().map(|s| s.conditions.clone()).collect::<Vec<Option<String>>>())
+            .bind(batch.iter().map(|s| s.secondary_ids.clone()).collect::<Vec<Option<String>>>())
+            .execute(&mut *t).await
+            .map_err(|e| AppError::SqlxError(e, sql.to_string()))?.rows_affected();
+        total += r;
+        info!("{} CTG study summaries transferred", total);
+    }
+
+    t.commit().await.map_err(|e| AppError::SqlxError(e, "commit".to_string()))?;
+    info!("{} CTG study summaries now in the who database {}", total, who_db_name);
+    info!("");
 
-    // TO DO! 
     Ok(())
+}
+
+
+fn summaries_table_sql <'a>() -> &'a str {
+
+    // The table is shared with the other registries, so is only created if not already present.
+
+    r#"SET client_min_messages TO WARNING;
+    CREATE SCHEMA IF NOT EXISTS who;
+    CREATE TABLE IF NOT EXISTS who.study_summaries(
+      id                     INT             PRIMARY KEY GENERATED ALWAYS AS IDENTITY
+    , source_id              INT             NOT NULL
+    , sd_sid                 VARCHAR         NOT NULL
+    , title                  VARCHAR         NULL
+    , reg_year               INT             NULL
+    , reg_month              INT             NULL
+    , start_year             INT             NULL
+    , start_month            INT             NULL
+    , comp_year              INT             NULL
+    , comp_month             INT             NULL
+    , status_id              INT             NULL
+    , type_id                INT             NULL
+    , countries              VARCHAR         NULL
+    , conditions             VARCHAR         NULL
+    , secondary_ids          VARCHAR         NULL
+    , added_on               TIMESTAMPTZ     NOT NULL default now()
+    );
+    CREATE INDEX IF NOT EXISTS study_summaries_source_sid ON who.study_summaries(source_id, sd_sid);"#
+}
+
+
+fn fetch_summaries_sql () -> String {
+
+    // Countries, conditions and secondary ids are each listed in a single string,
+    // separated by semi-colons.
+
+    format!(r#"select s.id, s.sd_sid, s.display_title as title,
+    s.reg_year, s.reg_month, s.start_year, s.start_month, s.comp_year, s.comp_month,
+    s.status_id, s.type_id,
+    (select string_agg(distinct c.country_name, '; ' order by c.country_name)
+     from ad.study_countries c where c.sd_sid = s.sd_sid) as countries,
+    (select string_agg(distinct x.original_value, '; ' order by x.original_value)
+     from ad.study_conditions x where x.sd_sid = s.sd_sid) as conditions,
+    (select string_agg(distinct i.id_value, '; ' order by i.id_value)
+     from ad.study_identifiers i where i.sd_sid = s.sd_sid
+     and ({})) as secondary_ids
+    from ad.studies s
+    where s.id > $1
+    order by s.id
+    limit $2"#, REGISTRY_ID_TYPES)
+}
+
 
-}
\ No newline at end of file
+fn insert_summaries_sql <'a>() -> &'a str {
+    r#"insert into who.study_summaries (source_id, sd_sid, title,
+    reg_year, reg_month, start_year, start_month, comp_year, comp_month,
+    status_id, type_id, countries, conditions, secondary_ids)
+    select $1, * from unnest($2::varchar[], $3::varchar[],
+    $4::int[], $5::int[], $6::int[], $7::int[], $8::int[], $9::int[],
+    $10::int[], $11::int[], $12::varchar[], $13::varchar[], $14::varchar[])"#
+}